@@ -21,6 +21,7 @@
 #![allow(deprecated)]
 
 use client;
+use keystore;
 use error_chain::{error_chain, error_chain_processing, impl_error_chain_processed,
 	impl_extract_backtrace, impl_error_chain_kind};
 
@@ -32,6 +33,7 @@ error_chain! {
 	}
 	links {
 		Client(client::error::Error, client::error::ErrorKind) #[doc="Client error"];
+		Keystore(keystore::Error, keystore::ErrorKind) #[doc="Keystore error"];
 	}
 	errors {
 		/// Input error.
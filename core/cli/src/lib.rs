@@ -36,7 +36,8 @@ use network::{
 	config::{NetworkConfiguration, NonReservedPeerMode, NodeKeyConfig},
 	build_multiaddr,
 };
-use primitives::H256;
+use primitives::{H256, ed25519, crypto::Ss58Codec};
+use keystore::KeystoreBackend;
 
 use std::{
 	io::{Write, Read, stdin, stdout, ErrorKind}, iter, fs::{self, File}, net::{Ipv4Addr, SocketAddr},
@@ -49,11 +50,12 @@ use structopt::{StructOpt, clap::AppSettings};
 #[doc(hidden)]
 pub use structopt::clap::App;
 use params::{
-	RunCmd, PurgeChainCmd, RevertCmd, ImportBlocksCmd, ExportBlocksCmd, BuildSpecCmd,
+	RunCmd, PurgeChainCmd, RevertCmd, ImportBlocksCmd, ExportBlocksCmd, ImportStateCmd, ExportStateCmd,
+	BuildSpecCmd, KeysCmd, KeysAction,
 	NetworkConfigurationParams, SharedParams, MergeParameters, TransactionPoolParams,
 	NodeKeyParams, NodeKeyType
 };
-pub use params::{NoCustom, CoreParams};
+pub use params::{NoCustom, CoreParams, SharedParams};
 pub use traits::{GetLogFilter, AugmentClap};
 use app_dirs::{AppInfo, AppDataType};
 use error_chain::bail;
@@ -76,6 +78,14 @@ const NODE_KEY_SECP256K1_FILE: &str = "secret";
 /// is specified in combination with `--node-key-type=ed25519`.
 const NODE_KEY_ED25519_FILE: &str = "secret_ed25519";
 
+/// The browser origins allowed to access the RPC servers if `--rpc-cors` is not specified.
+const DEFAULT_RPC_CORS: &[&str] = &[
+	"http://localhost:*",
+	"http://127.0.0.1:*",
+	"https://localhost:*",
+	"https://127.0.0.1:*",
+];
+
 /// Executable version. Used to pass version information from the root crate.
 pub struct VersionInfo {
 	/// Implemtation name.
@@ -240,6 +250,12 @@ where
 			purge_chain::<F, _>(params, spec_factory, version).map(|_| None),
 		params::CoreParams::Revert(params) =>
 			revert_chain::<F, _>(params, spec_factory, version).map(|_| None),
+		params::CoreParams::ExportState(params) =>
+			export_state::<F, _>(params, spec_factory, version).map(|_| None),
+		params::CoreParams::ImportState(params) =>
+			import_state::<F, _>(params, spec_factory, version).map(|_| None),
+		params::CoreParams::Keys(params) =>
+			manage_keys::<F, _>(params, spec_factory, version).map(|_| None),
 		params::CoreParams::Custom(params) => Ok(Some(params)),
 	}
 }
@@ -401,6 +417,7 @@ where
 		.unwrap_or_else(|| keystore_path(&base_path, config.chain_spec.id()))
 		.to_string_lossy()
 		.into();
+	config.password = cli.password;
 
 	config.database_path =
 		db_path(&base_path, config.chain_spec.id()).to_string_lossy().into();
@@ -479,6 +496,19 @@ where
 	config.rpc_ws = Some(
 		parse_address(&format!("{}:{}", ws_interface, 9944), cli.ws_port)?
 	);
+	config.rpc_cors = match cli.rpc_cors {
+		Some(ref cors) => parse_cors(cors),
+		None => Some(DEFAULT_RPC_CORS.iter().map(|origin| origin.to_string()).collect()),
+	};
+	config.rpc_rate_limit = cli.rpc_rate_limit;
+	config.rpc_trusted_proxies = cli.rpc_trusted_proxies;
+	config.rpc_ws_max_connections = cli.ws_max_connections;
+	config.rpc_methods = match cli.rpc_methods {
+		params::RpcMethods::Safe => service::RpcMethods::Safe,
+		params::RpcMethods::Unsafe => service::RpcMethods::Unsafe,
+		params::RpcMethods::Auto if cli.rpc_external || cli.ws_external => service::RpcMethods::Safe,
+		params::RpcMethods::Auto => service::RpcMethods::Unsafe,
+	};
 
 	// Override telemetry
 	if cli.no_telemetry {
@@ -564,7 +594,9 @@ where
 	Ok(())
 }
 
-fn create_config_with_db_path<F, S>(
+/// Create a configuration pointing at the database of the chain selected by `cli`, for
+/// commands that work on the local database without starting the node.
+pub fn create_config_with_db_path<F, S>(
 	spec_factory: S, cli: &SharedParams, version: &VersionInfo,
 ) -> error::Result<FactoryFullConfiguration<F>>
 where
@@ -643,6 +675,57 @@ where
 	Ok(service::chain_ops::revert_chain::<F>(config, As::sa(blocks))?)
 }
 
+fn export_state<F, S>(
+	cli: ExportStateCmd,
+	spec_factory: S,
+	version: &VersionInfo,
+) -> error::Result<()>
+where
+	F: ServiceFactory,
+	S: FnOnce(&str) -> Result<Option<ChainSpec<FactoryGenesis<F>>>, String>,
+{
+	let config = create_config_with_db_path::<F, _>(spec_factory, &cli.shared_params, version)?;
+
+	info!("DB path: {}", config.database_path);
+	let file: Box<Write> = match cli.output {
+		Some(filename) => Box::new(File::create(filename)?),
+		None => Box::new(stdout()),
+	};
+
+	service::chain_ops::export_state::<F, _>(config, cli.at.map(As::sa), file).map_err(Into::into)
+}
+
+fn import_state<F, S>(
+	cli: ImportStateCmd,
+	spec_factory: S,
+	version: &VersionInfo,
+) -> error::Result<()>
+where
+	F: ServiceFactory,
+	S: FnOnce(&str) -> Result<Option<ChainSpec<FactoryGenesis<F>>>, String>,
+{
+	let hash = parse_hash(&cli.hash)?;
+	let config = create_config_with_db_path::<F, _>(spec_factory, &cli.shared_params, version)?;
+
+	info!("DB path: {}", config.database_path);
+	let file: Box<Read> = match cli.input {
+		Some(filename) => Box::new(File::open(filename)?),
+		None => Box::new(stdin()),
+	};
+
+	service::chain_ops::import_state::<F, _>(config, file, hash).map_err(Into::into)
+}
+
+/// Parse a block hash given as hex.
+fn parse_hash<H: Default + AsMut<[u8]>>(hash: &str) -> error::Result<H> {
+	let mut parsed = H::default();
+	let bytes = hex::decode(hash.trim_start_matches("0x")).ok()
+		.filter(|bytes| bytes.len() == parsed.as_mut().len())
+		.ok_or_else(|| input_err(format!("Invalid block hash `{}`, expected hex", hash)))?;
+	parsed.as_mut().copy_from_slice(&bytes);
+	Ok(parsed)
+}
+
 fn purge_chain<F, S>(
 	cli: PurgeChainCmd,
 	spec_factory: S,
@@ -685,6 +768,81 @@ where
 	}
 }
 
+fn manage_keys<F, S>(
+	cli: KeysCmd,
+	spec_factory: S,
+	version: &VersionInfo,
+) -> error::Result<()>
+where
+	F: ServiceFactory,
+	S: FnOnce(&str) -> Result<Option<ChainSpec<FactoryGenesis<F>>>, String>,
+{
+	let path = match cli.keystore_path {
+		Some(path) => path,
+		None => {
+			let spec = load_spec(&cli.shared_params, spec_factory)?;
+			keystore_path(&base_path(&cli.shared_params, version), spec.id())
+		},
+	};
+	let store = keystore::Store::open(path)?;
+
+	match cli.action {
+		KeysAction::Generate { key_type, password } => {
+			let public = store.generate_key(key_type, &password)?;
+			println!("{}", public.to_ss58check());
+		},
+		KeysAction::List { key_type } => {
+			let key_types = key_type.map(|key_type| vec![key_type])
+				.unwrap_or_else(|| keystore::KeyType::ALL.to_vec());
+			for key_type in key_types {
+				for public in store.keys(key_type)? {
+					println!("{} {} 0x{}", key_type, public.to_ss58check(), hex::encode(public.as_slice()));
+				}
+			}
+		},
+		KeysAction::Remove { public, key_type } => {
+			store.remove_key(key_type, &parse_public(&public)?)?;
+			println!("Removed {} key {}", key_type, public);
+		},
+		KeysAction::ChangePassword { public, key_type, password, new_password } => {
+			store.change_password(key_type, &parse_public(&public)?, &password, &new_password)?;
+			println!("Password of {} key {} changed", key_type, public);
+		},
+		KeysAction::Export { public, output, key_type, password } => {
+			let json = store.export_key(key_type, &parse_public(&public)?, &password)?;
+			match output {
+				Some(output) => fs::write(output, json)?,
+				None => println!("{}", json),
+			}
+		},
+		KeysAction::Import { input, key_type, password } => {
+			let json = match input {
+				Some(input) => fs::read_to_string(input)?,
+				None => {
+					let mut json = String::new();
+					stdin().read_to_string(&mut json)?;
+					json
+				},
+			};
+			let (key_type, public) = store.import_key(&json, &password, key_type)?;
+			println!("Imported {} key {}", key_type, public.to_ss58check());
+		},
+	}
+
+	Ok(())
+}
+
+/// Parse a public key given as SS58 or hex.
+fn parse_public(public: &str) -> error::Result<ed25519::Public> {
+	if let Ok(public) = ed25519::Public::from_ss58check(public) {
+		return Ok(public);
+	}
+	hex::decode(public.trim_start_matches("0x")).ok()
+		.filter(|bytes| bytes.len() == 32)
+		.map(|bytes| ed25519::Public::from_slice(&bytes))
+		.ok_or_else(|| input_err(format!("Invalid public key `{}`, expected SS58 or hex", public)))
+}
+
 fn parse_address(
 	address: &str,
 	port: Option<u16>,
@@ -699,6 +857,20 @@ fn parse_address(
 	Ok(address)
 }
 
+/// Parse a comma-separated origin list; only an explicit `all` allows every origin.
+fn parse_cors(cors: &str) -> Option<Vec<String>> {
+	let origins: Vec<String> = cors.split(',')
+		.map(str::trim)
+		.filter(|origin| !origin.is_empty())
+		.map(Into::into)
+		.collect();
+	if origins.iter().any(|origin| origin == "all" || origin == "*") {
+		None
+	} else {
+		Some(origins)
+	}
+}
+
 fn keystore_path(base_path: &Path, chain_id: &str) -> PathBuf {
 	let mut path = base_path.to_owned();
 	path.push("chains");
@@ -807,6 +979,16 @@ mod tests {
 		assert!(is_node_name_valid("email@domain").is_err());
 	}
 
+	#[test]
+	fn parse_cors_origins() {
+		assert_eq!(parse_cors("all"), None);
+		assert_eq!(parse_cors(" , "), Some(vec![]));
+		assert_eq!(
+			parse_cors("https://app.totem.live, http://localhost:8000"),
+			Some(vec!["https://app.totem.live".to_string(), "http://localhost:8000".to_string()])
+		);
+	}
+
 	#[test]
 	fn test_node_key_config_input() {
 		fn secret_input(net_config_dir: Option<String>) -> error::Result<()> {
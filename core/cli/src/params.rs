@@ -16,6 +16,7 @@
 
 use crate::traits::{AugmentClap, GetLogFilter};
 
+use std::net::IpAddr;
 use std::path::PathBuf;
 use structopt::{StructOpt, clap::{arg_enum, _clap_count_exprs, App, AppSettings, SubCommand, Arg}};
 use client;
@@ -65,6 +66,16 @@ arg_enum! {
 	}
 }
 
+arg_enum! {
+	/// Which RPC methods to expose
+	#[derive(Debug, Clone, Copy)]
+	pub enum RpcMethods {
+		Auto,
+		Safe,
+		Unsafe,
+	}
+}
+
 /// Shared parameters used by all `CoreParams`.
 #[derive(Debug, StructOpt, Clone)]
 pub struct SharedParams {
@@ -297,6 +308,10 @@ pub struct RunCmd {
 	#[structopt(long = "key", value_name = "STRING")]
 	pub key: Option<String>,
 
+	/// Password of the keys the node signs with, such as the offchain worker keys
+	#[structopt(long = "password", value_name = "PASSWORD", default_value = "")]
+	pub password: String,
+
 	/// Enable validator mode
 	#[structopt(long = "validator")]
 	pub validator: bool,
@@ -329,6 +344,40 @@ pub struct RunCmd {
 	#[structopt(long = "ws-port", value_name = "PORT")]
 	pub ws_port: Option<u16>,
 
+	/// Specify browser origins allowed to access the HTTP and WS RPC servers, as a comma-separated list
+	/// (e.g. `https://app.totem.live,http://localhost:8000`), or 'all' to disable origin checks.
+	/// Default is to allow localhost origins only.
+	#[structopt(long = "rpc-cors", value_name = "ORIGINS")]
+	pub rpc_cors: Option<String>,
+
+	/// Limit the number of RPC requests (HTTP) or connection attempts (WS) per second per client.
+	/// Clients are identified by their address, or by the address forwarded by a proxy given in
+	/// `--rpc-trusted-proxy`. Default is unlimited.
+	#[structopt(long = "rpc-rate-limit", value_name = "COUNT")]
+	pub rpc_rate_limit: Option<u32>,
+
+	/// Address of a reverse proxy whose `X-Forwarded-For` or `Forwarded` client address is used for rate limiting.
+	/// May be given more than once.
+	#[structopt(long = "rpc-trusted-proxy", value_name = "IP")]
+	pub rpc_trusted_proxies: Vec<IpAddr>,
+
+	/// Maximum number of concurrent WS RPC connections. Default is 100.
+	#[structopt(long = "ws-max-connections", value_name = "COUNT")]
+	pub ws_max_connections: Option<usize>,
+
+	/// RPC methods to expose. 'Safe' hides methods revealing node internals or using local keys, 'Auto' exposes
+	/// them only when both RPC servers listen on local interfaces.
+	#[structopt(
+		long = "rpc-methods",
+		value_name = "METHOD_SET",
+		raw(
+			possible_values = "&RpcMethods::variants()",
+			case_insensitive = "true",
+			default_value = r#""Auto""#
+		)
+	)]
+	pub rpc_methods: RpcMethods,
+
 	/// Specify the pruning mode, a number of blocks to keep or 'archive'. Default is 256.
 	#[structopt(long = "pruning", value_name = "PRUNING_MODE")]
 	pub pruning: Option<String>,
@@ -549,6 +598,43 @@ pub struct RevertCmd {
 
 impl_get_log_filter!(RevertCmd);
 
+/// The `export-state` command used to export the state of a finalized block.
+#[derive(Debug, StructOpt, Clone)]
+pub struct ExportStateCmd {
+	/// Output file name or stdout if unspecified.
+	#[structopt(parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	/// Specify the block number to export the state of. Latest finalized block by default.
+	#[structopt(long = "at", value_name = "BLOCK")]
+	pub at: Option<u64>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl_get_log_filter!(ExportStateCmd);
+
+/// The `import-state` command used to start a new database from a state snapshot.
+#[derive(Debug, StructOpt, Clone)]
+pub struct ImportStateCmd {
+	/// Input file or stdin if unspecified.
+	#[structopt(parse(from_os_str))]
+	pub input: Option<PathBuf>,
+
+	/// Hash of the snapshot block, in hex, obtained from a trusted source. The snapshot is
+	/// rejected if its header does not match.
+	#[structopt(long = "hash", value_name = "HASH")]
+	pub hash: String,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl_get_log_filter!(ImportStateCmd);
+
 /// The `purge-chain` command used to remove the whole chain.
 #[derive(Debug, StructOpt, Clone)]
 pub struct PurgeChainCmd {
@@ -563,6 +649,115 @@ pub struct PurgeChainCmd {
 
 impl_get_log_filter!(PurgeChainCmd);
 
+/// The `keys` command used to manage the keys in the keystore.
+#[derive(Debug, StructOpt, Clone)]
+pub struct KeysCmd {
+	#[allow(missing_docs)]
+	#[structopt(subcommand)]
+	pub action: KeysAction,
+
+	/// Specify custom keystore path
+	#[structopt(long = "keystore-path", value_name = "PATH", parse(from_os_str))]
+	pub keystore_path: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl_get_log_filter!(KeysCmd);
+
+/// The actions of the `keys` command.
+///
+/// Key types are `session`, `boxkeys` and `offchain`. Public keys are given as hex or SS58.
+#[derive(Debug, StructOpt, Clone)]
+pub enum KeysAction {
+	/// Generate a new key and print its public key.
+	#[structopt(name = "generate")]
+	Generate {
+		/// Type of the key.
+		#[structopt(long = "key-type", value_name = "TYPE", default_value = "session")]
+		key_type: keystore::KeyType,
+
+		/// Password protecting the key.
+		#[structopt(long = "password", default_value = "")]
+		password: String,
+	},
+
+	/// List the public keys in the keystore.
+	#[structopt(name = "list")]
+	List {
+		/// Only list keys of this type.
+		#[structopt(long = "key-type", value_name = "TYPE")]
+		key_type: Option<keystore::KeyType>,
+	},
+
+	/// Remove a key.
+	#[structopt(name = "remove")]
+	Remove {
+		/// Public key of the key to remove.
+		public: String,
+
+		/// Type of the key.
+		#[structopt(long = "key-type", value_name = "TYPE", default_value = "session")]
+		key_type: keystore::KeyType,
+	},
+
+	/// Re-encrypt a key with a new password.
+	#[structopt(name = "change-password")]
+	ChangePassword {
+		/// Public key of the key.
+		public: String,
+
+		/// Type of the key.
+		#[structopt(long = "key-type", value_name = "TYPE", default_value = "session")]
+		key_type: keystore::KeyType,
+
+		/// Current password of the key.
+		#[structopt(long = "password", default_value = "")]
+		password: String,
+
+		/// New password of the key.
+		#[structopt(long = "new-password", default_value = "")]
+		new_password: String,
+	},
+
+	/// Export a key as an encrypted JSON key file.
+	#[structopt(name = "export")]
+	Export {
+		/// Public key of the key.
+		public: String,
+
+		/// Output file name or stdout if unspecified.
+		#[structopt(long = "output", value_name = "PATH", parse(from_os_str))]
+		output: Option<PathBuf>,
+
+		/// Type of the key.
+		#[structopt(long = "key-type", value_name = "TYPE", default_value = "session")]
+		key_type: keystore::KeyType,
+
+		/// Password of the key. The key file is encrypted with the same password.
+		#[structopt(long = "password", default_value = "")]
+		password: String,
+	},
+
+	/// Import an encrypted JSON key file.
+	#[structopt(name = "import")]
+	Import {
+		/// Input file or stdin if unspecified.
+		#[structopt(parse(from_os_str))]
+		input: Option<PathBuf>,
+
+		/// Store the key under this type instead of the one recorded in the key file.
+		#[structopt(long = "key-type", value_name = "TYPE")]
+		key_type: Option<keystore::KeyType>,
+
+		/// Password of the key file.
+		#[structopt(long = "password", default_value = "")]
+		password: String,
+	},
+}
+
 /// All core commands that are provided by default.
 ///
 /// The core commands are split into multiple subcommands and `Run` is the default subcommand. From
@@ -585,9 +780,18 @@ pub enum CoreParams<CC, RP> {
 	/// Revert chain to the previous state.
 	Revert(RevertCmd),
 
+	/// Export the state of a finalized block to a file.
+	ExportState(ExportStateCmd),
+
+	/// Import a state snapshot into an empty database.
+	ImportState(ImportStateCmd),
+
 	/// Remove the whole chain data.
 	PurgeChain(PurgeChainCmd),
 
+	/// Manage the keys in the keystore.
+	Keys(KeysCmd),
+
 	/// Further custom subcommands.
 	Custom(CC),
 }
@@ -617,10 +821,22 @@ impl<CC, RP> StructOpt for CoreParams<CC, RP> where
 			RevertCmd::augment_clap(SubCommand::with_name("revert"))
 				.about("Revert chain to the previous state.")
 		)
+		.subcommand(
+			ExportStateCmd::augment_clap(SubCommand::with_name("export-state"))
+				.about("Export the state of a finalized block to a file.")
+		)
+		.subcommand(
+			ImportStateCmd::augment_clap(SubCommand::with_name("import-state"))
+				.about("Import a state snapshot into an empty database.")
+		)
 		.subcommand(
 			PurgeChainCmd::augment_clap(SubCommand::with_name("purge-chain"))
 				.about("Remove the whole chain data.")
 		)
+		.subcommand(
+			KeysCmd::augment_clap(SubCommand::with_name("keys"))
+				.about("Manage the keys in the keystore.")
+		)
 	}
 
 	fn from_clap(matches: &::structopt::clap::ArgMatches) -> Self {
@@ -632,8 +848,13 @@ impl<CC, RP> StructOpt for CoreParams<CC, RP> where
 			("import-blocks", Some(matches)) =>
 				CoreParams::ImportBlocks(ImportBlocksCmd::from_clap(matches)),
 			("revert", Some(matches)) => CoreParams::Revert(RevertCmd::from_clap(matches)),
+			("export-state", Some(matches)) =>
+				CoreParams::ExportState(ExportStateCmd::from_clap(matches)),
+			("import-state", Some(matches)) =>
+				CoreParams::ImportState(ImportStateCmd::from_clap(matches)),
 			("purge-chain", Some(matches)) =>
 				CoreParams::PurgeChain(PurgeChainCmd::from_clap(matches)),
+			("keys", Some(matches)) => CoreParams::Keys(KeysCmd::from_clap(matches)),
 			(_, None) => CoreParams::Run(MergeParameters::from_clap(matches)),
 			_ => CoreParams::Custom(CC::from_clap(matches)),
 		}
@@ -648,7 +869,10 @@ impl<CC, RP> GetLogFilter for CoreParams<CC, RP> where CC: GetLogFilter {
 			CoreParams::ExportBlocks(c) => c.get_log_filter(),
 			CoreParams::ImportBlocks(c) => c.get_log_filter(),
 			CoreParams::PurgeChain(c) => c.get_log_filter(),
+			CoreParams::Keys(c) => c.get_log_filter(),
 			CoreParams::Revert(c) => c.get_log_filter(),
+			CoreParams::ExportState(c) => c.get_log_filter(),
+			CoreParams::ImportState(c) => c.get_log_filter(),
 			CoreParams::Custom(c) => c.get_log_filter(),
 		}
 	}
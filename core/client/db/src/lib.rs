@@ -27,6 +27,7 @@
 pub mod light;
 
 mod cache;
+mod offchain;
 mod storage_cache;
 mod utils;
 
@@ -45,6 +46,7 @@ use trie::{MemoryDB, PrefixedMemoryDB, prefixed_key};
 use parking_lot::{Mutex, RwLock};
 use primitives::{H256, Blake2Hasher, ChangesTrieConfiguration, convert_hash};
 use primitives::storage::well_known_keys;
+use primitives::offchain::OffchainStorage;
 use runtime_primitives::{generic::BlockId, Justification, StorageOverlay, ChildrenStorageOverlay};
 use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, As, NumberFor, Zero, Digest, DigestItem};
 use runtime_primitives::BuildStorage;
@@ -108,6 +110,8 @@ mod columns {
 	pub const JUSTIFICATION: Option<u32> = Some(6);
 	pub const CHANGES_TRIE: Option<u32> = Some(7);
 	pub const AUX: Option<u32> = Some(8);
+	/// Offchain workers local storage.
+	pub const OFFCHAIN: Option<u32> = Some(9);
 }
 
 struct PendingBlock<Block: BlockT> {
@@ -534,6 +538,7 @@ pub struct Backend<Block: BlockT> {
 	blockchain: BlockchainDb<Block>,
 	canonicalization_delay: u64,
 	shared_cache: SharedCache<Block, Blake2Hasher>,
+	offchain_storage: Arc<offchain::LocalStorage>,
 }
 
 impl<Block: BlockT<Hash=H256>> Backend<Block> {
@@ -569,6 +574,7 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			db: db.clone(),
 			state_db,
 		};
+		let offchain_storage = offchain::LocalStorage::new(db.clone());
 		let changes_tries_storage = DbChangesTrieStorage {
 			db,
 			meta,
@@ -583,9 +589,99 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			blockchain,
 			canonicalization_delay,
 			shared_cache: new_shared_cache(STATE_CACHE_SIZE_BYTES),
+			offchain_storage: Arc::new(offchain_storage),
 		})
 	}
 
+	/// Import the state of a finalized block whose ancestors are not in the database and make it
+	/// the best and finalized block, so that a node can start from a state snapshot instead of
+	/// executing all blocks since genesis.
+	///
+	/// The database must not hold any block but genesis. The state is checked against the state
+	/// root of `header`. `aux` entries, such as the finality voter state, are stored as they are.
+	pub fn import_state(
+		&self,
+		header: Block::Header,
+		justification: Option<Justification>,
+		top: StorageOverlay,
+		children: ChildrenStorageOverlay,
+		aux: Vec<(Vec<u8>, Vec<u8>)>,
+	) -> Result<(), client::error::Error> {
+		use client::backend::{Backend as ClientBackend, BlockImportOperation};
+
+		let hash = header.hash();
+		let number = *header.number();
+		if number.is_zero() || !self.blockchain.meta.read().best_number.is_zero() {
+			return Err(client::error::ErrorKind::Backend(
+				"State can only be imported for a block after genesis into a database holding just the genesis block".into()
+			).into());
+		}
+
+		let mut operation = self.begin_operation()?;
+		let root = operation.reset_storage(top, children)?;
+		if root != *header.state_root() {
+			return Err(client::error::ErrorKind::Backend(format!(
+				"Imported state root {:?} does not match the state root {:?} of block {:?}",
+				root, header.state_root(), hash,
+			)).into());
+		}
+
+		let mut changeset: state_db::ChangeSet<Vec<u8>> = state_db::ChangeSet::default();
+		for (key, (val, rc)) in operation.db_updates.drain() {
+			if rc > 0 {
+				changeset.inserted.push((key, val.to_vec()));
+			}
+		}
+
+		let mut transaction = DBTransaction::new();
+		let commit = self.storage.state_db.import_canonical(&hash, number.as_(), changeset)
+			.map_err(|e: state_db::Error<io::Error>| client::error::Error::from(format!("State database error: {:?}", e)))?;
+		apply_state_commit(&mut transaction, commit);
+
+		let lookup_key = utils::number_and_hash_to_lookup_key(number, hash);
+		utils::insert_hash_to_key_mapping(&mut transaction, columns::KEY_LOOKUP, number, hash);
+		utils::insert_number_to_key_mapping(&mut transaction, columns::KEY_LOOKUP, number, hash);
+		transaction.put(columns::HEADER, &lookup_key, &header.encode());
+		if let Some(justification) = justification {
+			transaction.put(columns::JUSTIFICATION, &lookup_key, &justification.encode());
+		}
+		transaction.put(columns::META, meta_keys::BEST_BLOCK, &lookup_key);
+		transaction.put(columns::META, meta_keys::FINALIZED_BLOCK, &lookup_key);
+		for (key, value) in aux {
+			transaction.put_vec(columns::AUX, &key, value);
+		}
+
+		let (displaced_leaf, finalization_displaced) = {
+			let mut leaves = self.blockchain.leaves.write();
+			let displaced_leaf = leaves.import(hash, number, *header.parent_hash());
+			let finalization_displaced = leaves.finalize_height(number);
+			leaves.prepare_transaction(&mut transaction, columns::META, meta_keys::LEAF_PREFIX);
+			(displaced_leaf, finalization_displaced)
+		};
+
+		if let Err(e) = self.storage.db.write(transaction).map_err(db_err) {
+			self.storage.state_db.revert_pending();
+			let mut leaves = self.blockchain.leaves.write();
+			let mut undo = leaves.undo();
+			if let Some(displaced_leaf) = displaced_leaf {
+				undo.undo_import(displaced_leaf);
+			}
+			undo.undo_finalization(finalization_displaced);
+			return Err(e);
+		}
+
+		self.storage.state_db.apply_pending();
+		self.blockchain.update_meta(hash, number, true, true);
+		Ok(())
+	}
+
+	/// All entries of the auxiliary storage.
+	pub fn aux_entries(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+		self.storage.db.iter(columns::AUX)
+			.map(|(key, value)| (key.into_vec(), value.into_vec()))
+			.collect()
+	}
+
 	/// Returns in-memory blockchain that contains the same set of blocks that the self.
 	#[cfg(feature = "test-helpers")]
 	pub fn as_in_memory(&self) -> InMemoryBackend<Block, Blake2Hasher> {
@@ -1121,6 +1217,10 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 		Some(&self.changes_tries_storage)
 	}
 
+	fn offchain_storage(&self) -> Option<Arc<dyn OffchainStorage>> {
+		Some(self.offchain_storage.clone())
+	}
+
 	fn revert(&self, n: NumberFor<Block>) -> Result<NumberFor<Block>, client::error::Error> {
 		use client::blockchain::HeaderBackend;
 
@@ -1959,4 +2059,85 @@ mod tests {
 			backend.commit_operation(op).unwrap_err();
 		}
 	}
+
+	#[test]
+	fn import_state_without_ancestors() {
+		use client::backend::AuxStore;
+
+		let backend = Backend::<Block>::new_test(10, 10);
+		insert_header(&backend, 0, Default::default(), Default::default(), Default::default());
+
+		let storage = vec![
+			(vec![1, 3, 5], vec![2, 4, 6]),
+			(vec![1, 2, 3], vec![9, 9, 9]),
+		];
+		let root = backend.state_at(BlockId::Hash(Default::default())).unwrap()
+			.storage_root(storage.iter().cloned().map(|(k, v)| (k, Some(v)))).0;
+		let mut header = Header {
+			number: 5,
+			parent_hash: H256::repeat_byte(4),
+			state_root: Default::default(),
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+
+		// the state must match the state root
+		assert!(backend.import_state(header.clone(), None, storage.iter().cloned().collect(), Default::default(), Vec::new()).is_err());
+		assert_eq!(backend.blockchain().info().unwrap().best_number, 0);
+
+		header.state_root = root;
+		let hash = header.hash();
+		backend.import_state(
+			header,
+			Some(vec![7]),
+			storage.iter().cloned().collect(),
+			Default::default(),
+			vec![(b"voters".to_vec(), b"set".to_vec())],
+		).unwrap();
+
+		let info = backend.blockchain().info().unwrap();
+		assert_eq!((info.best_number, info.best_hash), (5, hash));
+		assert_eq!((info.finalized_number, info.finalized_hash), (5, hash));
+		assert_eq!(backend.blockchain().hash(5).unwrap(), Some(hash));
+		assert_eq!(backend.blockchain().justification(BlockId::Number(5)).unwrap(), Some(vec![7]));
+		assert_eq!(backend.blockchain().leaves().unwrap(), vec![hash]);
+		assert_eq!(backend.get_aux(b"voters").unwrap(), Some(b"set".to_vec()));
+		assert!(backend.aux_entries().contains(&(b"voters".to_vec(), b"set".to_vec())));
+		let state = backend.state_at(BlockId::Number(5)).unwrap();
+		assert_eq!(state.storage(&[1, 3, 5]).unwrap(), Some(vec![2, 4, 6]));
+		assert_eq!(state.storage(&[1, 2, 3]).unwrap(), Some(vec![9, 9, 9]));
+
+		// blocks are imported on top of the snapshot as usual
+		let mut op = backend.begin_operation().unwrap();
+		backend.begin_state_operation(&mut op, BlockId::Number(5)).unwrap();
+		let changes = vec![(vec![1, 3, 5], None), (vec![5, 5, 5], Some(vec![4, 5, 6]))];
+		let (root, overlay) = op.old_state.storage_root(changes.iter().cloned());
+		op.update_db_storage(overlay).unwrap();
+		let header = Header {
+			number: 6,
+			parent_hash: hash,
+			state_root: root,
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+		op.set_block_data(header, Some(vec![]), None, NewBlockState::Final).unwrap();
+		backend.commit_operation(op).unwrap();
+
+		assert_eq!(backend.blockchain().info().unwrap().finalized_number, 6);
+		let state = backend.state_at(BlockId::Number(6)).unwrap();
+		assert_eq!(state.storage(&[1, 3, 5]).unwrap(), None);
+		assert_eq!(state.storage(&[5, 5, 5]).unwrap(), Some(vec![4, 5, 6]));
+
+		// only a database without blocks after genesis accepts a snapshot
+		let root = backend.state_at(BlockId::Hash(Default::default())).unwrap()
+			.storage_root(Vec::<(Vec<u8>, Option<Vec<u8>>)>::new()).0;
+		let header = Header {
+			number: 10,
+			parent_hash: Default::default(),
+			state_root: root,
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+		assert!(backend.import_state(header, None, Default::default(), Default::default(), Vec::new()).is_err());
+	}
 }
@@ -0,0 +1,92 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RocksDB-based offchain workers local storage.
+
+use std::sync::Arc;
+
+use crate::columns;
+use kvdb::KeyValueDB;
+use log::error;
+use parking_lot::Mutex;
+use primitives::offchain::OffchainStorage;
+
+/// Offchain local storage kept in a dedicated column of the node database.
+pub struct LocalStorage {
+	db: Arc<KeyValueDB>,
+	/// Serializes writes, so that compare-and-set is atomic.
+	lock: Mutex<()>,
+}
+
+impl LocalStorage {
+	/// Create offchain local storage with given `KeyValueDB` backend.
+	pub fn new(db: Arc<KeyValueDB>) -> Self {
+		LocalStorage {
+			db,
+			lock: Mutex::new(()),
+		}
+	}
+
+	fn write(&self, key: &[u8], value: &[u8]) {
+		let mut tx = self.db.transaction();
+		tx.put(columns::OFFCHAIN, key, value);
+		if let Err(e) = self.db.write(tx) {
+			error!("Error writing to the offchain DB: {:?}", e);
+		}
+	}
+}
+
+impl OffchainStorage for LocalStorage {
+	fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.db.get(columns::OFFCHAIN, key)
+			.ok()
+			.and_then(|x| x)
+			.map(|v| v.to_vec())
+	}
+
+	fn set(&self, key: &[u8], value: &[u8]) {
+		let _guard = self.lock.lock();
+		self.write(key, value);
+	}
+
+	fn compare_and_set(&self, key: &[u8], old_value: Option<&[u8]>, new_value: &[u8]) -> bool {
+		let _guard = self.lock.lock();
+		let current = self.get(key);
+		if current.as_ref().map(|v| &v[..]) != old_value {
+			return false;
+		}
+		self.write(key, new_value);
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn should_compare_and_set_and_persist() {
+		let db = Arc::new(::kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+		let storage = LocalStorage::new(db.clone());
+
+		assert!(storage.compare_and_set(b"counter", None, &[1]));
+		assert!(!storage.compare_and_set(b"counter", None, &[2]));
+		assert!(storage.compare_and_set(b"counter", Some(&[1]), &[2]));
+
+		let reopened = LocalStorage::new(db);
+		assert_eq!(reopened.get(b"counter"), Some(vec![2]));
+	}
+}
@@ -17,8 +17,10 @@
 //! Substrate Client data backend
 
 use std::collections::HashMap;
+use std::sync::Arc;
 use crate::error;
 use primitives::ChangesTrieConfiguration;
+use primitives::offchain::OffchainStorage;
 use runtime_primitives::{generic::BlockId, Justification, StorageOverlay, ChildrenStorageOverlay};
 use runtime_primitives::traits::{Block as BlockT, NumberFor};
 use state_machine::backend::Backend as StateBackend;
@@ -143,6 +145,10 @@ pub trait Backend<Block, H>: AuxStore + Send + Sync where
 	fn blockchain(&self) -> &Self::Blockchain;
 	/// Returns reference to changes trie storage.
 	fn changes_trie_storage(&self) -> Option<&Self::ChangesTrieStorage>;
+	/// Returns node-local storage for offchain workers, if the backend keeps one.
+	fn offchain_storage(&self) -> Option<Arc<dyn OffchainStorage>> {
+		None
+	}
 	/// Returns true if state for given block is available.
 	fn have_state_at(&self, hash: &Block::Hash, _number: NumberFor<Block>) -> bool {
 		self.state_at(BlockId::Hash(hash.clone())).is_ok()
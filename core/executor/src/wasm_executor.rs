@@ -31,6 +31,7 @@ use crate::wasm_utils::UserError;
 use primitives::{blake2_128, blake2_256, twox_64, twox_128, twox_256, ed25519, sr25519, Pair};
 use primitives::hexdisplay::HexDisplay;
 use primitives::sandbox as sandbox_primitives;
+use primitives::offchain;
 use primitives::{H256, Blake2Hasher};
 use trie::ordered_trie_root;
 use crate::sandbox;
@@ -598,6 +599,115 @@ impl_function_executor!(this: FunctionExecutor<'e, E>,
 
 		Ok(())
 	},
+	// return 0 and place u32::max_value() into written_out if no value exists for the key.
+	ext_local_storage_get(key_data: *const u8, key_len: u32, written_out: *mut u32) -> *mut u8 => {
+		let key = this.memory.get(key_data, key_len as usize)
+			.map_err(|_| UserError("OOB while ext_local_storage_get: wasm"))?;
+
+		let maybe_value = this.ext.offchain()
+			.map(|api| api.local_storage_get(&key))
+			.ok_or_else(|| UserError("Calling unavailable API ext_local_storage_get: wasm"))?;
+
+		if let Some(value) = maybe_value {
+			let offset = this.heap.allocate(value.len() as u32)? as u32;
+			this.memory.set(offset, &value).map_err(|_| UserError("Invalid attempt to set memory in ext_local_storage_get"))?;
+			this.memory.write_primitive(written_out, value.len() as u32)
+				.map_err(|_| UserError("Invalid attempt to write written_out in ext_local_storage_get"))?;
+			Ok(offset)
+		} else {
+			this.memory.write_primitive(written_out, u32::max_value())
+				.map_err(|_| UserError("Invalid attempt to write failed written_out in ext_local_storage_get"))?;
+			Ok(0)
+		}
+	},
+	ext_local_storage_set(key_data: *const u8, key_len: u32, value_data: *const u8, value_len: u32) => {
+		let key = this.memory.get(key_data, key_len as usize)
+			.map_err(|_| UserError("OOB while ext_local_storage_set: wasm"))?;
+		let value = this.memory.get(value_data, value_len as usize)
+			.map_err(|_| UserError("OOB while ext_local_storage_set: wasm"))?;
+
+		this.ext.offchain()
+			.map(|api| api.local_storage_set(&key, &value))
+			.ok_or_else(|| UserError("Calling unavailable API ext_local_storage_set: wasm"))?;
+
+		Ok(())
+	},
+	// `old_value_len` of u32::max_value() means the key is expected to be unset.
+	ext_local_storage_compare_and_set(
+		key_data: *const u8,
+		key_len: u32,
+		old_value_data: *const u8,
+		old_value_len: u32,
+		new_value_data: *const u8,
+		new_value_len: u32
+	) -> u32 => {
+		let key = this.memory.get(key_data, key_len as usize)
+			.map_err(|_| UserError("OOB while ext_local_storage_compare_and_set: wasm"))?;
+		let old_value = if old_value_len == u32::max_value() {
+			None
+		} else {
+			Some(this.memory.get(old_value_data, old_value_len as usize)
+				.map_err(|_| UserError("OOB while ext_local_storage_compare_and_set: wasm"))?)
+		};
+		let new_value = this.memory.get(new_value_data, new_value_len as usize)
+			.map_err(|_| UserError("OOB while ext_local_storage_compare_and_set: wasm"))?;
+
+		let res = this.ext.offchain()
+			.map(|api| api.local_storage_compare_and_set(&key, old_value.as_ref().map(|v| &v[..]), &new_value))
+			.ok_or_else(|| UserError("Calling unavailable API ext_local_storage_compare_and_set: wasm"))?;
+
+		Ok(if res { 1 } else { 0 })
+	},
+	ext_keystore_public_keys(written_out: *mut u32) -> *mut u8 => {
+		let keys = this.ext.offchain()
+			.map(|api| api.pubkeys())
+			.ok_or_else(|| UserError("Calling unavailable API ext_keystore_public_keys: wasm"))?;
+		let encoded: Vec<u8> = keys.iter().flat_map(|k| k.iter().cloned()).collect();
+
+		let offset = this.heap.allocate(encoded.len() as u32)? as u32;
+		this.memory.set(offset, &encoded).map_err(|_| UserError("Invalid attempt to set memory in ext_keystore_public_keys"))?;
+		this.memory.write_primitive(written_out, encoded.len() as u32)
+			.map_err(|_| UserError("Invalid attempt to write written_out in ext_keystore_public_keys"))?;
+		Ok(offset)
+	},
+	ext_keystore_sign(key_data: *const u8, msg_data: *const u8, msg_len: u32, out: *mut u8) -> u32 => {
+		let mut key = [0u8; 32];
+		this.memory.get_into(key_data, &mut key[..]).map_err(|_| UserError("Invalid attempt to get key in ext_keystore_sign"))?;
+		let msg = this.memory.get(msg_data, msg_len as usize).map_err(|_| UserError("Invalid attempt to get message in ext_keystore_sign"))?;
+
+		let signature = this.ext.offchain()
+			.map(|api| api.sign(key, &msg))
+			.ok_or_else(|| UserError("Calling unavailable API ext_keystore_sign: wasm"))?;
+
+		match signature {
+			Some(signature) => {
+				this.memory.set(out, &signature).map_err(|_| UserError("Invalid attempt to set signature in ext_keystore_sign"))?;
+				Ok(0)
+			},
+			None => Ok(1),
+		}
+	},
+	// place the encoded `Result<HttpResponse, HttpError>` into memory and its length into written_out.
+	ext_http_request(request_data: *const u8, request_len: u32, written_out: *mut u32) -> *mut u8 => {
+		use parity_codec::{Decode, Encode};
+
+		let encoded_request = this.memory.get(request_data, request_len as usize)
+			.map_err(|_| UserError("OOB while ext_http_request: wasm"))?;
+
+		let result = match offchain::HttpRequest::decode(&mut &encoded_request[..]) {
+			Some(request) => this.ext.offchain()
+				.map(|api| api.http_request(request))
+				.ok_or_else(|| UserError("Calling unavailable API ext_http_request: wasm"))?,
+			None => Err(offchain::HttpError::InvalidRequest),
+		};
+		let encoded = result.encode();
+
+		let offset = this.heap.allocate(encoded.len() as u32)? as u32;
+		this.memory.set(offset, &encoded).map_err(|_| UserError("Invalid attempt to set memory in ext_http_request"))?;
+		this.memory.write_primitive(written_out, encoded.len() as u32)
+			.map_err(|_| UserError("Invalid attempt to write written_out in ext_http_request"))?;
+		Ok(offset)
+	},
 	ext_sandbox_instantiate(
 		dispatch_thunk_idx: usize,
 		wasm_ptr: *const u8,
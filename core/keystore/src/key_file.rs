@@ -0,0 +1,135 @@
+// Copyright 2017-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate. If not, see <http://www.gnu.org/licenses/>.
+
+//! Encrypted JSON key files.
+//!
+//! The seed of the key is encrypted with AES-128-CTR under a key derived from the password
+//! with PBKDF2-SHA256. The MAC is the blake2-256 hash of the second half of the derived key
+//! followed by the ciphertext.
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "type": "offchain",
+//!   "public": "<hex public key>",
+//!   "crypto": {
+//!     "cipher": "aes-128-ctr",
+//!     "iv": "<hex, 16 bytes>",
+//!     "kdf": "pbkdf2-sha256",
+//!     "salt": "<hex, 32 bytes>",
+//!     "iterations": 10240,
+//!     "ciphertext": "<hex, 32 bytes>",
+//!     "mac": "<hex, 32 bytes>"
+//!   }
+//! }
+//! ```
+
+use rand::{RngCore, rngs::OsRng};
+use serde_json::{json, Value};
+use subtle::ConstantTimeEq;
+use substrate_primitives::{blake2_256, ed25519::Pair, Pair as PairT};
+
+use crate::{Error, ErrorKind, KeyType, Result, KEY_ITERATIONS};
+
+const VERSION: u64 = 1;
+
+/// Encrypt `pair` with `password` into a key file.
+pub fn encrypt(key_type: KeyType, pair: &Pair, password: &str) -> String {
+	let mut rng = OsRng::new().expect("OS randomness is available; qed");
+	let mut salt = [0u8; 32];
+	let mut iv = [0u8; 16];
+	rng.fill_bytes(&mut salt);
+	rng.fill_bytes(&mut iv);
+
+	let iterations = KEY_ITERATIONS as u32;
+	let (cipher_key, mac_key) = crypto::derive_key_iterations(password.as_bytes(), &salt, iterations);
+	let seed = pair.seed();
+	let mut ciphertext = vec![0u8; seed.len()];
+	crypto::aes::encrypt_128_ctr(&cipher_key, &iv, &seed[..], &mut ciphertext)
+		.expect("key and iv have the sizes required by AES-128-CTR; qed");
+
+	json!({
+		"version": VERSION,
+		"type": key_type.name(),
+		"public": hex::encode(pair.public().as_slice()),
+		"crypto": {
+			"cipher": "aes-128-ctr",
+			"iv": hex::encode(iv),
+			"kdf": "pbkdf2-sha256",
+			"salt": hex::encode(salt),
+			"iterations": iterations,
+			"ciphertext": hex::encode(&ciphertext),
+			"mac": hex::encode(mac(&mac_key, &ciphertext)),
+		},
+	}).to_string()
+}
+
+/// Decrypt a key file with `password`.
+pub fn decrypt(file: &Value, password: &str) -> Result<(KeyType, Pair)> {
+	if file["version"].as_u64() != Some(VERSION) {
+		return Err(invalid("unsupported version"));
+	}
+	let key_type = file["type"].as_str()
+		.ok_or_else(|| invalid("missing key type"))?
+		.parse::<KeyType>()
+		.map_err(invalid)?;
+
+	let crypto = &file["crypto"];
+	if crypto["cipher"] != "aes-128-ctr" || crypto["kdf"] != "pbkdf2-sha256" {
+		return Err(invalid("unsupported cipher or key derivation"));
+	}
+	let iterations = crypto["iterations"].as_u64()
+		.filter(|i| *i > 0 && *i <= u32::max_value() as u64)
+		.ok_or_else(|| invalid("invalid iterations"))? as u32;
+	let iv = bytes(&crypto["iv"], 16)?;
+	let salt = bytes(&crypto["salt"], 32)?;
+	let ciphertext = bytes(&crypto["ciphertext"], 32)?;
+	let expected_mac = bytes(&crypto["mac"], 32)?;
+
+	let mut salt_bytes = [0u8; 32];
+	salt_bytes.copy_from_slice(&salt);
+	let (cipher_key, mac_key) = crypto::derive_key_iterations(password.as_bytes(), &salt_bytes, iterations);
+	if !bool::from(mac(&mac_key, &ciphertext)[..].ct_eq(&expected_mac[..])) {
+		return Err(ErrorKind::InvalidPassword.into());
+	}
+
+	let mut seed = [0u8; 32];
+	crypto::aes::decrypt_128_ctr(&cipher_key, &iv, &ciphertext, &mut seed)
+		.map_err(|_| invalid("decryption failed"))?;
+	let pair = Pair::from_seed(seed);
+
+	if file["public"].as_str() != Some(&hex::encode(pair.public().as_slice())[..]) {
+		return Err(invalid("public key does not match the encrypted seed"));
+	}
+	Ok((key_type, pair))
+}
+
+fn mac(mac_key: &[u8], ciphertext: &[u8]) -> [u8; 32] {
+	let mut data = mac_key.to_vec();
+	data.extend_from_slice(ciphertext);
+	blake2_256(&data)
+}
+
+fn bytes(value: &Value, len: usize) -> Result<Vec<u8>> {
+	value.as_str()
+		.and_then(|s| hex::decode(s).ok())
+		.filter(|bytes| bytes.len() == len)
+		.ok_or_else(|| invalid("invalid hex field"))
+}
+
+fn invalid<M: Into<String>>(message: M) -> Error {
+	ErrorKind::InvalidKeyFile(message.into()).into()
+}
@@ -15,20 +15,28 @@
 // along with Substrate. If not, see <http://www.gnu.org/licenses/>.
 
 //! Keystore (and session key management) for ed25519 based chains like Polkadot.
+//!
+//! Keys are tagged with a `KeyType` so that session keys, boxkeys signing keys and
+//! offchain worker keys can be kept side by side. `KeystoreBackend` abstracts over where
+//! the keys live; `Store` keeps them as password encrypted JSON files.
 
 // Silence: `use of deprecated item 'std::error::Error::cause': replaced by Error::source, which can support downcasting`
 // https://github.com/paritytech/substrate/issues/1547
 #![allow(deprecated)]
 
+mod key_file;
+
 use std::collections::HashMap;
+use std::fmt;
 use std::path::PathBuf;
-use std::fs::{self, File};
-use std::io::{self, Write};
+use std::fs;
+use std::io;
+use std::str::FromStr;
 
 use error_chain::{bail, error_chain, error_chain_processing, impl_error_chain_processed,
 	impl_extract_backtrace, impl_error_chain_kind};
 
-use substrate_primitives::{ed25519::{Pair, Public}, Pair as PairT};
+use substrate_primitives::{ed25519::{Pair, Public, Signature}, Pair as PairT};
 
 pub use crypto::KEY_ITERATIONS;
 
@@ -51,10 +59,100 @@ error_chain! {
 			description("Invalid seed"),
 			display("Invalid seed"),
 		}
+		InvalidKeyFile(m: String) {
+			description("Invalid key file"),
+			display("Invalid key file: {}", m),
+		}
+		Unsupported(m: &'static str) {
+			description("Operation not supported by the keystore backend"),
+			display("Operation not supported by the keystore backend: {}", m),
+		}
+	}
+}
+
+/// The purpose of a key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyType {
+	/// Session keys used for authoring and finality.
+	Session,
+	/// Signing keys registered with the boxkeys module.
+	Boxkeys,
+	/// Keys used by offchain workers to sign transactions.
+	Offchain,
+}
+
+impl KeyType {
+	/// All key types.
+	pub const ALL: [KeyType; 3] = [KeyType::Session, KeyType::Boxkeys, KeyType::Offchain];
+
+	/// The name of the key type, as used on the command line and in key files.
+	pub fn name(&self) -> &'static str {
+		match self {
+			KeyType::Session => "session",
+			KeyType::Boxkeys => "boxkeys",
+			KeyType::Offchain => "offchain",
+		}
+	}
+}
+
+impl fmt::Display for KeyType {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(self.name())
+	}
+}
+
+impl FromStr for KeyType {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, String> {
+		KeyType::ALL.iter()
+			.find(|key_type| key_type.name() == s.to_lowercase())
+			.cloned()
+			.ok_or_else(|| format!("Unknown key type `{}`, expected one of session, boxkeys, offchain", s))
 	}
 }
 
-/// Key store.
+/// Storage of typed keys.
+///
+/// Implementations may keep the secret keys out of reach of the node, in which case
+/// `load_key` and `export_key` return `ErrorKind::Unsupported` and callers should use `sign`.
+pub trait KeystoreBackend: Send + Sync {
+	/// Generate a new key of the given type, protected by `password`.
+	fn generate_key(&self, key_type: KeyType, password: &str) -> Result<Public>;
+
+	/// Add an existing key pair, protected by `password`.
+	fn insert_key(&self, key_type: KeyType, pair: &Pair, password: &str) -> Result<()>;
+
+	/// Public keys of all keys of the given type.
+	fn keys(&self, key_type: KeyType) -> Result<Vec<Public>>;
+
+	/// Load a key pair.
+	fn load_key(&self, key_type: KeyType, public: &Public, password: &str) -> Result<Pair>;
+
+	/// Sign `message` with a key.
+	fn sign(&self, key_type: KeyType, public: &Public, password: &str, message: &[u8]) -> Result<Signature> {
+		Ok(self.load_key(key_type, public, password)?.sign(message))
+	}
+
+	/// Remove a key.
+	fn remove_key(&self, key_type: KeyType, public: &Public) -> Result<()>;
+
+	/// Re-encrypt a key with a new password.
+	fn change_password(&self, key_type: KeyType, public: &Public, old: &str, new: &str) -> Result<()>;
+
+	/// Export a key as an encrypted JSON key file, protected by its current password.
+	fn export_key(&self, key_type: KeyType, public: &Public, password: &str) -> Result<String>;
+
+	/// Import an encrypted JSON key file. The key type recorded in the file is used unless
+	/// another one is given. Returns the type and public key of the imported key.
+	fn import_key(&self, json: &str, password: &str, key_type: Option<KeyType>) -> Result<(KeyType, Public)>;
+}
+
+/// Key store keeping each key in an encrypted JSON file named by its public key.
+///
+/// Session keys live in the root directory, other key types in a sub-directory named after
+/// the type. Key files written by earlier versions, holding a BIP39 phrase protected by the
+/// password, are still read; changing their password converts them to the encrypted format.
 pub struct Store {
 	path: PathBuf,
 	additional: HashMap<Public, Pair>,
@@ -67,12 +165,10 @@ impl Store {
 		Ok(Store { path, additional: HashMap::new() })
 	}
 
-	/// Generate a new key, placing it into the store.
+	/// Generate a new session key, placing it into the store.
 	pub fn generate(&self, password: &str) -> Result<Pair> {
-		let (pair, phrase) = Pair::generate_with_phrase(Some(password));
-		let mut file = File::create(self.key_file_path(&pair.public()))?;
-		::serde_json::to_writer(&file, &phrase)?;
-		file.flush()?;
+		let pair = Pair::generate();
+		self.write_key(KeyType::Session, &pair, password)?;
 		Ok(pair)
 	}
 
@@ -84,27 +180,29 @@ impl Store {
 		Ok(pair)
 	}
 
-	/// Load a key file with given public key.
+	/// Load a session key with given public key.
 	pub fn load(&self, public: &Public, password: &str) -> Result<Pair> {
 		if let Some(pair) = self.additional.get(public) {
 			return Ok(pair.clone());
 		}
-		let path = self.key_file_path(public);
-		let file = File::open(path)?;
-
-		let phrase: String = ::serde_json::from_reader(&file)?;
-		let pair = Pair::from_phrase(&phrase, Some(password))
-			.map_err(|_| Error::from(ErrorKind::InvalidPhrase))?;
-		if &pair.public() != public {
-			bail!(ErrorKind::InvalidPassword);
-		}
-		Ok(pair)
+		self.read_key(KeyType::Session, public, password)
 	}
 
-	/// Get public keys of all stored keys.
+	/// Get public keys of all session keys.
 	pub fn contents(&self) -> Result<Vec<Public>> {
 		let mut public_keys: Vec<Public> = self.additional.keys().cloned().collect();
-		for entry in fs::read_dir(&self.path)? {
+		public_keys.extend(self.stored_keys(KeyType::Session)?);
+		Ok(public_keys)
+	}
+
+	fn stored_keys(&self, key_type: KeyType) -> Result<Vec<Public>> {
+		let dir = self.key_dir(key_type);
+		if !dir.exists() {
+			return Ok(Vec::new());
+		}
+
+		let mut public_keys = Vec::new();
+		for entry in fs::read_dir(&dir)? {
 			let entry = entry?;
 			let path = entry.path();
 
@@ -127,13 +225,92 @@ impl Store {
 		Ok(public_keys)
 	}
 
-	fn key_file_path(&self, public: &Public) -> PathBuf {
-		let mut buf = self.path.clone();
+	fn read_key(&self, key_type: KeyType, public: &Public, password: &str) -> Result<Pair> {
+		let contents = fs::read_to_string(self.key_file_path(key_type, public))?;
+		let pair = match serde_json::from_str(&contents)? {
+			// key files of earlier versions hold the phrase, with the password as BIP39 password
+			serde_json::Value::String(phrase) => Pair::from_phrase(&phrase, Some(password))
+				.map_err(|_| Error::from(ErrorKind::InvalidPhrase))?,
+			value => key_file::decrypt(&value, password)?.1,
+		};
+		if &pair.public() != public {
+			bail!(ErrorKind::InvalidPassword);
+		}
+		Ok(pair)
+	}
+
+	fn write_key(&self, key_type: KeyType, pair: &Pair, password: &str) -> Result<()> {
+		fs::create_dir_all(self.key_dir(key_type))?;
+		let path = self.key_file_path(key_type, &pair.public());
+		// write to a temporary file first, so an existing key is never left half written
+		let temp_path = path.with_extension("tmp");
+		fs::write(&temp_path, key_file::encrypt(key_type, pair, password))?;
+		fs::rename(&temp_path, &path)?;
+		Ok(())
+	}
+
+	fn key_dir(&self, key_type: KeyType) -> PathBuf {
+		match key_type {
+			KeyType::Session => self.path.clone(),
+			key_type => self.path.join(key_type.name()),
+		}
+	}
+
+	fn key_file_path(&self, key_type: KeyType, public: &Public) -> PathBuf {
+		let mut buf = self.key_dir(key_type);
 		buf.push(hex::encode(public.as_slice()));
 		buf
 	}
 }
 
+impl KeystoreBackend for Store {
+	fn generate_key(&self, key_type: KeyType, password: &str) -> Result<Public> {
+		let pair = Pair::generate();
+		self.write_key(key_type, &pair, password)?;
+		Ok(pair.public())
+	}
+
+	fn insert_key(&self, key_type: KeyType, pair: &Pair, password: &str) -> Result<()> {
+		self.write_key(key_type, pair, password)
+	}
+
+	fn keys(&self, key_type: KeyType) -> Result<Vec<Public>> {
+		match key_type {
+			KeyType::Session => self.contents(),
+			key_type => self.stored_keys(key_type),
+		}
+	}
+
+	fn load_key(&self, key_type: KeyType, public: &Public, password: &str) -> Result<Pair> {
+		match key_type {
+			KeyType::Session => self.load(public, password),
+			key_type => self.read_key(key_type, public, password),
+		}
+	}
+
+	fn remove_key(&self, key_type: KeyType, public: &Public) -> Result<()> {
+		fs::remove_file(self.key_file_path(key_type, public))?;
+		Ok(())
+	}
+
+	fn change_password(&self, key_type: KeyType, public: &Public, old: &str, new: &str) -> Result<()> {
+		let pair = self.read_key(key_type, public, old)?;
+		self.write_key(key_type, &pair, new)
+	}
+
+	fn export_key(&self, key_type: KeyType, public: &Public, password: &str) -> Result<String> {
+		let pair = self.read_key(key_type, public, password)?;
+		Ok(key_file::encrypt(key_type, &pair, password))
+	}
+
+	fn import_key(&self, json: &str, password: &str, key_type: Option<KeyType>) -> Result<(KeyType, Public)> {
+		let (file_key_type, pair) = key_file::decrypt(&serde_json::from_str(json)?, password)?;
+		let key_type = key_type.unwrap_or(file_key_type);
+		self.write_key(key_type, &pair, password)?;
+		Ok((key_type, pair.public()))
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -157,6 +334,65 @@ mod tests {
 		assert_eq!(store.contents().unwrap()[0], key.public());
 	}
 
+	#[test]
+	fn key_types_are_kept_apart() {
+		let temp_dir = TempDir::new("keystore").unwrap();
+		let store = Store::open(temp_dir.path().to_owned()).unwrap();
+
+		let session = store.generate("").unwrap().public();
+		let offchain = store.generate_key(KeyType::Offchain, "").unwrap();
+
+		assert_eq!(store.keys(KeyType::Session).unwrap(), vec![session.clone()]);
+		assert_eq!(store.keys(KeyType::Offchain).unwrap(), vec![offchain.clone()]);
+		assert!(store.keys(KeyType::Boxkeys).unwrap().is_empty());
+		assert!(store.load_key(KeyType::Session, &offchain, "").is_err());
+
+		store.remove_key(KeyType::Offchain, &offchain).unwrap();
+		assert!(store.keys(KeyType::Offchain).unwrap().is_empty());
+	}
+
+	#[test]
+	fn change_password() {
+		let temp_dir = TempDir::new("keystore").unwrap();
+		let store = Store::open(temp_dir.path().to_owned()).unwrap();
+
+		let public = store.generate_key(KeyType::Boxkeys, "old").unwrap();
+		assert!(store.change_password(KeyType::Boxkeys, &public, "wrong", "new").is_err());
+		store.change_password(KeyType::Boxkeys, &public, "old", "new").unwrap();
+
+		assert!(store.load_key(KeyType::Boxkeys, &public, "old").is_err());
+		assert_eq!(store.load_key(KeyType::Boxkeys, &public, "new").unwrap().public(), public);
+	}
+
+	#[test]
+	fn legacy_key_files_are_read_and_converted() {
+		let temp_dir = TempDir::new("keystore").unwrap();
+		let store = Store::open(temp_dir.path().to_owned()).unwrap();
+
+		let (pair, phrase) = Pair::generate_with_phrase(Some("password"));
+		let path = temp_dir.path().join(hex::encode(pair.public().as_slice()));
+		fs::write(&path, serde_json::to_string(&phrase).unwrap()).unwrap();
+
+		assert_eq!(store.load(&pair.public(), "password").unwrap().public(), pair.public());
+		store.change_password(KeyType::Session, &pair.public(), "password", "").unwrap();
+		assert_eq!(store.load(&pair.public(), "").unwrap().public(), pair.public());
+	}
+
+	#[test]
+	fn export_and_import() {
+		let source_dir = TempDir::new("keystore").unwrap();
+		let source = Store::open(source_dir.path().to_owned()).unwrap();
+		let target_dir = TempDir::new("keystore").unwrap();
+		let target = Store::open(target_dir.path().to_owned()).unwrap();
+
+		let public = source.generate_key(KeyType::Offchain, "secret").unwrap();
+		let json = source.export_key(KeyType::Offchain, &public, "secret").unwrap();
+
+		assert!(target.import_key(&json, "wrong", None).is_err());
+		assert_eq!(target.import_key(&json, "secret", None).unwrap(), (KeyType::Offchain, public.clone()));
+		assert_eq!(target.load_key(KeyType::Offchain, &public, "secret").unwrap().public(), public);
+	}
+
 	#[test]
 	fn test_generate_from_seed() {
 		let temp_dir = TempDir::new("keystore").unwrap();
@@ -18,14 +18,18 @@ use std::sync::Arc;
 use futures::{Stream, Future, sync::mpsc};
 use inherents::pool::InherentsPool;
 use log::{info, debug, warn};
+use keystore::{KeystoreBackend, KeyType};
 use parity_codec::Decode;
-use primitives::OffchainExt;
+use primitives::{OffchainExt, ed25519};
+use primitives::offchain::{OffchainStorage, HttpRequest, HttpResponse, HttpError};
 use runtime_primitives::{
 	generic::BlockId,
 	traits::{self, Extrinsic},
 };
 use transaction_pool::txpool::{Pool, ChainApi};
 
+use crate::http;
+
 /// A message between the offchain extension and the processing thread.
 enum ExtMessage {
 	SubmitExtrinsic(Vec<u8>),
@@ -34,11 +38,101 @@ enum ExtMessage {
 /// Asynchronous offchain API.
 ///
 /// NOTE this is done to prevent recursive calls into the runtime (which are not supported currently).
-pub(crate) struct AsyncApi(mpsc::UnboundedSender<ExtMessage>);
+/// Local storage, keystore and HTTP access do not call into the runtime, so they are served directly.
+pub(crate) struct AsyncApi {
+	sender: mpsc::UnboundedSender<ExtMessage>,
+	storage: Option<Arc<dyn OffchainStorage>>,
+	keystore: Option<Arc<dyn KeystoreBackend>>,
+	password: String,
+	http: Option<http::Api>,
+}
 
 impl OffchainExt for AsyncApi {
 	fn submit_extrinsic(&mut self, ext: Vec<u8>) {
-		let _ = self.0.unbounded_send(ExtMessage::SubmitExtrinsic(ext));
+		let _ = self.sender.unbounded_send(ExtMessage::SubmitExtrinsic(ext));
+	}
+
+	fn local_storage_get(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+		match self.storage {
+			Some(ref storage) => storage.get(key),
+			None => {
+				warn!("Offchain local storage is not available.");
+				None
+			},
+		}
+	}
+
+	fn local_storage_set(&mut self, key: &[u8], value: &[u8]) {
+		match self.storage {
+			Some(ref storage) => storage.set(key, value),
+			None => warn!("Offchain local storage is not available."),
+		}
+	}
+
+	fn local_storage_compare_and_set(
+		&mut self,
+		key: &[u8],
+		old_value: Option<&[u8]>,
+		new_value: &[u8],
+	) -> bool {
+		match self.storage {
+			Some(ref storage) => storage.compare_and_set(key, old_value, new_value),
+			None => {
+				warn!("Offchain local storage is not available.");
+				false
+			},
+		}
+	}
+
+	fn pubkeys(&mut self) -> Vec<[u8; 32]> {
+		let keystore = match self.keystore.as_ref() {
+			Some(keystore) => keystore,
+			None => return Vec::new(),
+		};
+		match offchain_keys(keystore) {
+			Ok(keys) => keys.into_iter().map(|key| key.0).collect(),
+			Err(e) => {
+				warn!("{}", e);
+				Vec::new()
+			},
+		}
+	}
+
+	fn sign(&mut self, key: [u8; 32], data: &[u8]) -> Option<[u8; 64]> {
+		let keystore = self.keystore.as_ref()?;
+		let public = ed25519::Public(key);
+		if let Err(e) = offchain_keys(keystore) {
+			warn!("{}", e);
+			return None;
+		}
+		match keystore.sign(KeyType::Offchain, &public, &self.password, data) {
+			Ok(signature) => Some(signature.0),
+			Err(e) => {
+				debug!("Unable to sign with offchain key {:?}: {:?}", public, e);
+				None
+			},
+		}
+	}
+
+	fn http_request(&mut self, request: HttpRequest) -> Result<HttpResponse, HttpError> {
+		match self.http {
+			Some(ref mut http) => http.request(request),
+			None => {
+				warn!("Offchain HTTP requests are not enabled.");
+				Err(HttpError::Unavailable)
+			},
+		}
+	}
+}
+
+/// The keys offchain workers sign with. Only the dedicated offchain keys are used, so that a
+/// worker can never sign with the session keys of the node.
+fn offchain_keys(keystore: &Arc<dyn KeystoreBackend>) -> Result<Vec<ed25519::Public>, String> {
+	match keystore.keys(KeyType::Offchain) {
+		Ok(ref keys) if keys.is_empty() =>
+			Err("No offchain keys in the keystore; offchain workers cannot sign.".into()),
+		Ok(keys) => Ok(keys),
+		Err(e) => Err(format!("Unable to read the offchain keys: {:?}", e)),
 	}
 }
 
@@ -54,6 +148,10 @@ impl<A: ChainApi> Api<A> {
 	pub fn new(
 		transaction_pool: Arc<Pool<A>>,
 		inherents_pool: Arc<InherentsPool<<A::Block as traits::Block>::Extrinsic>>,
+		storage: Option<Arc<dyn OffchainStorage>>,
+		keystore: Option<Arc<dyn KeystoreBackend>>,
+		password: String,
+		http: Option<http::Api>,
 		at: BlockId<A::Block>,
 	) -> (AsyncApi, Self) {
 		let (tx, rx) = mpsc::unbounded();
@@ -63,7 +161,7 @@ impl<A: ChainApi> Api<A> {
 			inherents_pool,
 			at,
 		};
-		(AsyncApi(tx), api)
+		(AsyncApi { sender: tx, storage, keystore, password, http }, api)
 	}
 
 	/// Run a processing task for the API
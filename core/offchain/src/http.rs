@@ -0,0 +1,273 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! HTTP requests for offchain workers.
+//!
+//! Requests are performed by an `HttpBackend`. Every offchain worker run gets its
+//! own `Api` handle, which applies the node limits from `HttpConfig`:
+//! a maximal deadline, a maximal response size and a number of requests per run.
+
+use std::{
+	collections::HashMap,
+	sync::{Arc, mpsc},
+	time::{Duration, Instant},
+};
+
+use futures::{Future, Stream};
+use hyper::{Body, Client, client::HttpConnector};
+use log::debug;
+use parking_lot::Mutex;
+use primitives::offchain::{HttpError, HttpMethod, HttpRequest, HttpResponse};
+use tokio::{runtime::TaskExecutor, timer::Timeout};
+
+/// Node-side limits applied to the requests of offchain workers.
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+	/// Number of requests a worker may issue during a single run.
+	pub max_requests: u32,
+	/// Largest response body accepted, in bytes.
+	pub max_response_size: u32,
+	/// Longest time a request may take.
+	pub max_deadline: Duration,
+}
+
+impl Default for HttpConfig {
+	fn default() -> Self {
+		HttpConfig {
+			max_requests: 16,
+			max_response_size: 1024 * 1024,
+			max_deadline: Duration::from_secs(10),
+		}
+	}
+}
+
+/// Something that can perform HTTP requests.
+pub trait HttpBackend: Send + Sync {
+	/// Perform the request, giving up at `deadline` or as soon as the response body
+	/// exceeds `max_response_size` bytes.
+	fn request(
+		&self,
+		request: HttpRequest,
+		deadline: Instant,
+		max_response_size: usize,
+	) -> Result<HttpResponse, HttpError>;
+}
+
+/// Per-run HTTP API, enforcing the node limits.
+pub(crate) struct Api {
+	backend: Arc<dyn HttpBackend>,
+	config: HttpConfig,
+	requests: u32,
+}
+
+impl Api {
+	pub fn new(backend: Arc<dyn HttpBackend>, config: HttpConfig) -> Self {
+		Api {
+			backend,
+			config,
+			requests: 0,
+		}
+	}
+
+	pub fn request(&mut self, request: HttpRequest) -> Result<HttpResponse, HttpError> {
+		if self.requests >= self.config.max_requests {
+			debug!("Offchain worker exceeded its quota of {} HTTP requests.", self.config.max_requests);
+			return Err(HttpError::QuotaExceeded);
+		}
+		self.requests += 1;
+
+		let max_deadline = self.config.max_deadline;
+		let deadline = match request.deadline {
+			0 => max_deadline,
+			ms => ::std::cmp::min(Duration::from_millis(ms), max_deadline),
+		};
+		let max_response_size = match request.max_response_size {
+			0 => self.config.max_response_size,
+			size => ::std::cmp::min(size, self.config.max_response_size),
+		};
+
+		self.backend.request(request, Instant::now() + deadline, max_response_size as usize)
+	}
+}
+
+/// HTTP backend performing requests with `hyper` on the node task executor.
+///
+/// Only plain `http` URIs are supported.
+pub struct HyperBackend {
+	client: Client<HttpConnector, Body>,
+	executor: TaskExecutor,
+}
+
+impl HyperBackend {
+	/// Create a new backend spawning its requests on given executor.
+	pub fn new(executor: TaskExecutor) -> Self {
+		HyperBackend {
+			client: Client::new(),
+			executor,
+		}
+	}
+}
+
+impl HttpBackend for HyperBackend {
+	fn request(
+		&self,
+		request: HttpRequest,
+		deadline: Instant,
+		max_response_size: usize,
+	) -> Result<HttpResponse, HttpError> {
+		let method = match request.method {
+			HttpMethod::Get => hyper::Method::GET,
+			HttpMethod::Post => hyper::Method::POST,
+			HttpMethod::Put => hyper::Method::PUT,
+			HttpMethod::Delete => hyper::Method::DELETE,
+		};
+		let uri = String::from_utf8(request.uri).map_err(|_| HttpError::InvalidRequest)?;
+		let mut builder = hyper::Request::builder();
+		builder.method(method).uri(uri.as_str());
+		for (name, value) in &request.headers {
+			builder.header(&name[..], &value[..]);
+		}
+		let http_request = builder.body(Body::from(request.body))
+			.map_err(|_| HttpError::InvalidRequest)?;
+
+		let response = self.client.request(http_request)
+			.map_err(|e| {
+				debug!("Offchain HTTP request failed: {:?}", e);
+				HttpError::Io
+			})
+			.and_then(move |response| {
+				let status = response.status().as_u16();
+				let headers = response.headers().iter()
+					.map(|(name, value)| (name.as_str().as_bytes().to_vec(), value.as_bytes().to_vec()))
+					.collect();
+				response.into_body()
+					.map_err(|_| HttpError::Io)
+					.fold(Vec::new(), move |mut body, chunk| {
+						if body.len() + chunk.len() > max_response_size {
+							return Err(HttpError::ResponseTooLarge);
+						}
+						body.extend_from_slice(&chunk);
+						Ok(body)
+					})
+					.map(move |body| HttpResponse { status, headers, body })
+			});
+
+		let (tx, rx) = mpsc::channel();
+		self.executor.spawn(Timeout::new_at(response, deadline).then(move |result| {
+			let result = result.map_err(|e| e.into_inner().unwrap_or(HttpError::DeadlineReached));
+			let _ = tx.send(result);
+			Ok(())
+		}));
+
+		// The executor drops the task (and the sender) if it shuts down.
+		rx.recv().unwrap_or(Err(HttpError::DeadlineReached))
+	}
+}
+
+/// HTTP backend serving canned responses, standing in for a remote server in tests.
+#[derive(Default)]
+pub struct MockBackend {
+	responses: Mutex<HashMap<Vec<u8>, HttpResponse>>,
+	requests: Mutex<Vec<HttpRequest>>,
+}
+
+impl MockBackend {
+	/// Serve `response` to every request made to `uri`.
+	pub fn respond(&self, uri: &str, response: HttpResponse) {
+		self.responses.lock().insert(uri.as_bytes().to_vec(), response);
+	}
+
+	/// Requests received so far, oldest first.
+	pub fn requests(&self) -> Vec<HttpRequest> {
+		self.requests.lock().clone()
+	}
+}
+
+impl HttpBackend for MockBackend {
+	fn request(
+		&self,
+		request: HttpRequest,
+		deadline: Instant,
+		max_response_size: usize,
+	) -> Result<HttpResponse, HttpError> {
+		let response = self.responses.lock().get(&request.uri).cloned();
+		self.requests.lock().push(request);
+
+		if Instant::now() >= deadline {
+			return Err(HttpError::DeadlineReached);
+		}
+		let response = response.ok_or(HttpError::Io)?;
+		if response.body.len() > max_response_size {
+			return Err(HttpError::ResponseTooLarge);
+		}
+		Ok(response)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn get(uri: &str) -> HttpRequest {
+		HttpRequest {
+			method: HttpMethod::Get,
+			uri: uri.as_bytes().to_vec(),
+			headers: vec![],
+			body: vec![],
+			deadline: 0,
+			max_response_size: 0,
+		}
+	}
+
+	fn ok(body: &[u8]) -> HttpResponse {
+		HttpResponse { status: 200, headers: vec![], body: body.to_vec() }
+	}
+
+	#[test]
+	fn should_serve_mocked_response_and_record_request() {
+		let backend = Arc::new(MockBackend::default());
+		backend.respond("http://localhost/rates", ok(b"1.25"));
+		let mut api = Api::new(backend.clone(), HttpConfig::default());
+
+		assert_eq!(api.request(get("http://localhost/rates")), Ok(ok(b"1.25")));
+		assert_eq!(api.request(get("http://localhost/unknown")), Err(HttpError::Io));
+		assert_eq!(backend.requests().len(), 2);
+	}
+
+	#[test]
+	fn should_enforce_request_quota() {
+		let backend = Arc::new(MockBackend::default());
+		backend.respond("http://localhost/rates", ok(b"1.25"));
+		let config = HttpConfig { max_requests: 1, ..Default::default() };
+		let mut api = Api::new(backend.clone(), config);
+
+		assert!(api.request(get("http://localhost/rates")).is_ok());
+		assert_eq!(api.request(get("http://localhost/rates")), Err(HttpError::QuotaExceeded));
+		assert_eq!(backend.requests().len(), 1);
+	}
+
+	#[test]
+	fn should_cap_response_size_to_node_limit() {
+		let backend = Arc::new(MockBackend::default());
+		backend.respond("http://localhost/invoice", ok(&[0u8; 64]));
+		let config = HttpConfig { max_response_size: 32, ..Default::default() };
+		let mut api = Api::new(backend, config);
+
+		let mut request = get("http://localhost/invoice");
+		request.max_response_size = 128;
+		assert_eq!(api.request(request), Err(HttpError::ResponseTooLarge));
+	}
+}
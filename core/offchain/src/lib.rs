@@ -21,6 +21,9 @@
 //! it's able to asynchronously submit extrinsics that will either
 //! be propagated to other nodes (transactions) or will be
 //! added to the next block produced by the node as inherents.
+//! Workers can also keep data between runs in a node-local storage,
+//! sign payloads with the keys held in the node keystore and fetch
+//! external data over HTTP, within limits set by the node.
 //!
 //! Offchain workers can be used for computation-heavy tasks
 //! that are not feasible for execution during regular block processing.
@@ -34,14 +37,16 @@
 #![warn(missing_docs)]
 
 use std::{
+	fmt,
 	marker::PhantomData,
 	sync::Arc,
 };
 
 use client::runtime_api::ApiExt;
 use inherents::pool::InherentsPool;
+use keystore::KeystoreBackend;
 use log::{debug, warn};
-use primitives::ExecutionContext;
+use primitives::{ExecutionContext, offchain::OffchainStorage};
 use runtime_primitives::{
 	generic::BlockId,
 	traits::{self, ProvideRuntimeApi},
@@ -50,32 +55,73 @@ use tokio::runtime::TaskExecutor;
 use transaction_pool::txpool::{Pool, ChainApi};
 
 mod api;
+pub mod http;
+pub mod testing;
 
 pub use offchain_primitives::OffchainWorkerApi;
 
 /// An offchain workers manager.
-#[derive(Debug)]
 pub struct OffchainWorkers<C, Block: traits::Block> {
 	client: Arc<C>,
 	inherents_pool: Arc<InherentsPool<<Block as traits::Block>::Extrinsic>>,
+	storage: Option<Arc<dyn OffchainStorage>>,
+	keystore: Option<Arc<dyn KeystoreBackend>>,
+	password: String,
+	http: Option<Arc<dyn http::HttpBackend>>,
+	http_config: http::HttpConfig,
 	executor: TaskExecutor,
 	_block: PhantomData<Block>,
 }
 
+impl<C, Block: traits::Block> fmt::Debug for OffchainWorkers<C, Block> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("OffchainWorkers")
+			.field("has_storage", &self.storage.is_some())
+			.field("has_keystore", &self.keystore.is_some())
+			.field("has_http", &self.http.is_some())
+			.field("http_config", &self.http_config)
+			.finish()
+	}
+}
+
 impl<C, Block: traits::Block> OffchainWorkers<C, Block> {
 	/// Creates new `OffchainWorkers`.
+	///
+	/// `storage` is the node-local storage, `keystore` the source of signing keys
+	/// and `http` the backend of HTTP requests exposed to the workers; any may be
+	/// omitted, making the related calls no-ops.
 	pub fn new(
 		client: Arc<C>,
 		inherents_pool: Arc<InherentsPool<<Block as traits::Block>::Extrinsic>>,
+		storage: Option<Arc<dyn OffchainStorage>>,
+		keystore: Option<Arc<dyn KeystoreBackend>>,
+		http: Option<Arc<dyn http::HttpBackend>>,
 		executor: TaskExecutor,
 	) -> Self {
 		Self {
 			client,
 			inherents_pool,
+			storage,
+			keystore,
+			password: String::new(),
+			http,
+			http_config: Default::default(),
 			executor,
 			_block: PhantomData,
 		}
 	}
+
+	/// Sets the password the keys in `keystore` are protected with. Defaults to an empty password.
+	pub fn with_password(mut self, password: String) -> Self {
+		self.password = password;
+		self
+	}
+
+	/// Replaces the default limits applied to HTTP requests.
+	pub fn with_http_config(mut self, http_config: http::HttpConfig) -> Self {
+		self.http_config = http_config;
+		self
+	}
 }
 
 impl<C, Block> OffchainWorkers<C, Block> where
@@ -97,7 +143,15 @@ impl<C, Block> OffchainWorkers<C, Block> where
 		debug!("Checking offchain workers at {:?}: {:?}", at, has_api);
 
 		if has_api.unwrap_or(false) {
-			let (api, runner) = api::Api::new(pool.clone(), self.inherents_pool.clone(), at.clone());
+			let (api, runner) = api::Api::new(
+				pool.clone(),
+				self.inherents_pool.clone(),
+				self.storage.clone(),
+				self.keystore.clone(),
+				self.password.clone(),
+				self.http.clone().map(|backend| http::Api::new(backend, self.http_config.clone())),
+				at.clone(),
+			);
 			self.executor.spawn(runner.process());
 
 			debug!("Running offchain workers at {:?}", at);
@@ -122,7 +176,7 @@ mod tests {
 		let inherents = Arc::new(InherentsPool::default());
 
 		// when
-		let offchain = OffchainWorkers::new(client, inherents.clone(), runtime.executor());
+		let offchain = OffchainWorkers::new(client, inherents.clone(), None, None, None, runtime.executor());
 		offchain.on_block_imported(&0u64, &pool);
 
 		// then
@@ -0,0 +1,128 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Offchain extensions for runtime tests.
+//!
+//! `TestOffchainExt` can be installed into `TestExternalities`, so that runtime
+//! offchain worker code can be exercised against a `MockBackend` HTTP server.
+
+use std::sync::Arc;
+
+use primitives::{OffchainExt, Pair, ed25519};
+use primitives::offchain::{
+	InMemOffchainStorage, OffchainStorage, HttpRequest, HttpResponse, HttpError,
+};
+
+use crate::http::{self, HttpConfig, MockBackend};
+
+/// Offchain extensions backed by in-memory storage and a mocked HTTP server.
+pub struct TestOffchainExt {
+	storage: InMemOffchainStorage,
+	keys: Vec<ed25519::Pair>,
+	http: http::Api,
+	/// Extrinsics submitted by the worker, oldest first.
+	pub extrinsics: Vec<Vec<u8>>,
+}
+
+impl TestOffchainExt {
+	/// Create new extensions answering HTTP requests from `server`.
+	pub fn new(server: Arc<MockBackend>) -> Self {
+		Self::with_http_config(server, Default::default())
+	}
+
+	/// Create new extensions answering HTTP requests from `server` within given limits.
+	pub fn with_http_config(server: Arc<MockBackend>, config: HttpConfig) -> Self {
+		TestOffchainExt {
+			storage: Default::default(),
+			keys: Vec::new(),
+			http: http::Api::new(server, config),
+			extrinsics: Vec::new(),
+		}
+	}
+
+	/// Make `pair` available for signing.
+	pub fn add_key(&mut self, pair: ed25519::Pair) {
+		self.keys.push(pair);
+	}
+}
+
+impl OffchainExt for TestOffchainExt {
+	fn submit_extrinsic(&mut self, extrinsic: Vec<u8>) {
+		self.extrinsics.push(extrinsic);
+	}
+
+	fn local_storage_get(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+		self.storage.get(key)
+	}
+
+	fn local_storage_set(&mut self, key: &[u8], value: &[u8]) {
+		self.storage.set(key, value)
+	}
+
+	fn local_storage_compare_and_set(
+		&mut self,
+		key: &[u8],
+		old_value: Option<&[u8]>,
+		new_value: &[u8],
+	) -> bool {
+		self.storage.compare_and_set(key, old_value, new_value)
+	}
+
+	fn pubkeys(&mut self) -> Vec<[u8; 32]> {
+		self.keys.iter().map(|pair| pair.public().0).collect()
+	}
+
+	fn sign(&mut self, key: [u8; 32], data: &[u8]) -> Option<[u8; 64]> {
+		self.keys.iter()
+			.find(|pair| pair.public().0 == key)
+			.map(|pair| pair.sign(data).0)
+	}
+
+	fn http_request(&mut self, request: HttpRequest) -> Result<HttpResponse, HttpError> {
+		self.http.request(request)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use primitives::offchain::HttpMethod;
+	use runtime_io::{with_externalities, TestExternalities};
+
+	#[test]
+	fn runtime_should_fetch_from_mock_server() {
+		let server = Arc::new(MockBackend::default());
+		server.respond("http://localhost/rates/EUR", HttpResponse {
+			status: 200,
+			headers: vec![],
+			body: b"1.0843".to_vec(),
+		});
+		let mut t = TestExternalities::<primitives::Blake2Hasher>::default();
+		t.set_offchain_externalities(TestOffchainExt::new(server.clone()));
+
+		let response = with_externalities(&mut t, || runtime_io::http_request(&HttpRequest {
+			method: HttpMethod::Get,
+			uri: b"http://localhost/rates/EUR".to_vec(),
+			headers: vec![],
+			body: vec![],
+			deadline: 500,
+			max_response_size: 0,
+		}));
+
+		assert_eq!(response.map(|r| r.body), Ok(b"1.0843".to_vec()));
+		assert_eq!(server.requests().len(), 1);
+	}
+}
@@ -49,6 +49,7 @@ pub mod hashing;
 pub use hashing::{blake2_128, blake2_256, twox_64, twox_128, twox_256};
 #[cfg(feature = "std")]
 pub mod hexdisplay;
+pub mod offchain;
 pub mod crypto;
 
 pub mod u32_trait;
@@ -98,11 +99,75 @@ pub trait OffchainExt {
 	/// The extrinsic will either go to the pool (signed)
 	/// or to the next produced block (inherent).
 	fn submit_extrinsic(&mut self, extrinsic: Vec<u8>);
+
+	/// Retrieves a value from the node-local persistent storage.
+	fn local_storage_get(&mut self, key: &[u8]) -> Option<Vec<u8>>;
+
+	/// Sets a value in the node-local persistent storage.
+	fn local_storage_set(&mut self, key: &[u8], value: &[u8]);
+
+	/// Sets a value in the node-local persistent storage if the current value
+	/// matches `old_value` (`None` meaning the key is not set).
+	///
+	/// Returns `true` if the value has been set.
+	fn local_storage_compare_and_set(
+		&mut self,
+		key: &[u8],
+		old_value: Option<&[u8]>,
+		new_value: &[u8],
+	) -> bool;
+
+	/// Returns the ed25519 public keys held by the node keystore.
+	fn pubkeys(&mut self) -> Vec<[u8; 32]>;
+
+	/// Signs `data` with the keystore key matching given ed25519 public key.
+	///
+	/// Returns `None` if the key is not in the keystore.
+	fn sign(&mut self, key: [u8; 32], data: &[u8]) -> Option<[u8; 64]>;
+
+	/// Performs an HTTP request and waits for the response, within the node's
+	/// deadline, response size and per-run quota limits.
+	fn http_request(
+		&mut self,
+		request: offchain::HttpRequest,
+	) -> Result<offchain::HttpResponse, offchain::HttpError>;
 }
 impl<T: OffchainExt + ?Sized> OffchainExt for Box<T> {
 	fn submit_extrinsic(&mut self, ex: Vec<u8>) {
 		(&mut **self).submit_extrinsic(ex)
 	}
+
+	fn local_storage_get(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+		(&mut **self).local_storage_get(key)
+	}
+
+	fn local_storage_set(&mut self, key: &[u8], value: &[u8]) {
+		(&mut **self).local_storage_set(key, value)
+	}
+
+	fn local_storage_compare_and_set(
+		&mut self,
+		key: &[u8],
+		old_value: Option<&[u8]>,
+		new_value: &[u8],
+	) -> bool {
+		(&mut **self).local_storage_compare_and_set(key, old_value, new_value)
+	}
+
+	fn pubkeys(&mut self) -> Vec<[u8; 32]> {
+		(&mut **self).pubkeys()
+	}
+
+	fn sign(&mut self, key: [u8; 32], data: &[u8]) -> Option<[u8; 64]> {
+		(&mut **self).sign(key, data)
+	}
+
+	fn http_request(
+		&mut self,
+		request: offchain::HttpRequest,
+	) -> Result<offchain::HttpResponse, offchain::HttpError> {
+		(&mut **self).http_request(request)
+	}
 }
 
 /// Hex-serialized shim for `Vec<u8>`.
@@ -0,0 +1,155 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Offchain workers types: local storage and HTTP requests.
+
+use rstd::prelude::*;
+use parity_codec::{Encode, Decode};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+
+/// HTTP request method.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum HttpMethod {
+	/// `GET` request.
+	Get,
+	/// `POST` request.
+	Post,
+	/// `PUT` request.
+	Put,
+	/// `DELETE` request.
+	Delete,
+}
+
+/// An HTTP request issued by an offchain worker.
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct HttpRequest {
+	/// Request method.
+	pub method: HttpMethod,
+	/// UTF-8 encoded absolute URI.
+	pub uri: Vec<u8>,
+	/// Request headers as name/value pairs.
+	pub headers: Vec<(Vec<u8>, Vec<u8>)>,
+	/// Request body.
+	pub body: Vec<u8>,
+	/// Milliseconds after which the request is abandoned.
+	///
+	/// `0` means the longest deadline allowed by the node.
+	pub deadline: u64,
+	/// Largest accepted response body, in bytes.
+	///
+	/// `0` means the largest size allowed by the node.
+	pub max_response_size: u32,
+}
+
+/// A response to an HTTP request.
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct HttpResponse {
+	/// Status code.
+	pub status: u16,
+	/// Response headers as name/value pairs.
+	pub headers: Vec<(Vec<u8>, Vec<u8>)>,
+	/// Response body.
+	pub body: Vec<u8>,
+}
+
+/// Reason an HTTP request failed.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum HttpError {
+	/// The response has not been received before the deadline.
+	DeadlineReached,
+	/// The response body is larger than allowed.
+	ResponseTooLarge,
+	/// The worker has used up its request quota for this run.
+	QuotaExceeded,
+	/// The request is malformed (e.g. invalid URI or header).
+	InvalidRequest,
+	/// Connecting to the server or reading the response failed.
+	Io,
+	/// HTTP requests are not enabled on this node.
+	Unavailable,
+}
+
+/// Persistent key-value storage local to the node, available to offchain workers.
+///
+/// The content is not part of the chain state and is never shared with other nodes.
+#[cfg(feature = "std")]
+pub trait OffchainStorage: Send + Sync {
+	/// Retrieve the value stored under given key.
+	fn get(&self, key: &[u8]) -> Option<Vec<u8>>;
+
+	/// Persist a value under given key, overwriting any previous one.
+	fn set(&self, key: &[u8], value: &[u8]);
+
+	/// Replace the value under given key with `new_value` only if the current value
+	/// is `old_value` (`None` meaning the key is not present).
+	///
+	/// Returns `true` if the value was replaced.
+	fn compare_and_set(&self, key: &[u8], old_value: Option<&[u8]>, new_value: &[u8]) -> bool;
+}
+
+/// In-memory storage for offchain workers.
+///
+/// Content is lost when the node stops; meant for light clients and tests.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct InMemOffchainStorage {
+	storage: Mutex<HashMap<Vec<u8>, Vec<u8>>>,
+}
+
+#[cfg(feature = "std")]
+impl OffchainStorage for InMemOffchainStorage {
+	fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.storage.lock().expect("offchain storage lock poisoned").get(key).cloned()
+	}
+
+	fn set(&self, key: &[u8], value: &[u8]) {
+		self.storage.lock().expect("offchain storage lock poisoned").insert(key.to_vec(), value.to_vec());
+	}
+
+	fn compare_and_set(&self, key: &[u8], old_value: Option<&[u8]>, new_value: &[u8]) -> bool {
+		let mut storage = self.storage.lock().expect("offchain storage lock poisoned");
+		if storage.get(key).map(|v| &v[..]) != old_value {
+			return false;
+		}
+		storage.insert(key.to_vec(), new_value.to_vec());
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn compare_and_set_only_replaces_expected_value() {
+		let storage = InMemOffchainStorage::default();
+
+		assert!(!storage.compare_and_set(b"key", Some(b"old"), b"new"));
+		assert!(storage.compare_and_set(b"key", None, b"first"));
+		assert_eq!(storage.get(b"key"), Some(b"first".to_vec()));
+
+		assert!(!storage.compare_and_set(b"key", None, b"second"));
+		assert!(storage.compare_and_set(b"key", Some(b"first"), b"second"));
+		assert_eq!(storage.get(b"key"), Some(b"second".to_vec()));
+	}
+}
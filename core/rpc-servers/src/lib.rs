@@ -18,64 +18,151 @@
 
 #[warn(missing_docs)]
 
+mod rate_limit;
+
 pub use substrate_rpc as apis;
 
-use std::io;
+use std::{collections::HashMap, io};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use log::error;
 use sr_primitives::{traits::{Block as BlockT, NumberFor}, generic::SignedBlock};
+use rate_limit::{Gate, RateLimiter};
 
 /// Maximal payload accepted by RPC servers
 const MAX_PAYLOAD: usize = 15 * 1024 * 1024;
 
+/// Default maximum number of concurrent WS connections.
+const WS_MAX_CONNECTIONS: usize = 100;
+
+/// Methods that expose node internals or locally held keys and are hidden from public nodes.
+pub const UNSAFE_METHODS: &[&str] = &[
+	"system_peers",
+	"system_networkState",
+	"author_pendingExtrinsics",
+	"messaging_readMessage",
+];
+
+/// Which RPC methods are exposed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcMethods {
+	/// Hide `UNSAFE_METHODS`.
+	Safe,
+	/// Expose every method.
+	Unsafe,
+}
+
+impl Default for RpcMethods {
+	fn default() -> Self {
+		RpcMethods::Unsafe
+	}
+}
+
+/// Access restrictions shared by the HTTP and WS servers.
+#[derive(Debug, Clone, Default)]
+pub struct ServerConfig {
+	/// Origins allowed to make cross-origin requests. `None` allows any origin.
+	pub cors: Option<Vec<String>>,
+	/// Maximal number of requests per second per client. `None` disables the limit.
+	pub rate_limit: Option<u32>,
+	/// Reverse proxies whose forwarded client addresses are used for rate limiting.
+	pub trusted_proxies: Vec<IpAddr>,
+	/// Maximal number of concurrent WS connections. `None` uses the default.
+	pub max_connections: Option<usize>,
+}
+
 type Metadata = apis::metadata::Metadata;
 type RpcHandler = pubsub::PubSubHandler<Metadata>;
-pub type HttpServer = http::Server;
-pub type WsServer = ws::Server;
+
+/// A running RPC server, and the gate in front of it when requests are rate limited.
+pub struct Server<S> {
+	_gate: Option<Gate>,
+	_server: S,
+}
+
+pub type HttpServer = Server<http::Server>;
+pub type WsServer = Server<ws::Server>;
 
 /// Construct rpc `IoHandler`
-pub fn rpc_handler<Block: BlockT, ExHash, S, C, A, Y>(
+pub fn rpc_handler<Block: BlockT, ExHash, AccountId, S, C, A, Y, B, M, T>(
 	state: S,
 	chain: C,
 	author: A,
 	system: Y,
+	bonsai: B,
+	messaging: M,
+	totem: T,
+	methods: RpcMethods,
 ) -> RpcHandler where
 	Block: BlockT + 'static,
 	ExHash: Send + Sync + 'static + sr_primitives::Serialize + sr_primitives::DeserializeOwned,
+	AccountId: Send + Sync + 'static + sr_primitives::Serialize + sr_primitives::DeserializeOwned,
 	S: apis::state::StateApi<Block::Hash, Metadata=Metadata>,
 	C: apis::chain::ChainApi<NumberFor<Block>, Block::Hash, Block::Header, SignedBlock<Block>, Metadata=Metadata>,
 	A: apis::author::AuthorApi<ExHash, Block::Hash, Metadata=Metadata>,
 	Y: apis::system::SystemApi<Block::Hash, NumberFor<Block>>,
+	B: apis::bonsai::BonsaiApi<Block::Hash, NumberFor<Block>>,
+	M: apis::messaging::MessagingApi<Block::Hash, NumberFor<Block>, AccountId>,
+	T: apis::totem::TotemApi<Block::Hash, AccountId>,
 {
 	let mut io = pubsub::PubSubHandler::default();
-	io.extend_with(state.to_delegate());
-	io.extend_with(chain.to_delegate());
-	io.extend_with(author.to_delegate());
-	io.extend_with(system.to_delegate());
+	extend_with(&mut io, state.to_delegate(), methods);
+	extend_with(&mut io, chain.to_delegate(), methods);
+	extend_with(&mut io, author.to_delegate(), methods);
+	extend_with(&mut io, system.to_delegate(), methods);
+	extend_with(&mut io, bonsai.to_delegate(), methods);
+	extend_with(&mut io, messaging.to_delegate(), methods);
+	extend_with(&mut io, totem.to_delegate(), methods);
 	io
 }
 
+/// Add the methods of `delegate` to `io`, leaving out unsafe ones unless allowed.
+fn extend_with<D>(io: &mut RpcHandler, delegate: D, methods: RpcMethods) where
+	D: Into<HashMap<String, jsonrpc_core::RemoteProcedure<Metadata>>>,
+{
+	let mut delegate = delegate.into();
+	if methods == RpcMethods::Safe {
+		delegate.retain(|name, _| !UNSAFE_METHODS.contains(&name.as_str()));
+	}
+	io.extend_with(delegate);
+}
+
 /// Start HTTP server listening on given address.
+///
+/// With a rate limit the server listens locally behind a `Gate`, and closes connections after
+/// each response so that every request is counted.
 pub fn start_http(
 	addr: &std::net::SocketAddr,
+	config: &ServerConfig,
 	io: RpcHandler,
-) -> io::Result<http::Server> {
-	http::ServerBuilder::new(io)
+) -> io::Result<HttpServer> {
+	let server = http::ServerBuilder::new(io)
 		.threads(4)
 		.health_api(("/health", "system_health"))
-		.rest_api(http::RestApi::Unsecure)
-		.cors(http::DomainsValidation::Disabled)
+		.allowed_hosts(hosts_filtering(config.cors.is_some(), addr))
+		.rest_api(if config.cors.is_some() { http::RestApi::Secure } else { http::RestApi::Unsecure })
+		.cors(map_cors::<http::AccessControlAllowOrigin>(config.cors.as_ref()))
+		.keep_alive(config.rate_limit.is_none())
 		.max_request_body_size(MAX_PAYLOAD)
-		.start_http(addr)
+		.start_http(&listen_address(addr, config))?;
+	let gate = start_gate(addr, *server.address(), config)?;
+	Ok(Server { _gate: gate, _server: server })
 }
 
 /// Start WS server listening on given address.
+///
+/// The rate limit applies to connection attempts, so a client cannot exhaust
+/// the connection cap by reconnecting in a loop.
 pub fn start_ws(
 	addr: &std::net::SocketAddr,
+	config: &ServerConfig,
 	io: RpcHandler,
-) -> io::Result<ws::Server> {
-	ws::ServerBuilder::with_meta_extractor(io, |context: &ws::RequestContext| Metadata::new(context.sender()))
+) -> io::Result<WsServer> {
+	let server = ws::ServerBuilder::with_meta_extractor(io, |context: &ws::RequestContext| Metadata::new(context.sender()))
 		.max_payload(MAX_PAYLOAD)
-		.start(addr)
+		.max_connections(config.max_connections.unwrap_or(WS_MAX_CONNECTIONS))
+		.allowed_origins(map_cors(config.cors.as_ref()))
+		.allowed_hosts(hosts_filtering(config.cors.is_some(), addr))
+		.start(&listen_address(addr, config))
 		.map_err(|err| match err {
 			ws::Error(ws::ErrorKind::Io(io), _) => io,
 			ws::Error(ws::ErrorKind::ConnectionClosed, _) => io::ErrorKind::BrokenPipe.into(),
@@ -83,5 +170,42 @@ pub fn start_ws(
 				error!("{}", e);
 				io::ErrorKind::Other.into()
 			}
-		})
+		})?;
+	let gate = start_gate(addr, *server.addr(), config)?;
+	Ok(Server { _gate: gate, _server: server })
+}
+
+/// The address a server binds to: `addr`, or a free local port when a gate listens on `addr`.
+fn listen_address(addr: &SocketAddr, config: &ServerConfig) -> SocketAddr {
+	match config.rate_limit {
+		Some(_) => SocketAddr::new(if addr.is_ipv4() { Ipv4Addr::LOCALHOST.into() } else { Ipv6Addr::LOCALHOST.into() }, 0),
+		None => *addr,
+	}
+}
+
+/// Start the gate limiting the connections to a server listening on `backend`, if there is a limit.
+fn start_gate(addr: &SocketAddr, backend: SocketAddr, config: &ServerConfig) -> io::Result<Option<Gate>> {
+	match config.rate_limit {
+		Some(limit) => Gate::start(addr, backend, RateLimiter::new(limit), config.trusted_proxies.clone()).map(Some),
+		None => Ok(None),
+	}
+}
+
+/// Turn the configured origins into a domain validation. `None` disables the check.
+fn map_cors<T: for<'a> From<&'a str>>(cors: Option<&Vec<String>>) -> http::DomainsValidation<T> {
+	cors.map(|origins| origins.iter().map(AsRef::as_ref).map(Into::into).collect::<Vec<_>>()).into()
+}
+
+/// Only accept the public address as `Host` once origins are restricted.
+fn hosts_filtering(enable: bool, addr: &SocketAddr) -> http::DomainsValidation<http::Host> {
+	if enable {
+		// The server also allows the address it listens on, which differs behind a gate.
+		let mut hosts: Vec<http::Host> = vec![addr.to_string().as_str().into()];
+		if addr.ip().is_loopback() {
+			hosts.push(format!("localhost:{}", addr.port()).as_str().into());
+		}
+		http::DomainsValidation::AllowOnly(hosts)
+	} else {
+		http::DomainsValidation::Disabled
+	}
 }
@@ -0,0 +1,290 @@
+// Copyright 2017-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Per-client rate limiting for the RPC servers.
+//!
+//! The RPC servers do not expose the peer address of a request, so limiting is done by a
+//! `Gate` in front of them. The gate accepts connections on the public address, counts
+//! them per client and relays the accepted ones to the server, which then only listens
+//! locally. A client is the peer address of the connection, unless the peer is a trusted
+//! reverse proxy, in which case it is the client address the proxy forwards.
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use log::debug;
+
+/// Length of a single rate limiting window.
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Number of tracked clients above which expired windows are dropped.
+const PRUNE_THRESHOLD: usize = 4096;
+
+/// Longest request head read from a trusted proxy to find the forwarded client address.
+const MAX_HEAD: usize = 8 * 1024;
+
+/// Time a trusted proxy has to send the request head.
+const HEAD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Response sent to clients over the limit, before closing the connection.
+const TOO_MANY_REQUESTS: &[u8] = b"HTTP/1.1 429 Too Many Requests\r\n\
+	Content-Type: text/plain; charset=utf-8\r\n\
+	Content-Length: 19\r\n\
+	Connection: close\r\n\r\n\
+	Too many requests.\n";
+
+/// Fixed-window request counter keyed by client address.
+pub struct RateLimiter {
+	limit: u32,
+	clients: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+	/// Create a limiter allowing `limit` requests per second per client.
+	pub fn new(limit: u32) -> Self {
+		RateLimiter {
+			limit,
+			clients: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Record a request from `client`, returning `false` if it exceeds the limit.
+	pub fn check(&self, client: &str) -> bool {
+		self.check_at(client, Instant::now())
+	}
+
+	fn check_at(&self, client: &str, now: Instant) -> bool {
+		let mut clients = match self.clients.lock() {
+			Ok(clients) => clients,
+			Err(poisoned) => poisoned.into_inner(),
+		};
+
+		if clients.len() > PRUNE_THRESHOLD {
+			clients.retain(|_, (start, _)| now.duration_since(*start) < WINDOW);
+		}
+
+		let entry = clients.entry(client.to_owned()).or_insert((now, 0));
+		if now.duration_since(entry.0) >= WINDOW {
+			*entry = (now, 0);
+		}
+		if entry.1 >= self.limit {
+			return false;
+		}
+		entry.1 += 1;
+		true
+	}
+}
+
+/// Rate limiting relay in front of an RPC server. Stops accepting connections when dropped.
+pub struct Gate {
+	addr: SocketAddr,
+	closed: Arc<AtomicBool>,
+}
+
+impl Gate {
+	/// Listen on `addr` and relay the connections within the limit to `backend`.
+	pub fn start(
+		addr: &SocketAddr,
+		backend: SocketAddr,
+		limiter: RateLimiter,
+		trusted_proxies: Vec<IpAddr>,
+	) -> io::Result<Gate> {
+		let listener = TcpListener::bind(addr)?;
+		let addr = listener.local_addr()?;
+		let closed = Arc::new(AtomicBool::new(false));
+		let limiter = Arc::new(limiter);
+		let trusted_proxies = Arc::new(trusted_proxies);
+
+		let accepting = closed.clone();
+		thread::Builder::new().name("rpc-gate".into()).spawn(move || {
+			for stream in listener.incoming() {
+				if accepting.load(Ordering::SeqCst) {
+					break;
+				}
+				let stream = match stream {
+					Ok(stream) => stream,
+					Err(e) => {
+						debug!(target: "rpc", "Failed to accept RPC connection: {}", e);
+						continue;
+					},
+				};
+				let limiter = limiter.clone();
+				let trusted_proxies = trusted_proxies.clone();
+				let relay = thread::Builder::new().name("rpc-relay".into()).spawn(move || {
+					if let Err(e) = relay(stream, backend, &limiter, &trusted_proxies) {
+						debug!(target: "rpc", "RPC relay failed: {}", e);
+					}
+				});
+				if let Err(e) = relay {
+					debug!(target: "rpc", "Failed to start RPC relay: {}", e);
+				}
+			}
+		})?;
+
+		Ok(Gate { addr, closed })
+	}
+
+	/// The address the gate listens on.
+	pub fn address(&self) -> &SocketAddr {
+		&self.addr
+	}
+}
+
+impl Drop for Gate {
+	fn drop(&mut self) {
+		self.closed.store(true, Ordering::SeqCst);
+		// Wake up the accepting thread so that it sees the flag.
+		let mut addr = self.addr;
+		if addr.ip().is_unspecified() {
+			addr.set_ip(match addr {
+				SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+				SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+			});
+		}
+		let _ = TcpStream::connect(addr);
+	}
+}
+
+/// Check the client of `stream` against the limit and relay the connection to `backend`.
+fn relay(mut stream: TcpStream, backend: SocketAddr, limiter: &RateLimiter, trusted_proxies: &[IpAddr]) -> io::Result<()> {
+	let peer = stream.peer_addr()?.ip();
+	let mut head = Vec::new();
+	let client = if trusted_proxies.contains(&peer) {
+		head = read_head(&mut stream)?;
+		forwarded_client(&head, trusted_proxies).unwrap_or_else(|| peer.to_string())
+	} else {
+		peer.to_string()
+	};
+
+	if !limiter.check(&client) {
+		debug!(target: "rpc", "RPC rate limit exceeded by {}", client);
+		return stream.write_all(TOO_MANY_REQUESTS);
+	}
+
+	let mut server = TcpStream::connect(backend)?;
+	server.write_all(&head)?;
+	let mut from_client = stream.try_clone()?;
+	let mut to_server = server.try_clone()?;
+	let upstream = thread::spawn(move || {
+		let _ = io::copy(&mut from_client, &mut to_server);
+		let _ = to_server.shutdown(Shutdown::Write);
+	});
+	let _ = io::copy(&mut server, &mut stream);
+	let _ = stream.shutdown(Shutdown::Both);
+	let _ = upstream.join();
+	Ok(())
+}
+
+/// Read from a proxy connection until the end of the request head, or `MAX_HEAD` bytes.
+fn read_head(stream: &mut TcpStream) -> io::Result<Vec<u8>> {
+	stream.set_read_timeout(Some(HEAD_TIMEOUT))?;
+	let mut head = Vec::new();
+	let mut buf = [0u8; 1024];
+	while head.len() < MAX_HEAD && !head.windows(4).any(|w| w == b"\r\n\r\n") {
+		let read = stream.read(&mut buf)?;
+		if read == 0 {
+			break;
+		}
+		head.extend_from_slice(&buf[..read]);
+	}
+	stream.set_read_timeout(None)?;
+	Ok(head)
+}
+
+/// The client address a trusted proxy forwards in the `X-Forwarded-For` or `Forwarded` header
+/// of a request head.
+///
+/// Proxies append the address they received the request from, so the client is the last
+/// address that is not itself a trusted proxy. Earlier entries are set by the client.
+fn forwarded_client(head: &[u8], trusted_proxies: &[IpAddr]) -> Option<String> {
+	let head = String::from_utf8_lossy(head);
+	let header = |name: &str| head.split("\r\n")
+		.skip(1)
+		.take_while(|line| !line.is_empty())
+		.filter_map(|line| {
+			let mut parts = line.splitn(2, ':');
+			match (parts.next(), parts.next()) {
+				(Some(n), Some(value)) if n.trim().eq_ignore_ascii_case(name) => Some(value.trim().to_owned()),
+				_ => None,
+			}
+		})
+		.collect::<Vec<_>>()
+		.join(",");
+	let untrusted = |addr: &&str| addr.parse::<IpAddr>().map_or(true, |ip| !trusted_proxies.contains(&ip));
+
+	let forwarded_for = header("x-forwarded-for");
+	if let Some(addr) = forwarded_for.split(',').map(str::trim).filter(|a| !a.is_empty()).rev().find(untrusted) {
+		return Some(addr.to_owned());
+	}
+
+	let forwarded = header("forwarded");
+	let addrs: Vec<&str> = forwarded.split(',')
+		.filter_map(|element| element.split(';').map(str::trim).find(|p| p.to_ascii_lowercase().starts_with("for=")))
+		.map(|p| p[4..].trim_matches('"'))
+		.filter(|a| !a.is_empty())
+		.collect();
+	addrs.into_iter().rev().find(untrusted).map(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn limits_requests_per_window() {
+		let limiter = RateLimiter::new(2);
+		let now = Instant::now();
+		assert!(limiter.check_at("a", now));
+		assert!(limiter.check_at("a", now));
+		assert!(!limiter.check_at("a", now));
+		assert!(limiter.check_at("b", now));
+		assert!(limiter.check_at("a", now + WINDOW));
+	}
+
+	#[test]
+	fn forwarded_client_skips_trusted_proxies() {
+		let proxy: IpAddr = "10.0.0.2".parse().unwrap();
+		let head = |headers: &str| format!("POST / HTTP/1.1\r\n{}\r\n\r\n{{}}", headers).into_bytes();
+
+		assert_eq!(forwarded_client(&head("X-Forwarded-For: 1.1.1.1, 10.0.0.1, 10.0.0.2"), &[proxy]), Some("10.0.0.1".into()));
+		assert_eq!(forwarded_client(&head("x-forwarded-for: 10.0.0.2"), &[proxy]), None);
+		assert_eq!(forwarded_client(&head("Forwarded: for=\"10.0.0.3\";proto=https, for=10.0.0.2"), &[proxy]), Some("10.0.0.3".into()));
+		assert_eq!(forwarded_client(&head("Host: localhost"), &[proxy]), None);
+	}
+
+	#[test]
+	fn gate_limits_connections_per_peer() {
+		let backend = TcpListener::bind("127.0.0.1:0").unwrap();
+		let backend_addr = backend.local_addr().unwrap();
+		thread::spawn(move || for stream in backend.incoming() {
+			let _ = stream.unwrap().write_all(b"ok");
+		});
+		let gate = Gate::start(&"127.0.0.1:0".parse().unwrap(), backend_addr, RateLimiter::new(1), Vec::new()).unwrap();
+
+		let response = |addr: &SocketAddr| {
+			let mut stream = TcpStream::connect(addr).unwrap();
+			let mut response = Vec::new();
+			stream.read_to_end(&mut response).unwrap();
+			response
+		};
+		assert_eq!(response(gate.address()), b"ok".to_vec());
+		assert_eq!(response(gate.address()), TOO_MANY_REQUESTS.to_vec());
+	}
+}
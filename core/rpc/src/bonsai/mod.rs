@@ -0,0 +1,211 @@
+// Copyright 2017-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Totem Bonsai record validation API.
+//!
+//! `BonsaiModule::update_record` stores a token per record key in `IsValidRecord`. An off-chain database
+//! only accepts a document for a record key if the hash of the document matches this token.
+//! This module is the reference implementation of that check. The free functions only rely on
+//! plain storage reads, so a third party database can reproduce them with `state_getStorage`.
+
+use std::sync::Arc;
+
+use client::{self, Client, CallExecutor};
+use jsonrpc_derive::rpc;
+use parity_codec::{Decode, Encode};
+use primitives::{H256, Blake2Hasher, Bytes, blake2_256};
+use primitives::storage::StorageKey;
+use runtime_primitives::generic::BlockId;
+use runtime_primitives::traits::{Block as BlockT, NumberFor};
+use serde::{Serialize, Deserialize};
+
+mod error;
+#[cfg(test)]
+mod tests;
+
+use self::error::Result;
+
+/// Storage prefix of the bonsai tokens (`BonsaiModule IsValidRecord`).
+pub const IS_VALID_RECORD_PREFIX: &[u8] = b"BonsaiModule IsValidRecord";
+/// Storage prefix of the block and extrinsic that last set a token (`BonsaiModule RecordSetAt`).
+pub const RECORD_SET_AT_PREFIX: &[u8] = b"BonsaiModule RecordSetAt";
+
+/// Storage prefix of started transaction UUIDs (`BonsaiModule IsStarted`).
+pub const IS_STARTED_PREFIX: &[u8] = b"BonsaiModule IsStarted";
+/// Storage prefix of completed transaction UUIDs (`BonsaiModule IsSuccessful`).
+pub const IS_SUCCESSFUL_PREFIX: &[u8] = b"BonsaiModule IsSuccessful";
+
+/// Storage key of a runtime `map` entry hashed with blake2_256.
+fn map_key(prefix: &[u8], record: &H256) -> StorageKey {
+	let mut key = prefix.to_vec();
+	record.encode_to(&mut key);
+	StorageKey(blake2_256(&key).to_vec())
+}
+
+/// Storage key holding the bonsai token of a record.
+pub fn token_key(record: &H256) -> StorageKey {
+	map_key(IS_VALID_RECORD_PREFIX, record)
+}
+
+/// Storage key holding the block number and extrinsic index that last set the token of a record.
+pub fn set_at_key(record: &H256) -> StorageKey {
+	map_key(RECORD_SET_AT_PREFIX, record)
+}
+
+/// Storage key holding the block in which a transaction UUID was started.
+pub fn started_key(uuid: &H256) -> StorageKey {
+	map_key(IS_STARTED_PREFIX, uuid)
+}
+
+/// Storage key holding the block after which a completed transaction UUID is removed.
+pub fn successful_key(uuid: &H256) -> StorageKey {
+	map_key(IS_SUCCESSFUL_PREFIX, uuid)
+}
+
+/// Hash of the document content. This is the value that must be submitted as the bonsai token.
+pub fn document_hash(document: &[u8]) -> H256 {
+	blake2_256(document).into()
+}
+
+/// Checks the document against the token found on chain.
+/// A record without a token is never valid.
+pub fn is_valid_document(token: Option<&H256>, document: &[u8]) -> bool {
+	token.map_or(false, |t| *t == document_hash(document))
+}
+
+/// Result of validating a document against the chain.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordValidation<Hash, Number> {
+	/// Record key that was checked.
+	pub record: H256,
+	/// Token stored on chain for the record, if any.
+	pub token: Option<H256>,
+	/// Hash of the supplied document.
+	pub document_hash: H256,
+	/// True if the document hash matches the token.
+	pub is_valid: bool,
+	/// Number of the block in which the token was last set.
+	pub set_at_block: Option<Number>,
+	/// Hash of the block in which the token was last set.
+	pub set_at_block_hash: Option<Hash>,
+	/// Index of the extrinsic in that block which set the token.
+	pub set_by_extrinsic: Option<u32>,
+}
+
+/// Lifecycle state of a transaction UUID.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransactionStatus<Number> {
+	/// The UUID was never seen or has expired.
+	Unknown,
+	/// The transaction was started in the given block but has not completed.
+	Started(Number),
+	/// The transaction completed. The UUID is removed in the given block.
+	Successful(Number),
+}
+
+/// Totem Bonsai API
+#[rpc]
+pub trait BonsaiApi<Hash, Number> {
+	/// Validates a document against the bonsai token of a record at a block's state.
+	#[rpc(name = "bonsai_validateRecord")]
+	fn validate_record(&self, record: H256, document: Bytes, hash: Option<Hash>) -> Result<RecordValidation<Hash, Number>>;
+
+	/// Returns the bonsai token of a record at a block's state.
+	#[rpc(name = "bonsai_getToken")]
+	fn token(&self, record: H256, hash: Option<Hash>) -> Result<Option<H256>>;
+
+	/// Returns the lifecycle state of a transaction UUID at a block's state.
+	#[rpc(name = "bonsai_transactionStatus")]
+	fn transaction_status(&self, uuid: H256, hash: Option<Hash>) -> Result<TransactionStatus<Number>>;
+}
+
+/// Bonsai API
+pub struct Bonsai<B, E, Block: BlockT, RA> {
+	/// Substrate client.
+	client: Arc<Client<B, E, Block, RA>>,
+}
+
+impl<B, E, Block: BlockT, RA> Bonsai<B, E, Block, RA> where
+	Block: BlockT<Hash=H256>,
+	B: client::backend::Backend<Block, Blake2Hasher>,
+	E: CallExecutor<Block, Blake2Hasher>,
+{
+	/// Create new Bonsai API RPC handler.
+	pub fn new(client: Arc<Client<B, E, Block, RA>>) -> Self {
+		Self {
+			client,
+		}
+	}
+
+	fn unwrap_or_best(&self, hash: Option<Block::Hash>) -> Result<Block::Hash> {
+		crate::helpers::unwrap_or_else(|| Ok(self.client.info()?.chain.best_hash), hash)
+	}
+
+	fn read<T: Decode>(&self, block: &BlockId<Block>, key: &StorageKey) -> Result<Option<T>> {
+		Ok(self.client.storage(block, key)?.and_then(|data| Decode::decode(&mut &data.0[..])))
+	}
+}
+
+impl<B, E, Block, RA> BonsaiApi<Block::Hash, NumberFor<Block>> for Bonsai<B, E, Block, RA> where
+	Block: BlockT<Hash=H256> + 'static,
+	B: client::backend::Backend<Block, Blake2Hasher> + Send + Sync + 'static,
+	E: CallExecutor<Block, Blake2Hasher> + Send + Sync + 'static,
+	RA: Send + Sync + 'static,
+{
+	fn validate_record(&self, record: H256, document: Bytes, hash: Option<Block::Hash>) -> Result<RecordValidation<Block::Hash, NumberFor<Block>>> {
+		let block = self.unwrap_or_best(hash)?;
+		let id = BlockId::Hash(block);
+		if self.client.header(&id)?.is_none() {
+			return Err(error::ErrorKind::UnknownBlock(format!("{}", block)).into());
+		}
+
+		let token: Option<H256> = self.read(&id, &token_key(&record))?;
+		let set_at: Option<(NumberFor<Block>, u32)> = self.read(&id, &set_at_key(&record))?;
+		let set_at_block_hash = match set_at {
+			Some((number, _)) => self.client.block_hash(number)?,
+			None => None,
+		};
+
+		Ok(RecordValidation {
+			record,
+			is_valid: is_valid_document(token.as_ref(), &document.0),
+			token,
+			document_hash: document_hash(&document.0),
+			set_at_block: set_at.map(|(number, _)| number),
+			set_at_block_hash,
+			set_by_extrinsic: set_at.map(|(_, index)| index),
+		})
+	}
+
+	fn token(&self, record: H256, hash: Option<Block::Hash>) -> Result<Option<H256>> {
+		let block = self.unwrap_or_best(hash)?;
+		self.read(&BlockId::Hash(block), &token_key(&record))
+	}
+
+	fn transaction_status(&self, uuid: H256, hash: Option<Block::Hash>) -> Result<TransactionStatus<NumberFor<Block>>> {
+		let block = self.unwrap_or_best(hash)?;
+		let id = BlockId::Hash(block);
+		if let Some(expires) = self.read(&id, &successful_key(&uuid))? {
+			return Ok(TransactionStatus::Successful(expires));
+		}
+		Ok(match self.read(&id, &started_key(&uuid))? {
+			Some(started) => TransactionStatus::Started(started),
+			None => TransactionStatus::Unknown,
+		})
+	}
+}
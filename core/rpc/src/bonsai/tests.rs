@@ -0,0 +1,85 @@
+// Copyright 2017-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+use assert_matches::assert_matches;
+use hex_literal::{hex, hex_impl};
+
+#[test]
+fn document_matches_token() {
+	let document = b"invoice 42";
+	let token = document_hash(document);
+
+	assert!(is_valid_document(Some(&token), document));
+	assert!(!is_valid_document(Some(&token), b"invoice 43"));
+	assert!(!is_valid_document(None, document));
+}
+
+#[test]
+fn storage_keys_follow_runtime_map_layout() {
+	// blake2_256 of the `decl_storage` prefix of each map in `BonsaiModule` followed by the record hash
+	let record = H256::repeat_byte(7);
+
+	assert_eq!(
+		token_key(&record),
+		StorageKey(hex!("88cc264f05e7e424d7e61e647f99223950a7179b06d703fbdc0a99b026e46445").to_vec()),
+	);
+	assert_eq!(
+		set_at_key(&record),
+		StorageKey(hex!("70d4bd6cae9104e1cf002be5825dfed8a7baf634291b0325f4743bfd3ec9af85").to_vec()),
+	);
+	assert_eq!(
+		started_key(&record),
+		StorageKey(hex!("3f2850abecbc5e520f44799bd40a0c0ac4885ffbf071057a89c6db528d694563").to_vec()),
+	);
+	assert_eq!(
+		successful_key(&record),
+		StorageKey(hex!("bc64ce8e326bd2729fddaadd0cfb656f13d995a2ce7cc0ca47835e7793b6a8ad").to_vec()),
+	);
+}
+
+#[test]
+fn should_not_validate_unknown_record() {
+	let client = Arc::new(test_client::new());
+	let genesis_hash = client.genesis_hash();
+	let api = Bonsai::new(client);
+
+	let result = api.validate_record(H256::repeat_byte(1), Bytes(b"data".to_vec()), Some(genesis_hash));
+	assert_matches!(result, Ok(RecordValidation { is_valid: false, token: None, set_at_block: None, .. }));
+}
+
+#[test]
+fn should_reject_unknown_block() {
+	let api = Bonsai::new(Arc::new(test_client::new()));
+
+	assert_matches!(
+		api.validate_record(H256::repeat_byte(1), Bytes(vec![]), Some(H256::repeat_byte(9))),
+		Err(error::Error(error::ErrorKind::UnknownBlock(_), _))
+	);
+}
+
+#[test]
+fn should_return_unknown_transaction_status() {
+	let client = Arc::new(test_client::new());
+	let genesis_hash = client.genesis_hash();
+	let api = Bonsai::new(client);
+
+	assert_matches!(
+		api.transaction_status(H256::repeat_byte(2), Some(genesis_hash)),
+		Ok(TransactionStatus::Unknown)
+	);
+}
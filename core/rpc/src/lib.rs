@@ -25,9 +25,12 @@ mod subscriptions;
 pub use subscriptions::Subscriptions;
 
 pub mod author;
+pub mod bonsai;
 pub mod chain;
+pub mod messaging;
 pub mod metadata;
 pub mod state;
 pub mod system;
+pub mod totem;
 
 use jsonrpc_core as rpc;
@@ -0,0 +1,72 @@
+// Copyright 2017-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Messaging RPC errors.
+
+use error_chain::*;
+use client;
+use crate::rpc;
+use crate::errors;
+
+error_chain! {
+	links {
+		Client(client::error::Error, client::error::ErrorKind) #[doc = "Client error"];
+		Keystore(keystore::Error, keystore::ErrorKind) #[doc = "Keystore error"];
+	}
+
+	errors {
+		/// No keystore key matches the recipient's encryption key.
+		NoLocalKey {
+			description("No local key"),
+			display("No box key for the recipient is held by this node"),
+		}
+		/// The message does not exist.
+		UnknownMessage {
+			description("Unknown message"),
+			display("The message does not exist or has expired"),
+		}
+		/// The message could not be decrypted with the local key.
+		CannotDecrypt {
+			description("Cannot decrypt"),
+			display("The message cannot be decrypted with the local key"),
+		}
+	}
+}
+
+const ERROR: i64 = 4000;
+
+impl From<Error> for rpc::Error {
+	fn from(e: Error) -> Self {
+		match e {
+			Error(ErrorKind::NoLocalKey, _) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(ERROR + 1),
+				message: "No box key for the recipient is held by this node".into(),
+				data: None,
+			},
+			Error(ErrorKind::UnknownMessage, _) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(ERROR + 2),
+				message: "The message does not exist or has expired".into(),
+				data: None,
+			},
+			Error(ErrorKind::CannotDecrypt, _) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(ERROR + 3),
+				message: "The message cannot be decrypted with the local key".into(),
+				data: None,
+			},
+			e => errors::internal(e),
+		}
+	}
+}
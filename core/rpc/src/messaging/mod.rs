@@ -0,0 +1,274 @@
+// Copyright 2017-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Totem encrypted messaging API.
+//!
+//! Messages are stored by the runtime `Messaging` module encrypted with NaCl `box`. This API lists the inbox
+//! of a user and serves the encrypted messages, to be decrypted client-side by the recipient.
+//!
+//! `messaging_readMessage` decrypts a message on the node instead. The box secret key is derived from the seed
+//! of a `boxkeys` key in the node keystore, the one whose box public key is the recipient's verified encryption key.
+//! It is an unsafe method and hidden from public nodes.
+
+use std::sync::Arc;
+
+use client::{self, Client, CallExecutor};
+use jsonrpc_derive::rpc;
+use keystore::{KeystoreBackend, KeyType};
+use parity_codec::{Decode, Encode};
+use primitives::{ed25519, H256, Blake2Hasher, Bytes, blake2_256};
+use primitives::storage::StorageKey;
+use runtime_primitives::generic::BlockId;
+use runtime_primitives::traits::{Block as BlockT, NumberFor};
+use serde::{Serialize, Deserialize};
+use sodalite::{box_keypair_seed, box_open, BoxNonce, BoxPublicKey, BoxSecretKey};
+use totem_primitives::Message;
+
+mod error;
+#[cfg(test)]
+mod tests;
+
+use self::error::{Result, ErrorKind};
+
+/// Storage prefix of the messages (`Messaging Messages`).
+pub const MESSAGES_PREFIX: &[u8] = b"Messaging Messages";
+/// Storage prefix of the inboxes (`Messaging Inbox`).
+pub const INBOX_PREFIX: &[u8] = b"Messaging Inbox";
+/// Storage prefix of the verified encryption keys (`BoxKeyS PublicKeyEnc`).
+pub const ENCRYPTION_KEYS_PREFIX: &[u8] = b"BoxKeyS PublicKeyEnc";
+
+/// Leading zero bytes of a `box` plaintext.
+const ZERO_BYTES: usize = 32;
+/// Leading zero bytes of a `box` ciphertext. These are not stored on chain.
+const BOX_ZERO_BYTES: usize = 16;
+
+/// Storage key of a runtime `map` entry hashed with blake2_256.
+fn map_key(prefix: &[u8], key: &H256) -> StorageKey {
+	let mut raw = prefix.to_vec();
+	key.encode_to(&mut raw);
+	StorageKey(blake2_256(&raw).to_vec())
+}
+
+/// Storage key of a message.
+pub fn message_key(message: &H256) -> StorageKey {
+	map_key(MESSAGES_PREFIX, message)
+}
+
+/// Storage key of the inbox of a user.
+pub fn inbox_key(user: &H256) -> StorageKey {
+	map_key(INBOX_PREFIX, user)
+}
+
+/// Storage key of the verified encryption key of a user.
+pub fn encryption_key_key(user: &H256) -> StorageKey {
+	map_key(ENCRYPTION_KEYS_PREFIX, user)
+}
+
+/// The box keypair derived from the seed of a keystore key.
+pub fn box_keypair(pair: &ed25519::Pair) -> (BoxPublicKey, BoxSecretKey) {
+	let mut public = [0u8; 32];
+	let mut secret = [0u8; 32];
+	box_keypair_seed(&mut public, &mut secret, pair.seed());
+	(public, secret)
+}
+
+/// Decrypts a payload stored on chain. Returns `None` if the payload was not encrypted for this key by the sender.
+pub fn open(payload: &[u8], nonce: &BoxNonce, sender_key: &BoxPublicKey, secret: &BoxSecretKey) -> Option<Vec<u8>> {
+	if payload.len() + BOX_ZERO_BYTES < ZERO_BYTES {
+		return None;
+	}
+	let mut cipher = vec![0u8; BOX_ZERO_BYTES];
+	cipher.extend_from_slice(payload);
+	let mut plain = vec![0u8; cipher.len()];
+	box_open(&mut plain, &cipher, nonce, sender_key, secret).ok()?;
+	Some(plain.split_off(ZERO_BYTES))
+}
+
+/// An encrypted message, to be opened by the recipient with NaCl `box_open` and their box secret key.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptedMessage<AccountId, Number> {
+	/// Message hash.
+	pub hash: H256,
+	/// User hash of the sender.
+	pub sender_user: H256,
+	/// Account that sent the message.
+	pub sender: AccountId,
+	/// Type of the referenced record (3000 project, 5000 order).
+	pub reference_type: u16,
+	/// Hash of the referenced record.
+	pub reference: H256,
+	/// Public box key of the sender.
+	pub sender_key: H256,
+	/// Nonce used for encryption.
+	pub nonce: Bytes,
+	/// Ciphertext without the 16 leading zero bytes of `box`.
+	pub payload: Bytes,
+	/// Block in which the message was sent.
+	pub sent: Number,
+	/// Block in which the message expires.
+	pub expires: Number,
+}
+
+impl<AccountId, Number> EncryptedMessage<AccountId, Number> {
+	/// Builds the message returned by the API from the runtime storage entry.
+	pub fn new(hash: H256, stored: Message<AccountId, H256, Number>) -> Self {
+		EncryptedMessage {
+			hash,
+			sender_user: stored.sender_user,
+			sender: stored.sender,
+			reference_type: stored.reference_type,
+			reference: stored.reference,
+			sender_key: stored.sender_key,
+			nonce: Bytes(stored.nonce.to_vec()),
+			payload: Bytes(stored.payload),
+			sent: stored.sent,
+			expires: stored.expires,
+		}
+	}
+}
+
+/// A message decrypted by the node.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecryptedMessage<AccountId, Number> {
+	/// Message hash.
+	pub hash: H256,
+	/// User hash of the sender.
+	pub sender_user: H256,
+	/// Account that sent the message.
+	pub sender: AccountId,
+	/// Type of the referenced record (3000 project, 5000 order).
+	pub reference_type: u16,
+	/// Hash of the referenced record.
+	pub reference: H256,
+	/// Block in which the message was sent.
+	pub sent: Number,
+	/// Block in which the message expires.
+	pub expires: Number,
+	/// Plaintext of the message.
+	pub payload: Bytes,
+}
+
+/// Totem Messaging API
+#[rpc]
+pub trait MessagingApi<Hash, Number, AccountId> {
+	/// Returns the hashes of the messages waiting for a user.
+	#[rpc(name = "messaging_inbox")]
+	fn inbox(&self, user: H256, hash: Option<Hash>) -> Result<Vec<H256>>;
+
+	/// Returns an encrypted message, if it exists and has not expired.
+	#[rpc(name = "messaging_message")]
+	fn message(&self, message: H256, hash: Option<Hash>) -> Result<Option<EncryptedMessage<AccountId, Number>>>;
+
+	/// Decrypts a message with the recipient's box key held in the node keystore.
+	#[rpc(name = "messaging_readMessage")]
+	fn read_message(&self, message: H256, hash: Option<Hash>) -> Result<DecryptedMessage<AccountId, Number>>;
+}
+
+/// Messaging API
+pub struct Messaging<B, E, Block: BlockT, RA> {
+	/// Substrate client.
+	client: Arc<Client<B, E, Block, RA>>,
+	/// Keystore holding the `boxkeys` keys the box keys are derived from.
+	keystore: Option<Arc<dyn KeystoreBackend>>,
+	/// Password of the keys in `keystore`.
+	password: String,
+}
+
+impl<B, E, Block: BlockT, RA> Messaging<B, E, Block, RA> where
+	Block: BlockT<Hash=H256>,
+	B: client::backend::Backend<Block, Blake2Hasher>,
+	E: CallExecutor<Block, Blake2Hasher>,
+{
+	/// Create new Messaging API RPC handler. Without a keystore messages cannot be read on the node.
+	pub fn new(client: Arc<Client<B, E, Block, RA>>, keystore: Option<Arc<dyn KeystoreBackend>>) -> Self {
+		Self {
+			client,
+			keystore,
+			password: String::new(),
+		}
+	}
+
+	/// Sets the password the keys in the keystore are protected with. Defaults to an empty password.
+	pub fn with_password(mut self, password: String) -> Self {
+		self.password = password;
+		self
+	}
+
+	/// The box secret key of the `boxkeys` keystore key matching the encryption key.
+	/// Keys are loaded on every call so that they are never kept in memory longer than needed.
+	fn secret(&self, encryption_key: &H256) -> Result<BoxSecretKey> {
+		let keystore = self.keystore.as_ref().ok_or(ErrorKind::NoLocalKey)?;
+		for public in keystore.keys(KeyType::Boxkeys)? {
+			let pair = keystore.load_key(KeyType::Boxkeys, &public, &self.password)?;
+			let (box_public, box_secret) = box_keypair(&pair);
+			if &box_public == encryption_key.as_fixed_bytes() {
+				return Ok(box_secret);
+			}
+		}
+		Err(ErrorKind::NoLocalKey.into())
+	}
+
+	fn unwrap_or_best(&self, hash: Option<Block::Hash>) -> Result<Block::Hash> {
+		crate::helpers::unwrap_or_else(|| Ok(self.client.info()?.chain.best_hash), hash)
+	}
+
+	fn read<T: Decode>(&self, block: &BlockId<Block>, key: &StorageKey) -> Result<Option<T>> {
+		Ok(self.client.storage(block, key)?.and_then(|data| Decode::decode(&mut &data.0[..])))
+	}
+}
+
+impl<B, E, Block, RA, AccountId> MessagingApi<Block::Hash, NumberFor<Block>, AccountId> for Messaging<B, E, Block, RA> where
+	Block: BlockT<Hash=H256> + 'static,
+	B: client::backend::Backend<Block, Blake2Hasher> + Send + Sync + 'static,
+	E: CallExecutor<Block, Blake2Hasher> + Send + Sync + 'static,
+	RA: Send + Sync + 'static,
+	AccountId: Decode + Send + Sync + 'static,
+{
+	fn inbox(&self, user: H256, hash: Option<Block::Hash>) -> Result<Vec<H256>> {
+		let block = self.unwrap_or_best(hash)?;
+		Ok(self.read(&BlockId::Hash(block), &inbox_key(&user))?.unwrap_or_default())
+	}
+
+	fn message(&self, message: H256, hash: Option<Block::Hash>) -> Result<Option<EncryptedMessage<AccountId, NumberFor<Block>>>> {
+		let block = self.unwrap_or_best(hash)?;
+		let stored: Option<Message<AccountId, H256, NumberFor<Block>>> = self.read(&BlockId::Hash(block), &message_key(&message))?;
+		Ok(stored.map(|stored| EncryptedMessage::new(message, stored)))
+	}
+
+	fn read_message(&self, message: H256, hash: Option<Block::Hash>) -> Result<DecryptedMessage<AccountId, NumberFor<Block>>> {
+		let block = BlockId::Hash(self.unwrap_or_best(hash)?);
+		let stored: Message<AccountId, H256, NumberFor<Block>> = self.read(&block, &message_key(&message))?
+			.ok_or(ErrorKind::UnknownMessage)?;
+		let encryption_key: H256 = self.read(&block, &encryption_key_key(&stored.recipient))?
+			.ok_or(ErrorKind::NoLocalKey)?;
+		let secret = self.secret(&encryption_key)?;
+		let payload = open(&stored.payload, &stored.nonce, stored.sender_key.as_fixed_bytes(), &secret)
+			.ok_or(ErrorKind::CannotDecrypt)?;
+
+		Ok(DecryptedMessage {
+			hash: message,
+			sender_user: stored.sender_user,
+			sender: stored.sender,
+			reference_type: stored.reference_type,
+			reference: stored.reference,
+			sent: stored.sent,
+			expires: stored.expires,
+			payload: Bytes(payload),
+		})
+	}
+}
@@ -0,0 +1,110 @@
+// Copyright 2017-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+use assert_matches::assert_matches;
+use keystore::Store;
+use sodalite::box_;
+
+fn keypair(seed: u8) -> (BoxPublicKey, BoxSecretKey) {
+	let mut public = [0u8; 32];
+	let mut secret = [0u8; 32];
+	box_keypair_seed(&mut public, &mut secret, &[seed; 32]);
+	(public, secret)
+}
+
+fn seal(message: &[u8], nonce: &BoxNonce, recipient: &BoxPublicKey, sender: &BoxSecretKey) -> Vec<u8> {
+	let mut plain = vec![0u8; ZERO_BYTES];
+	plain.extend_from_slice(message);
+	let mut cipher = vec![0u8; plain.len()];
+	box_(&mut cipher, &plain, nonce, recipient, sender).unwrap();
+	cipher.split_off(BOX_ZERO_BYTES)
+}
+
+#[test]
+fn should_open_payload_for_recipient() {
+	let (sender_public, sender_secret) = keypair(1);
+	let (recipient_public, recipient_secret) = keypair(2);
+	let (_, other_secret) = keypair(3);
+	let nonce = [7u8; 24];
+	let payload = seal(b"invoice attached", &nonce, &recipient_public, &sender_secret);
+
+	assert_eq!(open(&payload, &nonce, &sender_public, &recipient_secret), Some(b"invoice attached".to_vec()));
+	assert_eq!(open(&payload, &nonce, &sender_public, &other_secret), None);
+	assert_eq!(open(&payload[..4], &nonce, &sender_public, &recipient_secret), None);
+}
+
+#[test]
+fn should_find_box_key_in_keystore() {
+	let temp_dir = tempdir::TempDir::new("messaging-keystore").unwrap();
+	let store = Store::open(temp_dir.path().to_owned()).unwrap();
+	let public = store.generate_key(KeyType::Boxkeys, "secret").unwrap();
+	let (box_public, box_secret) = box_keypair(&store.load_key(KeyType::Boxkeys, &public, "secret").unwrap());
+
+	let client = Arc::new(test_client::new());
+	let api = Messaging::new(client.clone(), Some(Arc::new(store))).with_password("secret".into());
+	assert_eq!(api.secret(&H256::from(box_public)).unwrap(), box_secret);
+	assert_matches!(api.secret(&H256::repeat_byte(1)), Err(error::Error(ErrorKind::NoLocalKey, _)));
+
+	let api = Messaging::new(client, None);
+	assert_matches!(api.secret(&H256::from(box_public)), Err(error::Error(ErrorKind::NoLocalKey, _)));
+}
+
+#[test]
+fn should_build_message_from_storage() {
+	let stored = Message {
+		sender_user: H256::repeat_byte(1),
+		recipient: H256::repeat_byte(2),
+		reference_type: 5000,
+		reference: H256::repeat_byte(3),
+		sender_key: H256::repeat_byte(4),
+		nonce: [7u8; 24],
+		payload: b"ciphertext".to_vec(),
+		sent: 10u64,
+		expires: 20u64,
+		sender: 42u64,
+	};
+	let encoded = stored.encode();
+	let decoded: Message<u64, H256, u64> = Decode::decode(&mut &encoded[..]).unwrap();
+	let message = EncryptedMessage::new(H256::repeat_byte(9), decoded);
+
+	assert_eq!(message.sender, 42);
+	assert_eq!(message.sender_key, H256::repeat_byte(4));
+	assert_eq!(message.nonce, Bytes(vec![7u8; 24]));
+	assert_eq!(message.payload, Bytes(b"ciphertext".to_vec()));
+	assert_eq!((message.sent, message.expires), (10, 20));
+}
+
+#[test]
+fn should_return_empty_inbox() {
+	let client = Arc::new(test_client::new());
+	let genesis_hash = client.genesis_hash();
+	let api = Messaging::new(client, None);
+
+	assert_matches!(
+		MessagingApi::<_, _, u64>::inbox(&api, H256::repeat_byte(1), Some(genesis_hash)),
+		Ok(ref inbox) if inbox.is_empty()
+	);
+	assert_matches!(
+		MessagingApi::<_, _, u64>::message(&api, H256::repeat_byte(1), Some(genesis_hash)),
+		Ok(None)
+	);
+	assert_matches!(
+		MessagingApi::<_, _, u64>::read_message(&api, H256::repeat_byte(1), Some(genesis_hash)),
+		Err(error::Error(ErrorKind::UnknownMessage, _))
+	);
+}
@@ -0,0 +1,51 @@
+// Copyright 2017-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Totem RPC errors.
+
+use error_chain::*;
+use client;
+use crate::rpc;
+use crate::errors;
+
+error_chain! {
+	links {
+		Client(client::error::Error, client::error::ErrorKind) #[doc = "Client error"];
+	}
+
+	errors {
+		/// The runtime returned data that could not be decoded.
+		InvalidResponse(method: String) {
+			description("Invalid runtime response"),
+			display("Invalid response from runtime call {}", method),
+		}
+	}
+}
+
+const ERROR: i64 = 5000;
+
+impl From<Error> for rpc::Error {
+	fn from(e: Error) -> Self {
+		match e {
+			Error(ErrorKind::InvalidResponse(method), _) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(ERROR + 1),
+				message: format!("Invalid response from runtime call {}", method),
+				data: Some("The runtime may not implement the Totem api".into()),
+			},
+			e => errors::internal(e),
+		}
+	}
+}
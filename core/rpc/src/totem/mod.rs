@@ -0,0 +1,141 @@
+// Copyright 2017-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Totem query API.
+//!
+//! Serves orders, projects, timesheets and reputation by calling the `TotemApi` and `ReputationApi`
+//! runtime apis declared in `totem-primitives`. The calls are made by method name, so a node whose
+//! runtime does not implement these apis still starts and only fails the individual requests.
+
+use std::sync::Arc;
+
+use client::{self, Client, CallExecutor};
+use jsonrpc_derive::rpc;
+use parity_codec::{Decode, Encode};
+use primitives::{H256, Blake2Hasher};
+use runtime_primitives::generic::BlockId;
+use runtime_primitives::traits::Block as BlockT;
+use state_machine::{self, ExecutionStrategy};
+use totem_primitives::{OrderDetails, OrderParty, ProjectDetails, TimeRecordDetails, ReputationScores};
+
+mod error;
+#[cfg(test)]
+mod tests;
+
+use self::error::Result;
+
+/// Runtime method returning the orders of a party.
+pub const ORDERS_BY_PARTY: &str = "TotemApi_orders_by_party";
+/// Runtime method returning an order with its items.
+pub const ORDER: &str = "TotemApi_order";
+/// Runtime method returning a project with its team and totals.
+pub const PROJECT: &str = "TotemApi_project";
+/// Runtime method returning the time records of a worker.
+pub const TIMESHEET: &str = "TotemApi_timesheet";
+/// Runtime method returning the reputation scores of an account.
+pub const REPUTATION: &str = "ReputationApi_reputation";
+
+/// Decodes the return value of a runtime call.
+pub fn decode_response<R: Decode>(method: &str, data: &[u8]) -> Result<R> {
+	R::decode(&mut &data[..]).ok_or_else(|| error::ErrorKind::InvalidResponse(method.into()).into())
+}
+
+/// Totem API
+#[rpc]
+pub trait TotemApi<Hash, AccountId> {
+	/// Returns the orders in which the account is commander (0), fulfiller (1) or approver (2),
+	/// optionally filtered by order status.
+	#[rpc(name = "totem_ordersByParty")]
+	fn orders_by_party(&self, who: AccountId, party: OrderParty, status: Option<u16>, hash: Option<Hash>) -> Result<Vec<OrderDetails<AccountId, H256>>>;
+
+	/// Returns an order with its items.
+	#[rpc(name = "totem_order")]
+	fn order(&self, order_hash: H256, hash: Option<Hash>) -> Result<Option<OrderDetails<AccountId, H256>>>;
+
+	/// Returns a project with its team and time totals.
+	#[rpc(name = "totem_project")]
+	fn project(&self, project_hash: H256, hash: Option<Hash>) -> Result<Option<ProjectDetails<AccountId, H256>>>;
+
+	/// Returns the time records of a worker ending between the two block numbers (inclusive).
+	#[rpc(name = "totem_timesheet")]
+	fn timesheet(&self, worker: AccountId, from: u64, to: u64, hash: Option<Hash>) -> Result<Vec<TimeRecordDetails<AccountId, H256>>>;
+
+	/// Returns the reputation scores of an account.
+	#[rpc(name = "totem_reputation")]
+	fn reputation(&self, who: AccountId, hash: Option<Hash>) -> Result<ReputationScores>;
+}
+
+/// Totem API
+pub struct Totem<B, E, Block: BlockT, RA> {
+	/// Substrate client.
+	client: Arc<Client<B, E, Block, RA>>,
+}
+
+impl<B, E, Block: BlockT, RA> Totem<B, E, Block, RA> where
+	Block: BlockT<Hash=H256>,
+	B: client::backend::Backend<Block, Blake2Hasher>,
+	E: CallExecutor<Block, Blake2Hasher>,
+{
+	/// Create new Totem API RPC handler.
+	pub fn new(client: Arc<Client<B, E, Block, RA>>) -> Self {
+		Self {
+			client,
+		}
+	}
+
+	fn unwrap_or_best(&self, hash: Option<Block::Hash>) -> Result<Block::Hash> {
+		crate::helpers::unwrap_or_else(|| Ok(self.client.info()?.chain.best_hash), hash)
+	}
+
+	fn call<A: Encode, R: Decode>(&self, method: &str, args: A, hash: Option<Block::Hash>) -> Result<R> {
+		let block = self.unwrap_or_best(hash)?;
+		let data = self.client
+			.executor()
+			.call(
+				&BlockId::Hash(block),
+				method, &args.encode(), ExecutionStrategy::NativeElseWasm, state_machine::NeverOffchainExt::new(),
+			)?;
+		decode_response(method, &data)
+	}
+}
+
+impl<B, E, Block, RA, AccountId> TotemApi<Block::Hash, AccountId> for Totem<B, E, Block, RA> where
+	Block: BlockT<Hash=H256> + 'static,
+	B: client::backend::Backend<Block, Blake2Hasher> + Send + Sync + 'static,
+	E: CallExecutor<Block, Blake2Hasher> + Send + Sync + 'static,
+	RA: Send + Sync + 'static,
+	AccountId: Encode + Decode + Send + Sync + 'static,
+{
+	fn orders_by_party(&self, who: AccountId, party: OrderParty, status: Option<u16>, hash: Option<Block::Hash>) -> Result<Vec<OrderDetails<AccountId, H256>>> {
+		self.call(ORDERS_BY_PARTY, (who, party, status), hash)
+	}
+
+	fn order(&self, order_hash: H256, hash: Option<Block::Hash>) -> Result<Option<OrderDetails<AccountId, H256>>> {
+		self.call(ORDER, order_hash, hash)
+	}
+
+	fn project(&self, project_hash: H256, hash: Option<Block::Hash>) -> Result<Option<ProjectDetails<AccountId, H256>>> {
+		self.call(PROJECT, project_hash, hash)
+	}
+
+	fn timesheet(&self, worker: AccountId, from: u64, to: u64, hash: Option<Block::Hash>) -> Result<Vec<TimeRecordDetails<AccountId, H256>>> {
+		self.call(TIMESHEET, (worker, from, to), hash)
+	}
+
+	fn reputation(&self, who: AccountId, hash: Option<Block::Hash>) -> Result<ReputationScores> {
+		self.call(REPUTATION, who, hash)
+	}
+}
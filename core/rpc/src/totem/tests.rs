@@ -0,0 +1,63 @@
+// Copyright 2017-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+use assert_matches::assert_matches;
+use primitives::sr25519;
+use totem_primitives::OrderLine;
+
+fn order(who: sr25519::Public) -> OrderDetails<sr25519::Public, H256> {
+	OrderDetails {
+		order_hash: H256::repeat_byte(1),
+		commander: who.clone(),
+		fulfiller: who.clone(),
+		approver: who,
+		order_status: 5,
+		approval_status: 1,
+		buy_or_sell: 0,
+		amount: 300,
+		market_order: false,
+		order_type: 2,
+		deadline: 100,
+		due_date: 200,
+		items: vec![OrderLine { product: H256::repeat_byte(2), unit_price: 100, quantity: 3, unit_of_measure: 1 }],
+	}
+}
+
+#[test]
+fn decodes_runtime_response() {
+	let orders = vec![order(sr25519::Public([7u8; 32]))];
+
+	let decoded: Vec<OrderDetails<sr25519::Public, H256>> = decode_response(ORDERS_BY_PARTY, &orders.encode()).unwrap();
+	assert_eq!(decoded, orders);
+}
+
+#[test]
+fn rejects_malformed_response() {
+	assert_matches!(
+		decode_response::<ReputationScores>(REPUTATION, &[1, 2, 3]),
+		Err(error::Error(error::ErrorKind::InvalidResponse(_), _))
+	);
+}
+
+#[test]
+fn should_fail_when_runtime_lacks_api() {
+	let api = Totem::new(Arc::new(test_client::new()));
+
+	let result: Result<Option<ProjectDetails<sr25519::Public, H256>>> = api.project(H256::repeat_byte(1), None);
+	assert!(result.is_err());
+}
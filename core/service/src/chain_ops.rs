@@ -22,6 +22,9 @@ use log::{info, warn};
 
 use runtime_primitives::generic::{SignedBlock, BlockId};
 use runtime_primitives::traits::{As, Block, Header, NumberFor};
+use runtime_primitives::Justification;
+use primitives::storage::well_known_keys;
+use state_machine::Backend as StateBackend;
 use consensus_common::import_queue::{ImportQueue, IncomingBlock, Link};
 use network::message;
 
@@ -209,6 +212,127 @@ pub fn revert_chain<F>(
 	Ok(())
 }
 
+/// Version of the state snapshot format written by `export_state`.
+const STATE_SNAPSHOT_VERSION: u32 = 1;
+
+/// Export the state of a finalized block, along with its header and justification, to a binary
+/// stream.
+///
+/// The snapshot holds the format version, the genesis hash, the header, the justification, the
+/// top-level storage, the child storages and the auxiliary storage, each SCALE encoded.
+pub fn export_state<F, W>(
+	config: FactoryFullConfiguration<F>,
+	at: Option<FactoryBlockNumber<F>>,
+	mut output: W,
+) -> error::Result<()>
+	where F: ServiceFactory, W: Write,
+{
+	let client = new_client::<F>(&config)?;
+	let info = client.info()?.chain;
+	let number = at.unwrap_or(info.finalized_number);
+
+	if number > info.finalized_number {
+		return Err(format!(
+			"Block #{} is not finalized, the last finalized block is #{}",
+			number,
+			info.finalized_number,
+		).into());
+	}
+
+	let id = BlockId::Number(number);
+	let header = client.header(&id)?.ok_or_else(|| format!("Block #{} not found", number))?;
+	let justification = client.justification(&id)?;
+	if justification.is_none() {
+		warn!("Block #{} has no justification", number);
+	}
+
+	info!("Exporting state of block #{} ({})", number, header.hash());
+	let state = client.state_at(&id)?;
+	let (children_keys, top): (Vec<_>, Vec<_>) = state.pairs().into_iter()
+		.partition(|(key, _)| well_known_keys::is_child_storage_key(key));
+
+	let mut children = Vec::with_capacity(children_keys.len());
+	for (storage_key, _) in children_keys {
+		let mut keys = Vec::new();
+		state.for_keys_in_child_storage(&storage_key, |key| keys.push(key.to_vec()));
+		let mut child = Vec::with_capacity(keys.len());
+		for key in keys {
+			let value = state.child_storage(&storage_key, &key)
+				.map_err(|e| format!("Error reading child storage: {}", e))?
+				.ok_or("Child storage key without value")?;
+			child.push((key, value));
+		}
+		children.push((storage_key, child));
+	}
+	let aux = client.backend().aux_entries();
+
+	output.write_all(&STATE_SNAPSHOT_VERSION.encode())?;
+	output.write_all(&info.genesis_hash.encode())?;
+	output.write_all(&header.encode())?;
+	output.write_all(&justification.encode())?;
+	output.write_all(&top.encode())?;
+	output.write_all(&children.encode())?;
+	output.write_all(&aux.encode())?;
+
+	info!("Exported {} keys and {} child storages", top.len(), children.len());
+	Ok(())
+}
+
+/// Import a state snapshot written by `export_state` into a database that holds no block but
+/// genesis.
+///
+/// The snapshot header must hash to `hash`, a checkpoint obtained from a trusted source, and the
+/// state is checked against the state root of that header. The justification is stored as it is.
+pub fn import_state<F, R>(
+	config: FactoryFullConfiguration<F>,
+	mut input: R,
+	hash: <F::Block as Block>::Hash,
+) -> error::Result<()>
+	where F: ServiceFactory, R: Read,
+{
+	let client = new_client::<F>(&config)?;
+
+	let version: u32 = Decode::decode(&mut input).ok_or("Error reading file")?;
+	if version != STATE_SNAPSHOT_VERSION {
+		return Err(format!("Unsupported state snapshot version {}", version).into());
+	}
+	let genesis_hash: <F::Block as Block>::Hash = Decode::decode(&mut input)
+		.ok_or("Error reading genesis hash")?;
+	if genesis_hash != client.info()?.chain.genesis_hash {
+		return Err("The state snapshot belongs to a different chain".into());
+	}
+	let header: <F::Block as Block>::Header = Decode::decode(&mut input)
+		.ok_or("Error reading header")?;
+	if header.hash() != hash {
+		return Err(format!(
+			"The state snapshot is of block {}, expected the trusted block {}",
+			header.hash(),
+			hash,
+		).into());
+	}
+	let justification: Option<Justification> = Decode::decode(&mut input)
+		.ok_or("Error reading justification")?;
+	let top: Vec<(Vec<u8>, Vec<u8>)> = Decode::decode(&mut input)
+		.ok_or("Error reading storage")?;
+	let children: Vec<(Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>)> = Decode::decode(&mut input)
+		.ok_or("Error reading child storage")?;
+	let aux: Vec<(Vec<u8>, Vec<u8>)> = Decode::decode(&mut input)
+		.ok_or("Error reading auxiliary storage")?;
+
+	info!("Importing state of block #{} ({})", header.number(), header.hash());
+	client.backend().import_state(
+		header,
+		justification,
+		top.into_iter().collect(),
+		children.into_iter().map(|(key, child)| (key, child.into_iter().collect())).collect(),
+		aux,
+	)?;
+
+	let info = client.info()?.chain;
+	info!("Imported state. Best: #{} ({})", info.best_number, info.best_hash);
+	Ok(())
+}
+
 /// Build a chain spec json
 pub fn build_spec<G>(spec: ChainSpec<G>, raw: bool) -> error::Result<String>
 	where G: RuntimeGenesis,
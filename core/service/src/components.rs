@@ -18,12 +18,14 @@
 
 use std::{sync::Arc, net::SocketAddr, marker::PhantomData, ops::Deref, ops::DerefMut};
 use serde::{Serialize, de::DeserializeOwned};
+use parity_codec::Codec;
 use tokio::runtime::TaskExecutor;
 use crate::chain_spec::ChainSpec;
 use client_db;
 use client::{self, Client, runtime_api};
 use crate::{error, Service, maybe_start_server};
 use consensus_common::import_queue::ImportQueue;
+use keystore::KeystoreBackend;
 use network::{self, OnDemand};
 use substrate_executor::{NativeExecutor, NativeExecutionDispatch};
 use transaction_pool::txpool::{self, Options as TransactionPoolOptions, Pool as TransactionPool};
@@ -102,6 +104,9 @@ pub type FactoryBlock<F> = <F as ServiceFactory>::Block;
 /// `Extrinsic` type for a factory.
 pub type FactoryExtrinsic<F> = <<F as ServiceFactory>::Block as BlockT>::Extrinsic;
 
+/// `AccountId` type for a factory.
+pub type FactoryAccountId<F> = <F as ServiceFactory>::AccountId;
+
 /// `Number` type for a factory.
 pub type FactoryBlockNumber<F> = <<FactoryBlock<F> as BlockT>::Header as HeaderT>::Number;
 
@@ -143,8 +148,12 @@ pub trait StartRPC<C: Components> {
 		system_info: SystemInfo,
 		rpc_http: Option<SocketAddr>,
 		rpc_ws: Option<SocketAddr>,
+		rpc_config: rpc::ServerConfig,
+		rpc_methods: rpc::RpcMethods,
 		task_executor: TaskExecutor,
 		transaction_pool: Arc<TransactionPool<C::TransactionPoolApi>>,
+		keystore: Arc<dyn KeystoreBackend>,
+		keystore_password: String,
 	) -> error::Result<Self::ServersHandle>;
 }
 
@@ -161,8 +170,12 @@ impl<C: Components> StartRPC<Self> for C where
 		rpc_system_info: SystemInfo,
 		rpc_http: Option<SocketAddr>,
 		rpc_ws: Option<SocketAddr>,
+		rpc_config: rpc::ServerConfig,
+		rpc_methods: rpc::RpcMethods,
 		task_executor: TaskExecutor,
 		transaction_pool: Arc<TransactionPool<C::TransactionPoolApi>>,
+		keystore: Arc<dyn KeystoreBackend>,
+		keystore_password: String,
 	) -> error::Result<Self::ServersHandle> {
 		let handler = || {
 			let client = client.clone();
@@ -175,17 +188,25 @@ impl<C: Components> StartRPC<Self> for C where
 			let system = rpc::apis::system::System::new(
 				rpc_system_info.clone(), network.clone(), should_have_peers
 			);
-			rpc::rpc_handler::<ComponentBlock<C>, ComponentExHash<C>, _, _, _, _>(
+			let bonsai = rpc::apis::bonsai::Bonsai::new(client.clone());
+			let messaging = rpc::apis::messaging::Messaging::new(client.clone(), Some(keystore.clone()))
+				.with_password(keystore_password.clone());
+			let totem = rpc::apis::totem::Totem::new(client.clone());
+			rpc::rpc_handler::<ComponentBlock<C>, ComponentExHash<C>, FactoryAccountId<C::Factory>, _, _, _, _, _, _, _>(
 				state,
 				chain,
 				author,
 				system,
+				bonsai,
+				messaging,
+				totem,
+				rpc_methods,
 			)
 		};
 
 		Ok((
-			maybe_start_server(rpc_http, |address| rpc::start_http(address, handler()))?,
-			maybe_start_server(rpc_ws, |address| rpc::start_ws(address, handler()))?.map(Mutex::new),
+			maybe_start_server(rpc_http, |address| rpc::start_http(address, &rpc_config, handler()))?,
+			maybe_start_server(rpc_ws, |address| rpc::start_ws(address, &rpc_config, handler()))?.map(Mutex::new),
 		))
 	}
 }
@@ -285,6 +306,8 @@ pub trait ServiceFactory: 'static + Sized {
 	type Block: BlockT<Hash=H256>;
 	/// The type that implements the runtime API.
 	type RuntimeApi: Send + Sync;
+	/// Account id of the runtime, as taken and returned by the RPC.
+	type AccountId: Codec + Serialize + DeserializeOwned + Send + Sync + 'static;
 	/// Network protocol extensions.
 	type NetworkProtocol: network::specialization::NetworkSpecialization<Self::Block>;
 	/// Chain runtime.
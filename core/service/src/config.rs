@@ -16,12 +16,13 @@
 
 //! Service configuration.
 
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use transaction_pool;
 use crate::chain_spec::ChainSpec;
 pub use client::ExecutionStrategies;
 pub use client_db::PruningMode;
 pub use network::config::{NetworkConfiguration, Roles};
+pub use rpc::RpcMethods;
 use runtime_primitives::BuildStorage;
 use serde::{Serialize, de::DeserializeOwned};
 use target_info::Target;
@@ -44,6 +45,8 @@ pub struct Configuration<C, G: Serialize + DeserializeOwned + BuildStorage> {
 	pub network: NetworkConfiguration,
 	/// Path to key files.
 	pub keystore_path: String,
+	/// Password of the keys the node signs with, such as the offchain worker keys.
+	pub password: String,
 	/// Path to the database.
 	pub database_path: String,
 	/// Cache Size for internal database in MiB
@@ -64,6 +67,16 @@ pub struct Configuration<C, G: Serialize + DeserializeOwned + BuildStorage> {
 	pub rpc_http: Option<SocketAddr>,
 	/// RPC over Websockets binding address. `None` if disabled.
 	pub rpc_ws: Option<SocketAddr>,
+	/// Origins allowed to call the RPC servers. `None` allows any origin.
+	pub rpc_cors: Option<Vec<String>>,
+	/// Maximal number of RPC requests per second per client. `None` if unlimited.
+	pub rpc_rate_limit: Option<u32>,
+	/// Reverse proxies whose forwarded client addresses are used for rate limiting.
+	pub rpc_trusted_proxies: Vec<IpAddr>,
+	/// Maximal number of concurrent Websocket connections. `None` for the default.
+	pub rpc_ws_max_connections: Option<usize>,
+	/// Which RPC methods are exposed.
+	pub rpc_methods: RpcMethods,
 	/// Telemetry service URL. `None` if disabled.
 	pub telemetry_endpoints: Option<TelemetryEndpoints>,
 	/// The default number of 64KB pages to allocate for Wasm execution
@@ -89,6 +102,7 @@ impl<C: Default, G: Serialize + DeserializeOwned + BuildStorage> Configuration<C
 			transaction_pool: Default::default(),
 			network: Default::default(),
 			keystore_path: Default::default(),
+			password: Default::default(),
 			database_path: Default::default(),
 			database_cache_size: Default::default(),
 			keys: Default::default(),
@@ -97,6 +111,11 @@ impl<C: Default, G: Serialize + DeserializeOwned + BuildStorage> Configuration<C
 			execution_strategies: Default::default(),
 			rpc_http: None,
 			rpc_ws: None,
+			rpc_cors: None,
+			rpc_rate_limit: None,
+			rpc_trusted_proxies: Vec::new(),
+			rpc_ws_max_connections: None,
+			rpc_methods: Default::default(),
 			telemetry_endpoints: None,
 			default_heap_pages: None,
 			offchain_worker: Default::default(),
@@ -43,7 +43,7 @@ use substrate_executor::NativeExecutor;
 use tel::{telemetry, SUBSTRATE_INFO};
 
 pub use self::error::{ErrorKind, Error};
-pub use config::{Configuration, Roles, PruningMode};
+pub use config::{Configuration, Roles, PruningMode, RpcMethods};
 pub use chain_spec::{ChainSpec, Properties};
 pub use transaction_pool::txpool::{
 	self, Pool as TransactionPool, Options as TransactionPoolOptions, ChainApi, IntoPoolError
@@ -56,7 +56,7 @@ pub use components::{ServiceFactory, FullBackend, FullExecutor, LightBackend,
 	ComponentBlock, FullClient, LightClient, FullComponents, LightComponents,
 	CodeExecutor, NetworkService, FactoryChainSpec, FactoryBlock,
 	FactoryFullConfiguration, RuntimeGenesis, FactoryGenesis,
-	ComponentExHash, ComponentExtrinsic, FactoryExtrinsic
+	ComponentExHash, ComponentExtrinsic, FactoryExtrinsic, FactoryAccountId
 };
 use components::{StartRPC, MaintainTransactionPool, OffchainWorker};
 #[doc(hidden)]
@@ -74,7 +74,7 @@ pub struct Service<Components: components::Components> {
 	network: Option<Arc<components::NetworkService<Components::Factory>>>,
 	transaction_pool: Arc<TransactionPool<Components::TransactionPoolApi>>,
 	inherents_pool: Arc<InherentsPool<ComponentExtrinsic<Components>>>,
-	keystore: Keystore,
+	keystore: Arc<Keystore>,
 	exit: ::exit_future::Exit,
 	signal: Option<Signal>,
 	/// Configuration of this Service
@@ -125,6 +125,7 @@ impl<Components: components::Components> Service<Components> {
 				public_key
 			}
 		};
+		let keystore = Arc::new(keystore);
 
 		let (client, on_demand) = Components::build_client(&config, executor)?;
 		let import_queue = Box::new(Components::build_import_queue(&mut config, client.clone())?);
@@ -179,8 +180,11 @@ impl<Components: components::Components> Service<Components> {
 			Some(Arc::new(offchain::OffchainWorkers::new(
 				client.clone(),
 				inherents_pool.clone(),
+				client::backend::Backend::offchain_storage(&**client.backend()),
+				Some(keystore.clone()),
+				Some(Arc::new(offchain::http::HyperBackend::new(task_executor.clone()))),
 				task_executor.clone(),
-			)))
+			).with_password(config.password.clone())))
 		} else {
 			None
 		};
@@ -293,9 +297,16 @@ impl<Components: components::Components> Service<Components> {
 			impl_version: config.impl_version.into(),
 			properties: config.chain_spec.properties(),
 		};
+		let rpc_config = rpc::ServerConfig {
+			cors: config.rpc_cors.clone(),
+			rate_limit: config.rpc_rate_limit,
+			trusted_proxies: config.rpc_trusted_proxies.clone(),
+			max_connections: config.rpc_ws_max_connections,
+		};
 		let rpc = Components::RuntimeServices::start_rpc(
 			client.clone(), network.clone(), has_bootnodes, system_info, config.rpc_http,
-			config.rpc_ws, task_executor.clone(), transaction_pool.clone(),
+			config.rpc_ws, rpc_config, config.rpc_methods, task_executor.clone(), transaction_pool.clone(),
+			keystore.clone(), config.password.clone(),
 		)?;
 
 		// Telemetry
@@ -502,6 +513,9 @@ impl<C: Components> network::TransactionPool<ComponentExHash<C>, ComponentBlock<
 /// 	struct Factory {
 ///         // Declare the block type
 /// 		Block = Block,
+/// 		RuntimeApi = RuntimeApi,
+///         // Declare the account id served by the RPC
+/// 		AccountId = AccountId,
 ///         // Declare the network protocol and give an initializer.
 /// 		NetworkProtocol = NodeProtocol { |config| Ok(NodeProtocol::new()) },
 /// 		RuntimeDispatch = node_executor::Executor,
@@ -534,6 +548,7 @@ macro_rules! construct_service_factory {
 		struct $name:ident {
 			Block = $block:ty,
 			RuntimeApi = $runtime_api:ty,
+			AccountId = $account_id:ty,
 			NetworkProtocol = $protocol:ty { $( $protocol_init:tt )* },
 			RuntimeDispatch = $dispatch:ty,
 			FullTransactionPoolApi = $full_transaction:ty { $( $full_transaction_init:tt )* },
@@ -556,6 +571,7 @@ macro_rules! construct_service_factory {
 		impl $crate::ServiceFactory for $name {
 			type Block = $block;
 			type RuntimeApi = $runtime_api;
+			type AccountId = $account_id;
 			type NetworkProtocol = $protocol;
 			type RuntimeDispatch = $dispatch;
 			type FullTransactionPoolApi = $full_transaction;
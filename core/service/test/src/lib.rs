@@ -110,6 +110,7 @@ fn node_config<F: ServiceFactory> (
 		transaction_pool: Default::default(),
 		network: network_config,
 		keystore_path: root.join("key").to_str().unwrap().into(),
+		password: Default::default(),
 		database_path: root.join("db").to_str().unwrap().into(),
 		database_cache_size: None,
 		pruning: Default::default(),
@@ -120,6 +121,11 @@ fn node_config<F: ServiceFactory> (
 		execution_strategies: Default::default(),
 		rpc_http: None,
 		rpc_ws: None,
+		rpc_cors: None,
+		rpc_rate_limit: None,
+		rpc_trusted_proxies: Vec::new(),
+		rpc_ws_max_connections: None,
+		rpc_methods: Default::default(),
 		telemetry_endpoints: None,
 		default_heap_pages: None,
 		offchain_worker: false,
@@ -22,6 +22,7 @@ pub use primitives::{
 	sr25519, Pair
 };
 pub use tiny_keccak::keccak256 as keccak_256;
+pub use primitives::offchain::{HttpMethod, HttpRequest, HttpResponse, HttpError};
 // Switch to this after PoC-3
 // pub use primitives::BlakeHasher;
 pub use substrate_state_machine::{
@@ -261,6 +262,52 @@ pub fn submit_extrinsic<T: codec::Encode>(data: &T) {
 	).expect("submit_extrinsic cannot be called outside of an Externalities-provided environment.")
 }
 
+/// Execute a closure with the offchain worker extensions.
+fn with_offchain<R>(f: impl FnOnce(&mut dyn primitives::OffchainExt) -> R, msg: &'static str) -> R {
+	ext::with(|ext| ext
+		.offchain()
+		.map(|ext| f(ext))
+		.expect(msg)
+	).expect("offchain-worker functions cannot be called outside of an Externalities-provided environment.")
+}
+
+/// Get a value from the node-local offchain storage.
+pub fn local_storage_get(key: &[u8]) -> Option<Vec<u8>> {
+	with_offchain(|ext| ext.local_storage_get(key), "local_storage_get can be called only in offchain worker context")
+}
+
+/// Set a value in the node-local offchain storage.
+pub fn local_storage_set(key: &[u8], value: &[u8]) {
+	with_offchain(|ext| ext.local_storage_set(key, value), "local_storage_set can be called only in offchain worker context")
+}
+
+/// Set a value in the node-local offchain storage if the current value equals `old_value`.
+///
+/// Returns `true` if the value has been set.
+pub fn local_storage_compare_and_set(key: &[u8], old_value: Option<&[u8]>, new_value: &[u8]) -> bool {
+	with_offchain(
+		|ext| ext.local_storage_compare_and_set(key, old_value, new_value),
+		"local_storage_compare_and_set can be called only in offchain worker context",
+	)
+}
+
+/// Get the ed25519 public keys available in the node keystore.
+pub fn keystore_public_keys() -> Vec<[u8; 32]> {
+	with_offchain(|ext| ext.pubkeys(), "keystore_public_keys can be called only in offchain worker context")
+}
+
+/// Sign `data` with the keystore key of given ed25519 public key.
+///
+/// Returns `None` if the key is not available.
+pub fn keystore_sign(key: &[u8; 32], data: &[u8]) -> Option<[u8; 64]> {
+	with_offchain(|ext| ext.sign(*key, data), "keystore_sign can be called only in offchain worker context")
+}
+
+/// Perform an HTTP request and wait for the response.
+pub fn http_request(request: &HttpRequest) -> Result<HttpResponse, HttpError> {
+	with_offchain(|ext| ext.http_request(request.clone()), "http_request can be called only in offchain worker context")
+}
+
 /// Execute the given closure with global function available whose functionality routes into the
 /// externalities `ext`. Forwards the value that the closure returns.
 // NOTE: need a concrete hasher here due to limitations of the `environmental!` macro, otherwise a type param would have been fine I think.
@@ -19,6 +19,7 @@ pub use parity_codec as codec;
 #[doc(hidden)]
 pub use rstd;
 pub use rstd::{mem, slice};
+pub use primitives::offchain::{HttpMethod, HttpRequest, HttpResponse, HttpError};
 
 use core::{intrinsics, panic::PanicInfo};
 use rstd::{vec::Vec, cell::Cell};
@@ -292,6 +293,26 @@ extern_functions! {
 
 	/// Submit extrinsic.
 	fn ext_submit_extrinsic(data: *const u8, len: u32);
+	/// Get a value from the local storage; `written_out` is `u32::max_value()` if there is none.
+	fn ext_local_storage_get(key_data: *const u8, key_len: u32, written_out: *mut u32) -> *mut u8;
+	/// Set a value in the local storage.
+	fn ext_local_storage_set(key_data: *const u8, key_len: u32, value_data: *const u8, value_len: u32);
+	/// Set a value in the local storage if the current one matches; `old_value_len` of
+	/// `u32::max_value()` means no value is expected. Returns 1 if the value has been set.
+	fn ext_local_storage_compare_and_set(
+		key_data: *const u8,
+		key_len: u32,
+		old_value_data: *const u8,
+		old_value_len: u32,
+		new_value_data: *const u8,
+		new_value_len: u32
+	) -> u32;
+	/// Get the concatenated ed25519 public keys of the keystore.
+	fn ext_keystore_public_keys(written_out: *mut u32) -> *mut u8;
+	/// Note: ext_keystore_sign returns 0 if the signature has been written to `out`, nonzero otherwise.
+	fn ext_keystore_sign(key_data: *const u8, msg_data: *const u8, msg_len: u32, out: *mut u8) -> u32;
+	/// Perform an encoded `HttpRequest`, returning an encoded `Result<HttpResponse, HttpError>`.
+	fn ext_http_request(request_data: *const u8, request_len: u32, written_out: *mut u32) -> *mut u8;
 }
 
 /// Ensures we use the right crypto when calling into native
@@ -633,6 +654,85 @@ pub fn submit_extrinsic<T: codec::Encode>(data: &T) {
 	}
 }
 
+/// Get a value from the node-local offchain storage.
+pub fn local_storage_get(key: &[u8]) -> Option<Vec<u8>> {
+	let mut length: u32 = 0;
+	unsafe {
+		let ptr = ext_local_storage_get.get()(key.as_ptr(), key.len() as u32, &mut length);
+		if length == u32::max_value() {
+			None
+		} else {
+			// Invariants required by Vec::from_raw_parts are not formally fulfilled.
+			// We don't allocate via String/Vec<T>, but use a custom allocator instead.
+			// See #300 for more details.
+			Some(<Vec<u8>>::from_raw_parts(ptr, length as usize, length as usize))
+		}
+	}
+}
+
+/// Set a value in the node-local offchain storage.
+pub fn local_storage_set(key: &[u8], value: &[u8]) {
+	unsafe {
+		ext_local_storage_set.get()(key.as_ptr(), key.len() as u32, value.as_ptr(), value.len() as u32);
+	}
+}
+
+/// Set a value in the node-local offchain storage if the current value equals `old_value`.
+///
+/// Returns `true` if the value has been set.
+pub fn local_storage_compare_and_set(key: &[u8], old_value: Option<&[u8]>, new_value: &[u8]) -> bool {
+	let (old_ptr, old_len) = match old_value {
+		Some(old) => (old.as_ptr(), old.len() as u32),
+		None => (rstd::ptr::null(), u32::max_value()),
+	};
+	unsafe {
+		ext_local_storage_compare_and_set.get()(
+			key.as_ptr(), key.len() as u32,
+			old_ptr, old_len,
+			new_value.as_ptr(), new_value.len() as u32
+		) == 1
+	}
+}
+
+/// Get the ed25519 public keys available in the node keystore.
+pub fn keystore_public_keys() -> Vec<[u8; 32]> {
+	let mut length: u32 = 0;
+	let raw = unsafe {
+		let ptr = ext_keystore_public_keys.get()(&mut length);
+		<Vec<u8>>::from_raw_parts(ptr, length as usize, length as usize)
+	};
+	raw.chunks(32).filter(|c| c.len() == 32).map(|c| {
+		let mut key = [0u8; 32];
+		key.copy_from_slice(c);
+		key
+	}).collect()
+}
+
+/// Sign `data` with the keystore key of given ed25519 public key.
+///
+/// Returns `None` if the key is not available.
+pub fn keystore_sign(key: &[u8; 32], data: &[u8]) -> Option<[u8; 64]> {
+	let mut signature = [0u8; 64];
+	match unsafe {
+		ext_keystore_sign.get()(key.as_ptr(), data.as_ptr(), data.len() as u32, signature.as_mut_ptr())
+	} {
+		0 => Some(signature),
+		_ => None,
+	}
+}
+
+/// Perform an HTTP request and wait for the response.
+pub fn http_request(request: &HttpRequest) -> Result<HttpResponse, HttpError> {
+	let encoded_request = codec::Encode::encode(request);
+	let mut length: u32 = 0;
+	let encoded_result = unsafe {
+		let ptr = ext_http_request.get()(encoded_request.as_ptr(), encoded_request.len() as u32, &mut length);
+		<Vec<u8>>::from_raw_parts(ptr, length as usize, length as usize)
+	};
+	codec::Decode::decode(&mut &encoded_result[..])
+		.expect("ext_http_request always returns an encoded result; qed")
+}
+
 /// Trait for things which can be printed.
 pub trait Printable {
 	fn print(self);
@@ -42,6 +42,7 @@ pub mod testing;
 pub mod traits;
 pub mod generic;
 pub mod transaction_validity;
+pub mod weights;
 
 /// A message indicating an invalid signature in extrinsic.
 pub const BAD_SIGNATURE: &str = "bad signature in extrinsic";
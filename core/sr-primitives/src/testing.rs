@@ -21,6 +21,7 @@ use std::{fmt::Debug, ops::Deref, fmt};
 use crate::codec::{Codec, Encode, Decode};
 use crate::traits::{self, Checkable, Applyable, BlakeTwo256, Convert};
 use crate::generic::DigestItem as GenDigestItem;
+use crate::weights::{Weighable, Weight};
 pub use substrate_primitives::H256;
 use substrate_primitives::U256;
 use substrate_primitives::ed25519::{Public as AuthorityId, Signature as AuthoritySignature};
@@ -225,6 +226,11 @@ impl<Call: Codec + Sync + Send> traits::Extrinsic for TestXt<Call> {
 		None
 	}
 }
+impl<Call: Weighable> Weighable for TestXt<Call> {
+	fn weight(&self, len: usize) -> Weight {
+		self.2.weight(len)
+	}
+}
 impl<Call> Applyable for TestXt<Call> where
 	Call: 'static + Sized + Send + Sync + Clone + Eq + Codec + Debug,
 {
@@ -0,0 +1,70 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Primitives for transaction weighting.
+//!
+//! Every dispatchable function declares a `TransactionWeight`, an estimate of the work it does.
+//! The weight of the extrinsics in a block is limited, and transaction fees are computed from it.
+//! A call without an annotation weighs one unit per encoded byte.
+
+/// Numeric range of a transaction weight.
+pub type Weight = u32;
+
+/// Something that has a weight when included in a block.
+pub trait Weighable {
+	/// Return the weight of this, given its encoded length `len` in bytes.
+	fn weight(&self, len: usize) -> Weight;
+}
+
+/// Weight annotation of a dispatchable function.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TransactionWeight {
+	/// A fixed weight plus a weight per encoded byte of the extrinsic.
+	Basic(Weight, Weight),
+	/// No weight at all; only for calls which must always fit in a block.
+	Free,
+}
+
+impl Weighable for TransactionWeight {
+	fn weight(&self, len: usize) -> Weight {
+		match *self {
+			TransactionWeight::Basic(fixed, per_byte) =>
+				fixed.saturating_add(per_byte.saturating_mul(len as Weight)),
+			TransactionWeight::Free => 0,
+		}
+	}
+}
+
+impl Default for TransactionWeight {
+	/// The weight of a call which does little besides decoding its parameters: its length.
+	fn default() -> Self {
+		TransactionWeight::Basic(0, 1)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn basic_weight_grows_with_length() {
+		assert_eq!(TransactionWeight::default().weight(100), 100);
+		assert_eq!(TransactionWeight::Basic(1_000, 2).weight(100), 1_200);
+		assert_eq!(TransactionWeight::Basic(Weight::max_value(), 1).weight(100), Weight::max_value());
+		assert_eq!(TransactionWeight::Free.weight(100), 0);
+	}
+}
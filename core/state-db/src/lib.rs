@@ -226,6 +226,22 @@ impl<BlockHash: Hash, Key: Hash> StateDbSync<BlockHash, Key> {
 		Ok(commit)
 	}
 
+	pub fn import_canonical<E: fmt::Debug>(&mut self, hash: &BlockHash, number: u64, mut changeset: ChangeSet<Key>) -> Result<CommitSet<Key>, Error<E>> {
+		changeset.deleted.clear();
+		let mut commit = match self.mode {
+			PruningMode::ArchiveAll => CommitSet::default(),
+			PruningMode::ArchiveCanonical | PruningMode::Constrained(_) => {
+				self.non_canonical.import_canonical(hash, number)?
+			},
+		};
+		commit.data = changeset;
+		if let Some(ref mut pruning) = self.pruning {
+			pruning.note_canonical(hash, &mut commit);
+		}
+		self.prune(&mut commit);
+		Ok(commit)
+	}
+
 	pub fn best_canonical(&self) -> Option<u64> {
 		return self.non_canonical.last_canonicalized_block_number()
 	}
@@ -340,6 +356,13 @@ impl<BlockHash: Hash, Key: Hash> StateDb<BlockHash, Key> {
 		self.db.write().canonicalize_block(hash)
 	}
 
+	/// Add a canonical block whose parent is not known, e.g. when its state is imported from a
+	/// snapshot. `changeset` must contain the whole state of the block. Only possible while there
+	/// are no non-canonical blocks.
+	pub fn import_canonical<E: fmt::Debug>(&self, hash: &BlockHash, number: u64, changeset: ChangeSet<Key>) -> Result<CommitSet<Key>, Error<E>> {
+		self.db.write().import_canonical(hash, number, changeset)
+	}
+
 	/// Prevents pruning of specified block and its descendants.
 	pub fn pin(&self, hash: &BlockHash) {
 		self.db.write().pin(hash)
@@ -505,4 +528,36 @@ mod tests {
 		assert!(sdb.is_pruned(&H256::from_low_u64_be(22), 2));
 		assert!(db.data_eq(&make_db(&[1, 21, 3, 921, 922, 93, 94])));
 	}
+
+	#[test]
+	fn import_canonical_without_parent() {
+		let mut db = make_db(&[]);
+		let state_db: StateDb<H256, H256> = StateDb::new(PruningMode::keep_blocks(2), &db).unwrap();
+		db.commit(&state_db.import_canonical::<io::Error>(&H256::from_low_u64_be(10), 10, make_changeset(&[1, 2], &[3])).unwrap());
+		state_db.apply_pending();
+		assert_eq!(state_db.best_canonical(), Some(10));
+		assert!(!state_db.is_pruned(&H256::from_low_u64_be(10), 10));
+		assert!(db.data_eq(&make_db(&[1, 2])));
+
+		db.commit(
+			&state_db
+				.insert_block::<io::Error>(
+					&H256::from_low_u64_be(11),
+					11,
+					&H256::from_low_u64_be(10),
+					make_changeset(&[4], &[1]),
+				)
+				.unwrap(),
+		);
+		state_db.apply_pending();
+		assert!(state_db.import_canonical::<io::Error>(&H256::from_low_u64_be(12), 12, make_changeset(&[], &[])).is_err());
+		db.commit(&state_db.canonicalize_block::<io::Error>(&H256::from_low_u64_be(11)).unwrap());
+		state_db.apply_pending();
+		assert!(db.data_eq(&make_db(&[1, 2, 4])));
+
+		// the imported block is still known after reopening the database
+		let state_db: StateDb<H256, H256> = StateDb::new(PruningMode::keep_blocks(2), &db).unwrap();
+		assert_eq!(state_db.best_canonical(), Some(11));
+		assert!(!state_db.is_pruned(&H256::from_low_u64_be(10), 10));
+	}
 }
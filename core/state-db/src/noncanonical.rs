@@ -36,6 +36,7 @@ pub struct NonCanonicalOverlay<BlockHash: Hash, Key: Hash> {
 	parents: HashMap<BlockHash, BlockHash>,
 	pending_canonicalizations: Vec<BlockHash>,
 	pending_insertions: Vec<BlockHash>,
+	pending_import: Option<(BlockHash, u64)>,
 	values: HashMap<Key, (u32, DBValue)>, //ref counted
 }
 
@@ -166,6 +167,7 @@ impl<BlockHash: Hash, Key: Hash> NonCanonicalOverlay<BlockHash, Key> {
 			parents,
 			pending_canonicalizations: Default::default(),
 			pending_insertions: Default::default(),
+			pending_import: None,
 			values: values,
 		})
 	}
@@ -230,6 +232,23 @@ impl<BlockHash: Hash, Key: Hash> NonCanonicalOverlay<BlockHash, Key> {
 		Ok(commit)
 	}
 
+	/// Set the last canonicalized block without inserting it into the overlay first, e.g. when
+	/// the state of that block is imported from elsewhere. The overlay must be empty and the
+	/// block above the last canonicalized one. Takes effect on `apply_pending`.
+	pub fn import_canonical<E: fmt::Debug>(&mut self, hash: &BlockHash, number: u64) -> Result<CommitSet<Key>, Error<E>> {
+		if !self.levels.is_empty() || self.pending_import.is_some()
+			|| self.last_canonicalized.as_ref().map_or(false, |&(_, n)| number <= n)
+		{
+			return Err(Error::InvalidBlockNumber);
+		}
+		let mut commit = CommitSet::default();
+		let canonicalized = (hash.clone(), number);
+		commit.meta.inserted.push((to_meta_key(LAST_CANONICAL, &()), canonicalized.encode()));
+		trace!(target: "state-db", "Imported canonical block #{} ({:?})", number, hash);
+		self.pending_import = Some(canonicalized);
+		Ok(commit)
+	}
+
 	fn discard_journals(&self, level_index: usize, discarded_journals: &mut Vec<Vec<u8>>, hash: &BlockHash) {
 		if let Some(level) = self.levels.get(level_index) {
 			level.iter().for_each(|overlay| {
@@ -374,12 +393,16 @@ impl<BlockHash: Hash, Key: Hash> NonCanonicalOverlay<BlockHash, Key> {
 	pub fn apply_pending(&mut self) {
 		self.apply_canonicalizations();
 		self.pending_insertions.clear();
+		if let Some(imported) = self.pending_import.take() {
+			self.last_canonicalized = Some(imported);
+		}
 	}
 
 	/// Revert all pending changes
 	pub fn revert_pending(&mut self) {
 		self.pending_canonicalizations.clear();
 		self.revert_insertions();
+		self.pending_import = None;
 	}
 }
 
@@ -727,5 +750,21 @@ mod tests {
 		assert_eq!(overlay.levels.len(), 0);
 		assert_eq!(overlay.parents.len(), 0);
 	}
+
+	#[test]
+	fn revert_pending_import() {
+		let h1 = H256::random();
+		let h2 = H256::random();
+		let db = make_db(&[]);
+		let mut overlay = NonCanonicalOverlay::<H256, H256>::new(&db).unwrap();
+		overlay.import_canonical::<io::Error>(&h1, 10).unwrap();
+		assert!(overlay.import_canonical::<io::Error>(&h2, 11).is_err());
+		overlay.revert_pending();
+		assert_eq!(overlay.last_canonicalized_hash(), None);
+		overlay.import_canonical::<io::Error>(&h2, 11).unwrap();
+		overlay.apply_pending();
+		assert_eq!(overlay.last_canonicalized_hash(), Some(h2));
+		assert_eq!(overlay.last_canonicalized_block_number(), Some(11));
+	}
 }
 
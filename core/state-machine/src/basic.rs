@@ -23,7 +23,7 @@ use heapsize::HeapSizeOf;
 use trie::trie_root;
 use primitives::storage::well_known_keys::{CHANGES_TRIE_CONFIG, CODE, HEAP_PAGES};
 use parity_codec::Encode;
-use super::{ChildStorageKey, Externalities, OverlayedChanges};
+use super::{ChildStorageKey, Externalities, OverlayedChanges, OffchainExt};
 use log::warn;
 
 /// Simple HashMap-based Externalities impl.
@@ -160,6 +160,11 @@ impl<H: Hasher> Externalities<H> for BasicExternalities where H::Out: Ord + Heap
 		warn!("Call to submit_extrinsic without offchain externalities set.");
 		Err(())
 	}
+
+	fn offchain(&mut self) -> Option<&mut dyn OffchainExt> {
+		warn!("Call to offchain without offchain externalities set.");
+		None
+	}
 }
 
 #[cfg(test)]
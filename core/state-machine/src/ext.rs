@@ -356,6 +356,10 @@ where
 			Err(())
 		}
 	}
+
+	fn offchain(&mut self) -> Option<&mut dyn OffchainExt> {
+		self.offchain_externalities.as_mut().map(|x| &mut **x as _)
+	}
 }
 
 #[cfg(test)]
@@ -221,6 +221,9 @@ pub trait Externalities<H: Hasher> {
 	///
 	/// Returns an error in case the API is not available.
 	fn submit_extrinsic(&mut self, extrinsic: Vec<u8>) -> Result<(), ()>;
+
+	/// Returns the offchain worker extensions, if available.
+	fn offchain(&mut self) -> Option<&mut dyn OffchainExt>;
 }
 
 /// An implementation of offchain extensions that should never be triggered.
@@ -235,6 +238,26 @@ impl NeverOffchainExt {
 
 impl OffchainExt for NeverOffchainExt {
 	fn submit_extrinsic(&mut self, _extrinsic: Vec<u8>) { unreachable!() }
+
+	fn local_storage_get(&mut self, _key: &[u8]) -> Option<Vec<u8>> { unreachable!() }
+
+	fn local_storage_set(&mut self, _key: &[u8], _value: &[u8]) { unreachable!() }
+
+	fn local_storage_compare_and_set(
+		&mut self,
+		_key: &[u8],
+		_old_value: Option<&[u8]>,
+		_new_value: &[u8],
+	) -> bool { unreachable!() }
+
+	fn pubkeys(&mut self) -> Vec<[u8; 32]> { unreachable!() }
+
+	fn sign(&mut self, _key: [u8; 32], _data: &[u8]) -> Option<[u8; 64]> { unreachable!() }
+
+	fn http_request(
+		&mut self,
+		_request: primitives::offchain::HttpRequest,
+	) -> Result<primitives::offchain::HttpResponse, primitives::offchain::HttpError> { unreachable!() }
 }
 
 /// Code execution engine.
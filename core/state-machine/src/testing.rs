@@ -25,7 +25,7 @@ use crate::backend::InMemory;
 use crate::changes_trie::{compute_changes_trie_root, InMemoryStorage as ChangesTrieInMemoryStorage, AnchorBlockId};
 use primitives::storage::well_known_keys::{CHANGES_TRIE_CONFIG, CODE, HEAP_PAGES};
 use parity_codec::Encode;
-use super::{ChildStorageKey, Externalities, OverlayedChanges};
+use super::{ChildStorageKey, Externalities, OverlayedChanges, OffchainExt};
 
 /// Simple HashMap-based Externalities impl.
 pub struct TestExternalities<H: Hasher> where H::Out: HeapSizeOf {
@@ -33,6 +33,7 @@ pub struct TestExternalities<H: Hasher> where H::Out: HeapSizeOf {
 	changes_trie_storage: ChangesTrieInMemoryStorage<H>,
 	changes: OverlayedChanges,
 	code: Option<Vec<u8>>,
+	offchain: Option<Box<dyn OffchainExt + Send>>,
 }
 
 impl<H: Hasher> TestExternalities<H> where H::Out: HeapSizeOf {
@@ -57,6 +58,7 @@ impl<H: Hasher> TestExternalities<H> where H::Out: HeapSizeOf {
 			changes_trie_storage: ChangesTrieInMemoryStorage::new(),
 			changes: overlay,
 			code: Some(code.to_vec()),
+			offchain: None,
 		}
 	}
 
@@ -64,6 +66,11 @@ impl<H: Hasher> TestExternalities<H> where H::Out: HeapSizeOf {
 	pub fn insert(&mut self, k: Vec<u8>, v: Vec<u8>) -> Option<Vec<u8>> {
 		self.inner.insert(k, v)
 	}
+
+	/// Make offchain worker extensions available to the code under test.
+	pub fn set_offchain_externalities(&mut self, offchain: impl OffchainExt + Send + 'static) {
+		self.offchain = Some(Box::new(offchain));
+	}
 }
 
 impl<H: Hasher> ::std::fmt::Debug for TestExternalities<H> where H::Out: HeapSizeOf {
@@ -103,6 +110,7 @@ impl<H: Hasher> From< HashMap<Vec<u8>, Vec<u8>> > for TestExternalities<H> where
 			changes_trie_storage: ChangesTrieInMemoryStorage::new(),
 			changes: Default::default(),
 			code: None,
+			offchain: None,
 		}
 	}
 }
@@ -174,6 +182,10 @@ impl<H: Hasher> Externalities<H> for TestExternalities<H> where H::Out: Ord + He
 	fn submit_extrinsic(&mut self, _extrinsic: Vec<u8>) -> Result<(), ()> {
 		unimplemented!()
 	}
+
+	fn offchain(&mut self) -> Option<&mut dyn OffchainExt> {
+		self.offchain.as_mut().map(|x| &mut **x as _)
+	}
 }
 
 #[cfg(test)]
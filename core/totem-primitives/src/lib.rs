@@ -0,0 +1,210 @@
+// Copyright 2020 Chris D'Costa
+// This file is part of Totem Live Accounting.
+
+// Totem is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Totem is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Totem.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The Totem runtime api primitives.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![warn(missing_docs)]
+
+use client::decl_runtime_apis;
+use parity_codec::{Codec, Decode, Encode};
+use primitives::H256;
+use rstd::prelude::*;
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+
+/// Reputation of an account, per category, after decay up to the queried block.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct ReputationScores {
+	/// Delivering and paying on time.
+	pub timeliness: i128,
+	/// Quality of the goods or services delivered.
+	pub quality: i128,
+	/// Payment behaviour.
+	pub payment: i128,
+	/// Sum of all categories.
+	pub total: i128,
+	/// Number of settled orders fulfilled by the account.
+	pub completed_orders: u32,
+	/// Number of invoices raised by the account that were disputed.
+	pub disputed_orders: u32,
+}
+
+/// An encrypted message as stored by the runtime `Messaging` module.
+///
+/// The payload is encrypted with NaCl `box` from the sender's secret box key to the recipient's public box key.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Message<AccountId, Hash, BlockNumber> {
+	/// User hash of the sender.
+	pub sender_user: H256,
+	/// User hash of the recipient.
+	pub recipient: H256,
+	/// Type of the referenced record (3000 project, 5000 order).
+	pub reference_type: u16,
+	/// Hash of the referenced record.
+	pub reference: Hash,
+	/// Public box key of the sender at the time of sending.
+	pub sender_key: H256,
+	/// Nonce used for encryption.
+	pub nonce: [u8; 24],
+	/// Ciphertext without the 16 leading zero bytes produced by `box`.
+	pub payload: Vec<u8>,
+	/// Block in which the message was sent.
+	pub sent: BlockNumber,
+	/// Block in which the message expires.
+	pub expires: BlockNumber,
+	/// Account that sent the message.
+	pub sender: AccountId,
+}
+
+/// Party of an order as used by `TotemApi::orders_by_party`.
+pub type OrderParty = u8;
+/// The account that created the order.
+pub const PARTY_COMMANDER: OrderParty = 0;
+/// The account asked to fulfil the order.
+pub const PARTY_FULFILLER: OrderParty = 1;
+/// The account asked to approve the order.
+pub const PARTY_APPROVER: OrderParty = 2;
+
+/// A single line item of an order.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct OrderLine<Hash> {
+	/// Product reference.
+	pub product: Hash,
+	/// Price per unit.
+	pub unit_price: i128,
+	/// Number of units.
+	pub quantity: u128,
+	/// Unit of measure code.
+	pub unit_of_measure: u16,
+}
+
+/// An order header together with its items.
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct OrderDetails<AccountId, Hash> {
+	/// Order reference.
+	pub order_hash: Hash,
+	/// Account that created the order.
+	pub commander: AccountId,
+	/// Account asked to fulfil the order.
+	pub fulfiller: AccountId,
+	/// Account asked to approve the order.
+	pub approver: AccountId,
+	/// Order status code.
+	pub order_status: u16,
+	/// Approval status code.
+	pub approval_status: u16,
+	/// Buy (0) or sell (1) order.
+	pub buy_or_sell: u16,
+	/// Total order amount.
+	pub amount: i128,
+	/// Whether the order was open to the market.
+	pub market_order: bool,
+	/// Order type code.
+	pub order_type: u16,
+	/// Deadline for accepting the order, in blocks.
+	pub deadline: u64,
+	/// Due date for delivery, in blocks.
+	pub due_date: u64,
+	/// Line items.
+	pub items: Vec<OrderLine<Hash>>,
+}
+
+/// A member of a project team with the time they have booked.
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct TeamMember<AccountId> {
+	/// Member account.
+	pub account: AccountId,
+	/// Role held in the project.
+	pub role: u16,
+	/// Blocks booked on the project by this member.
+	pub total_blocks: u64,
+}
+
+/// A project with its team and time totals.
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct ProjectDetails<AccountId, Hash> {
+	/// Project reference.
+	pub project_hash: Hash,
+	/// Project owner.
+	pub owner: AccountId,
+	/// Project status code.
+	pub status: u16,
+	/// Owner, role holders and workers on the project.
+	pub team: Vec<TeamMember<AccountId>>,
+	/// Blocks booked on the project by all workers.
+	pub total_blocks: u64,
+	/// Number of time records submitted against the project.
+	pub time_records: u32,
+}
+
+/// A time record booked by a worker.
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct TimeRecordDetails<AccountId, Hash> {
+	/// Time record reference.
+	pub record_hash: Hash,
+	/// Worker who booked the time.
+	pub worker: AccountId,
+	/// Project the time was booked against.
+	pub project_hash: Hash,
+	/// Number of blocks worked.
+	pub total_blocks: u64,
+	/// Whether the record is locked.
+	pub locked_status: bool,
+	/// Submission status code.
+	pub submit_status: u16,
+	/// Accounting period the record is posted to.
+	pub posting_period: u16,
+	/// First block of the record.
+	pub start_block: u64,
+	/// Last block of the record.
+	pub end_block: u64,
+	/// Number of breaks taken.
+	pub nr_of_breaks: u16,
+}
+
+decl_runtime_apis! {
+	/// The Totem query api.
+	pub trait TotemApi<AccountId, Hash> where AccountId: Codec, Hash: Codec {
+		/// Returns the orders in which `who` takes the given party role, optionally filtered by order status.
+		fn orders_by_party(who: AccountId, party: OrderParty, status: Option<u16>) -> Vec<OrderDetails<AccountId, Hash>>;
+		/// Returns an order with its items.
+		fn order(order_hash: Hash) -> Option<OrderDetails<AccountId, Hash>>;
+		/// Returns a project with its team and time totals.
+		fn project(project_hash: Hash) -> Option<ProjectDetails<AccountId, Hash>>;
+		/// Returns the time records of a worker ending within `[from, to]`.
+		fn timesheet(worker: AccountId, from: u64, to: u64) -> Vec<TimeRecordDetails<AccountId, Hash>>;
+	}
+
+	/// The Totem reputation api.
+	pub trait ReputationApi<AccountId> where AccountId: Codec {
+		/// Returns the reputation scores of an account.
+		fn reputation(who: AccountId) -> ReputationScores;
+	}
+}
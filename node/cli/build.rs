@@ -18,7 +18,9 @@ use cli::{NoCustom, CoreParams};
 
 use std::{fs, env, path::Path};
 
-use structopt::{StructOpt, clap::Shell};
+use structopt::clap::Shell;
+
+include!("src/params.rs");
 
 fn main() {
 	build_shell_completion();
@@ -46,5 +48,5 @@ fn build_completion(shell: &Shell) {
 
 	fs::create_dir(&path).ok();
 
-	CoreParams::<NoCustom, NoCustom>::clap().gen_completions("totem-meccano-node", *shell, &path);
+	CoreParams::<CustomSubcommands, NoCustom>::clap().gen_completions("totem-meccano-node", *shell, &path);
 }
@@ -0,0 +1,349 @@
+// Copyright 2018-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Export of the accounting ledger from the local database.
+//!
+//! The ledger is read from the state at a block, so the node does not need to run. Without
+//! `--identity`, all identities holding an account index are exported, since the accounting
+//! storage maps are keyed by hash and cannot be enumerated. Identities with postings but no
+//! account index are missed, which is flagged in the output. The output schema is described in
+//! `doc/ledger-export.adoc`.
+
+use std::{collections::BTreeSet, fs, io::{self, Write}, path::Path};
+use log::{info, warn};
+use parity_codec::{Decode, Encode};
+use primitives::{blake2_256, twox_128, crypto::Ss58Codec, storage::{StorageData, StorageKey}};
+use node_primitives::{AccountId, AccountIndex, Block, BlockNumber, Hash};
+use serde_json::{json, Value};
+use sr_primitives::{generic::BlockId, traits::Header as HeaderT};
+use substrate_service::new_client;
+
+use crate::{error, params::{ExportLedgerCmd, LedgerFormat}, service::Factory, load_spec};
+
+/// General ledger account number.
+type Account = u64;
+/// Balance of a ledger account.
+type LedgerBalance = i128;
+/// Index of a posting.
+type PostingIndex = u128;
+
+/// A single posting to a ledger account.
+#[derive(Debug, PartialEq)]
+struct Posting {
+	identity: AccountId,
+	account: Account,
+	index: PostingIndex,
+	block_number: BlockNumber,
+	amount: LedgerBalance,
+	credit: bool,
+	reference: Hash,
+	applicable_block: BlockNumber,
+}
+
+/// Written with a ledger exported for the indexed identities only.
+const INDEXED_IDENTITIES_NOTE: &str = "Only identities holding an account index are exported, \
+	identities with postings but no account index are missing";
+
+/// The ledger of a set of identities at a block.
+#[derive(Debug, Default, PartialEq)]
+struct Ledger {
+	number: BlockNumber,
+	hash: Hash,
+	note: Option<&'static str>,
+	postings: Vec<Posting>,
+	balances: Vec<(AccountId, Account, LedgerBalance)>,
+	global: Vec<(Account, LedgerBalance)>,
+}
+
+/// Run the `export-ledger` command.
+pub fn export_ledger(cmd: ExportLedgerCmd, version: &cli::VersionInfo) -> error::Result<()> {
+	let config = cli::create_config_with_db_path::<Factory, _>(load_spec, &cmd.shared_params, version)?;
+	let client = new_client::<Factory>(&config)?;
+
+	let at = match cmd.at {
+		Some(ref at) => parse_block(at)?,
+		None => BlockId::hash(client.info()?.chain.finalized_hash),
+	};
+	let header = client.header(&at)?
+		.ok_or_else(|| format!("Block {} not found in the local database", at))?;
+	let at = BlockId::hash(header.hash());
+	let storage = |key: StorageKey| client.storage(&at, &key).map_err(error::Error::from);
+
+	let (identities, note) = if cmd.identities.is_empty() {
+		warn!("{}, use --identity to export them", INDEXED_IDENTITIES_NOTE);
+		(all_identities(&storage)?, Some(INDEXED_IDENTITIES_NOTE))
+	} else {
+		let identities = cmd.identities.iter().map(|identity| parse_identity(identity)).collect::<error::Result<_>>()?;
+		(identities, None)
+	};
+
+	info!("Exporting the ledger of {} identities at #{}", identities.len(), header.number());
+	let mut ledger = read_ledger(&storage, &identities)?;
+	ledger.number = *header.number();
+	ledger.hash = header.hash();
+	ledger.note = note;
+
+	match (cmd.format, cmd.output) {
+		(LedgerFormat::Json, Some(path)) => fs::write(path, ledger.to_json().to_string())?,
+		(LedgerFormat::Json, None) => println!("{}", ledger.to_json()),
+		(LedgerFormat::Csv, Some(path)) => ledger.write_csv(&path)?,
+		(LedgerFormat::Csv, None) =>
+			return Err("CSV export writes one file per table, specify an output directory with --output".into()),
+	}
+
+	Ok(())
+}
+
+/// Key of a storage value.
+fn value_key(name: &str) -> StorageKey {
+	StorageKey(twox_128(name.as_bytes()).to_vec())
+}
+
+/// Key of a storage map entry.
+fn map_key<K: Encode>(name: &str, key: &K) -> StorageKey {
+	let mut raw = name.as_bytes().to_vec();
+	key.encode_to(&mut raw);
+	StorageKey(blake2_256(&raw).to_vec())
+}
+
+/// Read and decode a storage entry, `None` if it is not set.
+fn get<T, S>(storage: &S, key: StorageKey) -> error::Result<Option<T>> where
+	T: Decode,
+	S: Fn(StorageKey) -> error::Result<Option<StorageData>>,
+{
+	match storage(key.clone())? {
+		Some(data) => T::decode(&mut &data.0[..])
+			.map(Some)
+			.ok_or_else(|| format!("Unable to decode storage entry 0x{}", hex::encode(&key.0)).into()),
+		None => Ok(None),
+	}
+}
+
+/// All accounts holding an account index.
+///
+/// The accounting maps are keyed by the hash of the identity, so identities with postings but
+/// without an account index cannot be found.
+fn all_identities<S>(storage: &S) -> error::Result<Vec<AccountId>> where
+	S: Fn(StorageKey) -> error::Result<Option<StorageData>>,
+{
+	let next_set: AccountIndex = get(storage, value_key("Indices NextEnumSet"))?.unwrap_or_default();
+	let mut identities = Vec::new();
+	for set in 0..=next_set {
+		let accounts: Vec<AccountId> = get(storage, map_key("Indices EnumSet", &set))?.unwrap_or_default();
+		identities.extend(accounts);
+	}
+	Ok(identities)
+}
+
+fn read_ledger<S>(storage: &S, identities: &[AccountId]) -> error::Result<Ledger> where
+	S: Fn(StorageKey) -> error::Result<Option<StorageData>>,
+{
+	let mut ledger = Ledger::default();
+	let mut used_accounts = BTreeSet::new();
+
+	for identity in identities {
+		let accounts: Vec<Account> = get(storage, map_key("AccountingModule AccountsById", identity))?
+			.unwrap_or_default();
+		for account in accounts {
+			used_accounts.insert(account);
+			let balance_key = (identity.clone(), account);
+			let balance = get(storage, map_key("AccountingModule BalanceByLedger", &balance_key))?.unwrap_or_default();
+			ledger.balances.push((identity.clone(), account, balance));
+
+			let indices: Vec<PostingIndex> = get(storage, map_key("AccountingModule IdAccountPostingIdList", &balance_key))?
+				.unwrap_or_default();
+			for index in indices {
+				let posting_key = (identity.clone(), account, index);
+				let detail: Option<(BlockNumber, LedgerBalance, bool, Hash, BlockNumber)> =
+					get(storage, map_key("AccountingModule PostingDetail", &posting_key))?;
+				if let Some((block_number, amount, credit, reference, applicable_block)) = detail {
+					ledger.postings.push(Posting {
+						identity: identity.clone(),
+						account,
+						index,
+						block_number,
+						amount,
+						credit,
+						reference,
+						applicable_block,
+					});
+				}
+			}
+		}
+	}
+
+	for account in used_accounts {
+		let balance = get(storage, map_key("AccountingModule GlobalLedger", &account))?.unwrap_or_default();
+		ledger.global.push((account, balance));
+	}
+	ledger.postings.sort_by_key(|posting| posting.index);
+
+	Ok(ledger)
+}
+
+impl Ledger {
+	fn to_json(&self) -> Value {
+		json!({
+			"block": { "number": self.number, "hash": format!("{:?}", self.hash), "note": self.note },
+			"posting_detail": self.postings.iter().map(|posting| json!({
+				"identity": posting.identity.to_ss58check(),
+				"account": posting.account,
+				"posting_index": posting.index.to_string(),
+				"block_number": posting.block_number,
+				"amount": posting.amount.to_string(),
+				"indicator": indicator(posting.credit),
+				"reference": format!("{:?}", posting.reference),
+				"applicable_block": posting.applicable_block,
+			})).collect::<Vec<_>>(),
+			"balance_by_ledger": self.balances.iter().map(|(identity, account, balance)| json!({
+				"identity": identity.to_ss58check(),
+				"account": account,
+				"balance": balance.to_string(),
+			})).collect::<Vec<_>>(),
+			"global_ledger": self.global.iter().map(|(account, balance)| json!({
+				"account": account,
+				"balance": balance.to_string(),
+			})).collect::<Vec<_>>(),
+		})
+	}
+
+	fn write_csv(&self, dir: &Path) -> io::Result<()> {
+		fs::create_dir_all(dir)?;
+
+		let mut file = fs::File::create(dir.join("block.csv"))?;
+		writeln!(file, "number,hash,note")?;
+		writeln!(file, "{},{:?},{}", self.number, self.hash, self.note.unwrap_or_default())?;
+
+		let mut file = fs::File::create(dir.join("posting_detail.csv"))?;
+		writeln!(file, "identity,account,posting_index,block_number,amount,indicator,reference,applicable_block")?;
+		for posting in &self.postings {
+			writeln!(
+				file, "{},{},{},{},{},{},{:?},{}",
+				posting.identity.to_ss58check(), posting.account, posting.index, posting.block_number,
+				posting.amount, indicator(posting.credit), posting.reference, posting.applicable_block,
+			)?;
+		}
+
+		let mut file = fs::File::create(dir.join("balance_by_ledger.csv"))?;
+		writeln!(file, "identity,account,balance")?;
+		for (identity, account, balance) in &self.balances {
+			writeln!(file, "{},{},{}", identity.to_ss58check(), account, balance)?;
+		}
+
+		let mut file = fs::File::create(dir.join("global_ledger.csv"))?;
+		writeln!(file, "account,balance")?;
+		for (account, balance) in &self.global {
+			writeln!(file, "{},{}", account, balance)?;
+		}
+
+		Ok(())
+	}
+}
+
+fn indicator(credit: bool) -> &'static str {
+	if credit { "credit" } else { "debit" }
+}
+
+/// Parse a block number or a `0x` prefixed block hash.
+fn parse_block(block: &str) -> error::Result<BlockId<Block>> {
+	if block.starts_with("0x") {
+		hex::decode(&block[2..]).ok()
+			.filter(|bytes| bytes.len() == 32)
+			.map(|bytes| BlockId::hash(Hash::from_slice(&bytes)))
+			.ok_or_else(|| format!("Invalid block hash `{}`", block).into())
+	} else {
+		block.parse::<BlockNumber>()
+			.map(BlockId::number)
+			.map_err(|_| format!("Invalid block number `{}`", block).into())
+	}
+}
+
+/// Parse an identity given as SS58 address or hex public key.
+fn parse_identity(identity: &str) -> error::Result<AccountId> {
+	if let Ok(account) = AccountId::from_ss58check(identity) {
+		return Ok(account);
+	}
+	hex::decode(identity.trim_start_matches("0x")).ok()
+		.filter(|bytes| bytes.len() == 32)
+		.map(|bytes| AccountId::from_slice(&bytes))
+		.ok_or_else(|| format!("Invalid identity `{}`, expected SS58 or hex", identity).into())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::HashMap;
+
+	fn storage(entries: Vec<(StorageKey, Vec<u8>)>) -> impl Fn(StorageKey) -> error::Result<Option<StorageData>> {
+		let entries: HashMap<_, _> = entries.into_iter().collect();
+		move |key| Ok(entries.get(&key).cloned().map(StorageData))
+	}
+
+	#[test]
+	fn reads_the_ledger_of_all_indexed_identities() {
+		let alice = AccountId::from_raw([1u8; 32]);
+		let bob = AccountId::from_raw([2u8; 32]);
+		let reference = Hash::repeat_byte(9);
+		let storage = storage(vec![
+			(value_key("Indices NextEnumSet"), 0u32.encode()),
+			(map_key("Indices EnumSet", &0u32), vec![alice.clone(), bob.clone()].encode()),
+			(map_key("AccountingModule AccountsById", &alice), vec![100u64, 200].encode()),
+			(map_key("AccountingModule BalanceByLedger", &(alice.clone(), 100u64)), 50i128.encode()),
+			(map_key("AccountingModule BalanceByLedger", &(alice.clone(), 200u64)), (-50i128).encode()),
+			(map_key("AccountingModule IdAccountPostingIdList", &(alice.clone(), 100u64)), vec![1u128].encode()),
+			(map_key("AccountingModule IdAccountPostingIdList", &(alice.clone(), 200u64)), vec![0u128].encode()),
+			(
+				map_key("AccountingModule PostingDetail", &(alice.clone(), 100u64, 1u128)),
+				(5u64, 50i128, false, reference, 5u64).encode(),
+			),
+			(
+				map_key("AccountingModule PostingDetail", &(alice.clone(), 200u64, 0u128)),
+				(5u64, 50i128, true, reference, 4u64).encode(),
+			),
+			(map_key("AccountingModule GlobalLedger", &100u64), 70i128.encode()),
+			(map_key("AccountingModule GlobalLedger", &200u64), (-70i128).encode()),
+		]);
+
+		let identities = all_identities(&storage).unwrap();
+		assert_eq!(identities, vec![alice.clone(), bob]);
+
+		let ledger = read_ledger(&storage, &identities).unwrap();
+		assert_eq!(ledger.balances, vec![(alice.clone(), 100, 50), (alice.clone(), 200, -50)]);
+		assert_eq!(ledger.global, vec![(100, 70), (200, -70)]);
+		assert_eq!(ledger.postings.iter().map(|p| (p.index, p.account, p.credit)).collect::<Vec<_>>(),
+			vec![(0, 200, true), (1, 100, false)]);
+
+		let json = ledger.to_json();
+		assert_eq!(json["posting_detail"][0]["indicator"], "credit");
+		assert_eq!(json["posting_detail"][0]["applicable_block"], 4);
+		assert_eq!(json["balance_by_ledger"][1]["balance"], "-50");
+		assert_eq!(json["block"]["note"], Value::Null);
+		let json = Ledger { note: Some(INDEXED_IDENTITIES_NOTE), ..Ledger::default() }.to_json();
+		assert_eq!(json["block"]["note"], INDEXED_IDENTITIES_NOTE);
+	}
+
+	#[test]
+	fn parses_blocks_and_identities() {
+		assert_eq!(parse_block("42").unwrap(), BlockId::number(42));
+		assert_eq!(parse_block(&format!("{:?}", Hash::repeat_byte(1))).unwrap(), BlockId::hash(Hash::repeat_byte(1)));
+		assert!(parse_block("0x12").is_err());
+
+		let account = AccountId::from_raw([7u8; 32]);
+		assert_eq!(parse_identity(&account.to_ss58check()).unwrap(), account);
+		assert_eq!(parse_identity(&format!("0x{}", hex::encode([7u8; 32]))).unwrap(), account);
+		assert!(parse_identity("alice").is_err());
+	}
+}
@@ -22,10 +22,13 @@
 pub use cli::error;
 pub mod chain_spec;
 mod service;
+mod params;
+mod ledger;
 
 use tokio::prelude::Future;
 use tokio::runtime::{Builder as RuntimeBuilder, Runtime};
 pub use cli::{VersionInfo, IntoExit, NoCustom};
+pub use params::CustomSubcommands;
 use substrate_service::{ServiceFactory, Roles as ServiceRoles};
 use std::ops::Deref;
 use log::info;
@@ -82,7 +85,7 @@ pub fn run<I, T, E>(args: I, exit: E, version: cli::VersionInfo) -> error::Resul
 	T: Into<std::ffi::OsString> + Clone,
 	E: IntoExit,
 {
-	cli::parse_and_execute::<service::Factory, NoCustom, NoCustom, _, _, _, _, _>(
+	let custom = cli::parse_and_execute::<service::Factory, CustomSubcommands, NoCustom, _, _, _, _, _>(
 		load_spec, &version, "totem-meccano-node", args, exit,
 		|exit, _custom_args, config| {
 			info!("{}", version.name);
@@ -107,7 +110,12 @@ pub fn run<I, T, E>(args: I, exit: E, version: cli::VersionInfo) -> error::Resul
 				),
 			}.map_err(|e| format!("{:?}", e))
 		}
-	).map_err(Into::into).map(|_| ())
+	)?;
+
+	match custom {
+		Some(CustomSubcommands::ExportLedger(cmd)) => ledger::export_ledger(cmd, &version),
+		None => Ok(()),
+	}
 }
 
 fn run_until_exit<T, C, E>(
@@ -0,0 +1,76 @@
+// Copyright 2018-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::path::PathBuf;
+use structopt::{StructOpt, clap::{arg_enum, _clap_count_exprs}};
+use cli::{GetLogFilter, SharedParams};
+
+/// Subcommands provided by the Totem node on top of the core ones.
+#[derive(Clone, Debug, StructOpt)]
+pub enum CustomSubcommands {
+	/// Export the accounting ledger from the local database.
+	#[structopt(name = "export-ledger")]
+	ExportLedger(ExportLedgerCmd),
+}
+
+impl GetLogFilter for CustomSubcommands {
+	fn get_log_filter(&self) -> Option<String> {
+		match self {
+			CustomSubcommands::ExportLedger(cmd) => cmd.shared_params.get_log_filter(),
+		}
+	}
+}
+
+arg_enum! {
+	/// Output format of the ledger export
+	#[derive(Debug, Clone, Copy)]
+	pub enum LedgerFormat {
+		Json,
+		Csv,
+	}
+}
+
+/// The `export-ledger` command used to export the accounting ledger.
+#[derive(Debug, StructOpt, Clone)]
+pub struct ExportLedgerCmd {
+	/// Block number or hash to read the ledger at. Latest finalized block by default.
+	#[structopt(long = "at", value_name = "BLOCK")]
+	pub at: Option<String>,
+
+	/// Identity (SS58 address or hex public key) to export. All identities if unspecified.
+	#[structopt(long = "identity", value_name = "ADDRESS")]
+	pub identities: Vec<String>,
+
+	/// Output format.
+	#[structopt(
+		long = "format",
+		value_name = "FORMAT",
+		raw(
+			possible_values = "&LedgerFormat::variants()",
+			case_insensitive = "true",
+			default_value = r#""Json""#
+		)
+	)]
+	pub format: LedgerFormat,
+
+	/// Output file for JSON (stdout if unspecified) or output directory for CSV.
+	#[structopt(long = "output", value_name = "PATH", parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
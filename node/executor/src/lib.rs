@@ -44,13 +44,33 @@ mod tests {
 	use system::{EventRecord, Phase};
 	use node_runtime::{Header, Block, UncheckedExtrinsic, CheckedExtrinsic, Call, Runtime, Balances,
 		BuildStorage, GenesisConfig, BalancesConfig, SessionConfig, StakingConfig, System,
-		SystemConfig, GrandpaConfig, IndicesConfig, Event, Log};
+		SystemConfig, GrandpaConfig, IndicesConfig, Event, Log, AccountingEvent, FeesEvent};
 	use wabt;
 	use primitives::map;
 
 	const BLOATY_CODE: &[u8] = include_bytes!("../../runtime/wasm/target/wasm32-unknown-unknown/release/node_runtime.wasm");
 	const COMPACT_CODE: &[u8] = include_bytes!("../../runtime/wasm/target/wasm32-unknown-unknown/release/totem-meccano.compact.wasm");
 	const GENESIS_HASH: [u8; 32] = [69u8; 32];
+	const NETWORK_FEES: u64 = 250800010000000;
+	const XTX_BALANCE: u64 = 110100040000000;
+
+	// The fee of 1 is too small for an author share, so all of it goes to the treasury.
+	fn fee_events(payer: AccountId, extrinsic: u32, first_posting: u128) -> Vec<EventRecord<Event>> {
+		vec![
+			EventRecord {
+				phase: Phase::ApplyExtrinsic(extrinsic),
+				event: Event::accounting(AccountingEvent::LegderUpdate(payer.clone(), NETWORK_FEES, 1, first_posting))
+			},
+			EventRecord {
+				phase: Phase::ApplyExtrinsic(extrinsic),
+				event: Event::accounting(AccountingEvent::LegderUpdate(payer.clone(), XTX_BALANCE, -1, first_posting + 1))
+			},
+			EventRecord {
+				phase: Phase::ApplyExtrinsic(extrinsic),
+				event: Event::fees(FeesEvent::FeePaid(payer, 1))
+			},
+		]
+	}
 
 	fn alice() -> AccountId {
 		AccountKeyring::Alice.into()
@@ -485,11 +505,14 @@ mod tests {
 			// -1 is the default fee
 			assert_eq!(Balances::total_balance(&alice()), 111 - 69 - 1);
 			assert_eq!(Balances::total_balance(&bob()), 100 + 69);
-			assert_eq!(System::events(), vec![
+			let mut events = vec![
 				EventRecord {
 					phase: Phase::ApplyExtrinsic(0),
 					event: Event::system(system::Event::ExtrinsicSuccess)
 				},
+			];
+			events.extend(fee_events(alice(), 1, 0));
+			events.extend(vec![
 				EventRecord {
 					phase: Phase::ApplyExtrinsic(1),
 					event: Event::balances(balances::RawEvent::Transfer(
@@ -505,7 +528,7 @@ mod tests {
 				},
 				EventRecord {
 					phase: Phase::Finalization,
-					event: Event::treasury(treasury::RawEvent::Spending(0))
+					event: Event::treasury(treasury::RawEvent::Spending(1))
 				},
 				EventRecord {
 					phase: Phase::Finalization,
@@ -513,9 +536,10 @@ mod tests {
 				},
 				EventRecord {
 					phase: Phase::Finalization,
-					event: Event::treasury(treasury::RawEvent::Rollover(0))
+					event: Event::treasury(treasury::RawEvent::Rollover(1))
 				},
 			]);
+			assert_eq!(System::events(), events);
 		});
 
 		executor().call::<_, NeverNativeValue, fn() -> _>(
@@ -532,11 +556,14 @@ mod tests {
 			assert_eq!(Balances::total_balance(&alice()), 111 - 69 - 1 - 10 - 1);
 			// 100 + 69 + 10 - 1     = 178
 			assert_eq!(Balances::total_balance(&bob()), 100 + 69 + 10 - 1);
-			assert_eq!(System::events(), vec![
+			let mut events = vec![
 				EventRecord {
 					phase: Phase::ApplyExtrinsic(0),
 					event: Event::system(system::Event::ExtrinsicSuccess)
 				},
+			];
+			events.extend(fee_events(bob(), 1, 2));
+			events.extend(vec![
 				EventRecord {
 					phase: Phase::ApplyExtrinsic(1),
 					event: Event::balances(
@@ -552,6 +579,9 @@ mod tests {
 					phase: Phase::ApplyExtrinsic(1),
 					event: Event::system(system::Event::ExtrinsicSuccess)
 				},
+			]);
+			events.extend(fee_events(alice(), 2, 4));
+			events.extend(vec![
 				EventRecord {
 					phase: Phase::ApplyExtrinsic(2),
 					event: Event::balances(
@@ -573,7 +603,7 @@ mod tests {
 				},
 				EventRecord {
 					phase: Phase::Finalization,
-					event: Event::treasury(treasury::RawEvent::Spending(0))
+					event: Event::treasury(treasury::RawEvent::Spending(3))
 				},
 				EventRecord {
 					phase: Phase::Finalization,
@@ -581,9 +611,10 @@ mod tests {
 				},
 				EventRecord {
 					phase: Phase::Finalization,
-					event: Event::treasury(treasury::RawEvent::Rollover(0))
+					event: Event::treasury(treasury::RawEvent::Rollover(3))
 				},
 			]);
+			assert_eq!(System::events(), events);
 		});
 	}
 
@@ -84,23 +84,25 @@
 // * Therefore the currency conversion for revenue and related expenses is calculated at the spot rate for the period (block) in which they are recognised.
 // * All other currency conversions are made at the rate for the period close. The UI can therefore present the correct conversions for any given value at any point in time. 
 
-use parity_codec::{ Encode }; //v1
-// use codec::{ Encode }; //v2
+use parity_codec::{ Encode, Decode }; //v1
+// use codec::{ Encode, Decode }; //v2
 
-use support::{decl_event, decl_module, decl_storage, dispatch::Result, StorageValue, StorageMap}; //v1
+use support::{decl_event, decl_module, decl_storage, dispatch::Result, ensure, StorageValue, StorageMap}; //v1
+use support::dispatch::TransactionWeight;
 // use frame_support::{decl_event, decl_error, decl_module, decl_storage, dispatch::DispatchResult, weights::{Weight, DispatchClass}, StorageValue, StorageMap}; // v2
 
-use system::{self}; //v1
+use system::{self, ensure_signed}; //v1
 // use frame_system::{self}; //v2
 
-use rstd::prelude::*; //v1
+use rstd::{prelude::*, result}; //v1
 // use sp_std::prelude::*; //v2
 
 use runtime_primitives::traits::Hash; //v1
 // use sp_runtime{DispatchResult, DispatchError, traits::{Hash},}; //v2
 
 // Totem Traits
-use crate::accounting_traits::{ Posting };
+use crate::accounting_traits::{ Posting, Quantities };
+use crate::weights::{ WRITE };
 
 pub trait Trait: system::Trait + timestamp::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
@@ -110,6 +112,21 @@ type LedgerBalance = i128; // Balance on an account can be negative
 type Account = u64; // General ledger account number
 type Indicator = bool; // 0=Debit(false) 1=Credit(true) Note: Debit and Credit balances are account specific - see chart of accounts
 type PostingIndex = u128; // The index number for identifying the posting to ledgers
+type ValuationMethod = u8; // FIFO(0), weighted average(1)
+
+// Ledger accounts used by the quantities accounting
+const INVENTORY: Account = 110100090000000; // 110100090000000 Inventory (Stock)
+const GOODS_RECEIVED_NOT_INVOICED: Account = 120200040000000; // 120200040000000 Goods received not invoiced
+const GOODS_AND_MATERIALS: Account = 250500110000001; // 250500110000001 Goods and materials
+const COST_OF_GOODS_SOLD: Account = 251100010000000; // 251100010000000 Cost of goods sold
+
+/// Goods of one product received together. The value is the total cost of the remaining quantity.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct StockLayer {
+    pub quantity: u128,
+    pub value: LedgerBalance,
+}
 
 decl_storage! {
     trait Store for Module<T: Trait> as AccountingModule {
@@ -128,9 +145,16 @@ decl_storage! {
         // Address to book the sales tax to and the tax jurisdiction (Experimental, may be deprecated in future) 
         TaxesByJurisdiction get(taxes_by_jurisdiction): map (T::AccountId, T::AccountId) => LedgerBalance;
         
-        // TODO
         // Quantities Accounting
-        // Depreciation (calculated everytime there is a transaction so as not to overwork the runtime) - sets "last seen block" to calculate the delta for depreciation
+        // Stock held by an identity per product, oldest receipt first. Weighted average valuation keeps a single layer.
+        // A product without an entry is not tracked by the identity.
+        StockLayers get(stock_layers): map (T::AccountId, T::Hash) => Option<Vec<StockLayer>>;
+        // Convenience list of products tracked by an identity. Useful for UI read performance
+        ProductsById get(products_by_id): map T::AccountId => Vec<T::Hash>;
+        // Inventory valuation method of the identity
+        Valuation get(valuation): map T::AccountId => ValuationMethod;
+        
+        // Depreciation is calculated by the fixed assets module on the next interaction with an asset, using the last seen block
     }
 }
 
@@ -143,6 +167,16 @@ decl_module! {
         fn adjustment() -> Result {
             Ok(())
         }
+        /// Sets the inventory valuation method: FIFO(0) or weighted average(1).
+        /// Stock held under FIFO is averaged the next time goods are received or issued.
+        #[weight = TransactionWeight::Basic(WRITE, 1)]
+        fn set_valuation_method(origin, method: ValuationMethod) -> Result {
+            let who = ensure_signed(origin)?;
+            ensure!(method <= 1, "Unknown valuation method");
+            <Valuation<T>>::insert(&who, method);
+            Self::deposit_event(RawEvent::ValuationMethodSet(who, method));
+            Ok(())
+        }
     }
 }
 
@@ -229,6 +263,43 @@ impl<T: Trait> Module<T> {
         
         Ok(())
     }
+
+    /// Converts a quantity for valuation. Quantities above the range of a ledger balance are rejected.
+    fn signed_quantity(q: u128) -> result::Result<LedgerBalance, &'static str> {
+        if q > LedgerBalance::max_value() as u128 {
+            return Err("Quantity too large");
+        }
+        Ok(q as LedgerBalance)
+    }
+
+    /// Merges all layers into one layer valued at the weighted average cost.
+    fn average(layers: Vec<StockLayer>) -> result::Result<Vec<StockLayer>, &'static str> {
+        let mut total = StockLayer::default();
+        for l in layers {
+            total.quantity = total.quantity.checked_add(l.quantity).ok_or("Stock quantity overflow")?;
+            total.value = total.value.checked_add(l.value).ok_or("Stock value overflow")?;
+        }
+        Self::signed_quantity(total.quantity)?;
+        
+        let mut averaged: Vec<StockLayer> = Vec::new();
+        if total.quantity > 0 { averaged.push(total) };
+        Ok(averaged)
+    }
+
+    /// Posts a debit and a credit of the same value for one identity.
+    fn post_pair(o: T::AccountId, debit: Account, credit: Account, value: LedgerBalance, credit_decreases: bool, h: T::Hash) -> Result {
+        let current_block = <system::Module<T>>::block_number();
+        let credit_amount: LedgerBalance = if credit_decreases { -value } else { value };
+        
+        let mut forward_keys = Vec::<(T::AccountId, Account, LedgerBalance, bool, T::Hash, T::BlockNumber, T::BlockNumber)>::with_capacity(2);
+        forward_keys.push((o.clone(), debit, value, true, h, current_block, current_block));
+        forward_keys.push((o.clone(), credit, credit_amount, false, h, current_block, current_block));
+        
+        let mut reversal_keys = Vec::<(T::AccountId, Account, LedgerBalance, bool, T::Hash, T::BlockNumber, T::BlockNumber)>::with_capacity(1);
+        reversal_keys.push((o, debit, -value, false, h, current_block, current_block));
+        
+        <Self as Posting<T::AccountId,T::Hash,T::BlockNumber>>::handle_multiposting_amounts(forward_keys, reversal_keys, Vec::new())
+    }
 }
 
 impl<T: Trait> Posting<T::AccountId,T::Hash,T::BlockNumber> for Module<T> {
@@ -293,11 +364,116 @@ impl<T: Trait> Posting<T::AccountId,T::Hash,T::BlockNumber> for Module<T> {
             return T::Hashing::hash(input.encode().as_slice()); // default hash BlakeTwo256
         } 
     }
+
+impl<T: Trait> Quantities<T::AccountId,T::Hash> for Module<T> {
+    fn can_issue(who: T::AccountId, product: T::Hash, quantity: u128) -> bool {
+        match Self::stock_layers((who, product)) {
+            Some(layers) => layers.iter().fold(0u128, |t, l| t.saturating_add(l.quantity)) >= quantity,
+            None => true,
+        }
+    }
+    
+    fn can_receive(who: T::AccountId, product: T::Hash, quantity: u128, unit_cost: i128, stocked: bool) -> bool {
+        if quantity == 0 || unit_cost < 0 {
+            return false;
+        }
+        let value = match Self::signed_quantity(quantity).ok().and_then(|q| q.checked_mul(unit_cost)) {
+            Some(v) => v,
+            None => return false,
+        };
+        if !stocked {
+            return true;
+        }
+        let mut layers = Self::stock_layers((who, product)).unwrap_or_default();
+        layers.push(StockLayer { quantity: quantity, value: value });
+        Self::average(layers).is_ok()
+    }
+    
+    /// Goods received: Debit increase Inventory (stocked) or Goods and materials, Credit increase Goods received not invoiced.
+    /// The invoice later clears the goods received not invoiced account against accounts payable.
+    fn goods_received(who: T::AccountId, product: T::Hash, quantity: u128, unit_cost: i128, stocked: bool, reference: T::Hash) -> Result {
+        ensure!(quantity > 0, "Quantity must be greater than zero");
+        ensure!(unit_cost >= 0, "Unit cost cannot be negative");
+        let value: LedgerBalance = Self::signed_quantity(quantity)?.checked_mul(unit_cost).ok_or("Stock value overflow")?;
+        
+        let key = (who.clone(), product.clone());
+        let tracked = Self::stock_layers(&key);
+        let mut layers: Vec<StockLayer> = Vec::new();
+        if stocked {
+            layers = tracked.clone().unwrap_or_default();
+            layers.push(StockLayer { quantity: quantity, value: value });
+            if Self::valuation(&who) == 1 {
+                layers = Self::average(layers)?;
+            }
+        }
+        
+        let debit: Account = if stocked { INVENTORY } else { GOODS_AND_MATERIALS };
+        if value > 0 {
+            Self::post_pair(who.clone(), debit, GOODS_RECEIVED_NOT_INVOICED, value, false, reference)?;
+        }
+        
+        if stocked {
+            if tracked.is_none() {
+                <ProductsById<T>>::mutate(&who, |products_by_id| products_by_id.push(product.clone()));
+            }
+            <StockLayers<T>>::insert(&key, layers);
+        }
+        
+        Self::deposit_event(RawEvent::GoodsReceived(who, product, quantity, value));
+        Ok(())
+    }
+    
+    /// Goods issued: Debit increase Cost of goods sold, Credit decrease Inventory at the valuation of the identity.
+    /// Goods that the identity does not track in stock are issued without a cost.
+    fn goods_issued(who: T::AccountId, product: T::Hash, quantity: u128, reference: T::Hash) -> Result {
+        ensure!(quantity > 0, "Quantity must be greater than zero");
+        let key = (who.clone(), product.clone());
+        let mut layers: Vec<StockLayer> = match Self::stock_layers(&key) {
+            Some(l) => l,
+            None => {
+                Self::deposit_event(RawEvent::GoodsIssued(who, product, quantity, 0));
+                return Ok(());
+            },
+        };
+        if Self::valuation(&who) == 1 {
+            layers = Self::average(layers)?;
+        }
+        
+        // Consume the oldest layers first. A partly consumed layer gives up a proportional share of its value.
+        let mut remaining: u128 = quantity;
+        let mut cost: LedgerBalance = 0;
+        while remaining > 0 {
+            let layer = layers.first_mut().ok_or("Insufficient stock")?;
+            if layer.quantity <= remaining {
+                remaining -= layer.quantity;
+                cost = cost.checked_add(layer.value).ok_or("Stock value overflow")?;
+                layers.remove(0);
+            } else {
+                let part: LedgerBalance = layer.value
+                    .checked_mul(Self::signed_quantity(remaining)?)
+                    .ok_or("Stock value overflow")? / Self::signed_quantity(layer.quantity)?;
+                layer.quantity -= remaining;
+                layer.value -= part;
+                cost = cost.checked_add(part).ok_or("Stock value overflow")?;
+                remaining = 0;
+            }
+        }
+        
+        if cost > 0 {
+            Self::post_pair(who.clone(), COST_OF_GOODS_SOLD, INVENTORY, cost, true, reference)?;
+        }
+        <StockLayers<T>>::insert(&key, layers);
+        
+        Self::deposit_event(RawEvent::GoodsIssued(who, product, quantity, cost));
+        Ok(())
+    }
+}
     
     decl_event!(
         pub enum Event<T>
         where
         AccountId = <T as system::Trait>::AccountId,
+        Hash = <T as system::Trait>::Hash,
         Account = u64,
         LedgerBalance = i128,
         PostingIndex = u128,
@@ -306,5 +482,10 @@ impl<T: Trait> Posting<T::AccountId,T::Hash,T::BlockNumber> for Module<T> {
             ErrorOverflow(Account),
             ErrorGlobalOverflow(),
             ErrorInError(),
+            /// Identity, product, quantity, value added to stock or expensed
+            GoodsReceived(AccountId, Hash, u128, LedgerBalance),
+            /// Identity, product, quantity, cost of goods sold
+            GoodsIssued(AccountId, Hash, u128, LedgerBalance),
+            ValuationMethodSet(AccountId, u8),
         }
     );
\ No newline at end of file
@@ -56,4 +56,20 @@ pub trait Posting<AccountId,Hash,BlockNumber> {
 
     fn get_pseudo_random_hash(s: AccountId, r: AccountId) -> Hash;
 
-}
\ No newline at end of file
+}
+pub trait Quantities<AccountId,Hash> {
+
+    /// Checks that the identity holds enough of a tracked product. Untracked products are always available.
+    fn can_issue(who: AccountId, product: Hash, quantity: u128) -> bool;
+
+    /// Checks that goods can be received at the unit cost without the stock value overflowing.
+    fn can_receive(who: AccountId, product: Hash, quantity: u128, unit_cost: i128, stocked: bool) -> bool;
+
+    /// Receives goods from an order at the invoiced unit cost. Stocked goods are added to inventory, 
+    /// otherwise they are expensed on receipt.
+    fn goods_received(who: AccountId, product: Hash, quantity: u128, unit_cost: i128, stocked: bool, reference: Hash) -> Result;
+
+    /// Issues goods for an order and posts the cost of goods sold at the valuation of the identity.
+    fn goods_issued(who: AccountId, product: Hash, quantity: u128, reference: Hash) -> Result;
+
+}
@@ -33,24 +33,42 @@
 //! You should have received a copy of the GNU General Public License
 //! along with Totem.  If not, see <http://www.gnu.org/licenses/>.
 
-use support::{decl_event, decl_module, dispatch::Result};
+use support::{decl_event, decl_module, decl_storage, dispatch::Result, ensure, StorageMap, StorageValue};
+use support::dispatch::TransactionWeight;
 // use frame_support::{decl_event, decl_module, dispatch::Result}; //v2
-use system::ensure_signed;
+use system::{ensure_signed, ensure_root};
 // use frame_system::ensure_signed; //v2
 use rstd::prelude::*;
 // use sp_std::prelude::*; //v2
+use runtime_primitives::traits::{One};
 
 // Totem crates
-use crate::timekeeping_traits::{ Validating as TimeValidating};
+use crate::archive_traits::{ Archivable, Retention };
+use crate::weights::{ LIST, READ, SETTING, WRITE };
 
 pub trait Trait: system::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
-    type Timekeeping: TimeValidating<Self::AccountId,Self::Hash>;
-
+    type Projects: Archivable<Self::AccountId,Self::Hash>;
+    type Timekeeping: Archivable<Self::AccountId,Self::Hash>;
+    type Orders: Archivable<Self::AccountId,Self::Hash>;
+    type Prefunding: Archivable<Self::AccountId,Self::Hash>;
 }
 
 pub type RecordType = u16;
 
+decl_storage! {
+    trait Store for Module<T: Trait> as ArchiveModule {
+        // Number of blocks a record must remain in a terminal status before it is archived automatically.
+        // No value means that records of this type are never archived automatically.
+        RetentionPeriod get(retention_period): map RecordType => Option<T::BlockNumber>;
+        // Records waiting to be archived, keyed by the block in which they become due.
+        // Each entry is the record type, the identity whose lists are archived and the record hash.
+        ArchiveQueue get(archive_queue): map T::BlockNumber => Vec<(RecordType, T::AccountId, T::Hash)>;
+        // Upper bound of records archived automatically in one block. Anything beyond this is carried over to the next block.
+        MaxArchivePerBlock get(max_archive_per_block): u32 = 50;
+    }
+}
+
 decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
         fn deposit_event<T>() = default;
@@ -61,10 +79,13 @@ decl_module! {
         /// 3000 Activities (previously Projects)
         /// 4000 Timekeeping
         /// 5000 Orders
-        /// 6000
+        /// 6000 Prefunding
         /// 7000
         /// 8000
         /// 9000
+        /// 
+        /// Setting `archive` to false restores the record.
+        #[weight = TransactionWeight::Basic(3 * READ + 2 * WRITE + LIST, 1)]
         fn archive_record(
             origin,
             record_type: RecordType, 
@@ -74,19 +95,112 @@ decl_module! {
             // check signed
             let who = ensure_signed(origin)?;
             
-            // check which type of record
+            // module specific archive handling
+            Self::set_archive(record_type, who.clone(), bonsai_token, archive)?;
+
+            // issue event
+            Self::deposit_event(RawEvent::RecordArchived(record_type, who, bonsai_token, archive));
+
+            Ok(())
+        }
+
+        /// Sets the retention period (in blocks) for a record type. A period of zero disables automatic archiving.
+        /// Records are only queued when they reach a terminal status, so a new period applies to those reaching it afterwards.
+        #[weight = TransactionWeight::Basic(SETTING, 1)]
+        fn set_retention_period(origin, record_type: RecordType, period: T::BlockNumber) -> Result {
+            ensure_root(origin)?;
+
+            // Prefunding is archived as soon as the lock is released, so there is nothing to retain
             match record_type {
-                4000 => {
-                    // module specific archive handling
-                    if let true = <<T as Trait>::Timekeeping as TimeValidating<T::AccountId, T::Hash>>::validate_and_archive(who.clone(), bonsai_token, archive) {
-                        // issue event
-                        Self::deposit_event(RawEvent::RecordArchived(4000, who, bonsai_token, archive));
-                    }
-                },
-                _ => return Err("Unknown or unimplemented record type. Cannot archive record"),
+                3000 | 4000 | 5000 => (),
+                _ => return Err("Unknown or unimplemented record type. Cannot set retention"),
             }
+
+            if period == T::BlockNumber::default() {
+                <RetentionPeriod<T>>::remove(record_type);
+            } else {
+                <RetentionPeriod<T>>::insert(record_type, period);
+            }
+
+            Self::deposit_event(RawEvent::RetentionPeriodSet(record_type, period));
+
+            Ok(())
+        }
+
+        /// Sets the maximum number of records that are archived automatically per block.
+        #[weight = TransactionWeight::Basic(SETTING, 1)]
+        fn set_max_archive_per_block(origin, max: u32) -> Result {
+            ensure_root(origin)?;
+            // the queue would never be processed, only carried over from block to block
+            ensure!(max > 0, "At least one record must be archived per block");
+            <MaxArchivePerBlock<T>>::put(max);
+
             Ok(())
         }
+
+        fn on_finalize(n: T::BlockNumber) {
+            Self::process_archive_queue(n);
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    fn set_archive(e: RecordType, o: T::AccountId, h: T::Hash, a: bool) -> Result {
+        // check which type of record
+        match e {
+            3000 => <<T as Trait>::Projects as Archivable<T::AccountId, T::Hash>>::validate_and_archive(o, h, a),
+            4000 => <<T as Trait>::Timekeeping as Archivable<T::AccountId, T::Hash>>::validate_and_archive(o, h, a),
+            5000 => <<T as Trait>::Orders as Archivable<T::AccountId, T::Hash>>::validate_and_archive(o, h, a),
+            6000 => <<T as Trait>::Prefunding as Archivable<T::AccountId, T::Hash>>::validate_and_archive(o, h, a),
+            _ => Err("Unknown or unimplemented record type. Cannot archive record"),
+        }
+    }
+
+    fn is_terminal(e: RecordType, h: T::Hash) -> bool {
+        match e {
+            3000 => <<T as Trait>::Projects as Archivable<T::AccountId, T::Hash>>::is_terminal(h),
+            4000 => <<T as Trait>::Timekeeping as Archivable<T::AccountId, T::Hash>>::is_terminal(h),
+            5000 => <<T as Trait>::Orders as Archivable<T::AccountId, T::Hash>>::is_terminal(h),
+            6000 => <<T as Trait>::Prefunding as Archivable<T::AccountId, T::Hash>>::is_terminal(h),
+            _ => false,
+        }
+    }
+
+    /// Archives the records that are due in this block.
+    /// Records that changed status in the meantime (for example a reopened project) are skipped.
+    fn process_archive_queue(n: T::BlockNumber) {
+        let mut due = <ArchiveQueue<T>>::take(&n);
+        if due.is_empty() {
+            return;
+        }
+
+        // Bound the work done in this block and carry the rest over
+        let max = Self::max_archive_per_block() as usize;
+        if due.len() > max {
+            let carried = due.split_off(max);
+            <ArchiveQueue<T>>::mutate(n + T::BlockNumber::one(), |queue| queue.extend(carried));
+        }
+
+        for (e, o, h) in due {
+            if Self::is_terminal(e, h.clone()) {
+                // Errors are ignored here as the record may have been archived manually already
+                if let Ok(_) = Self::set_archive(e, o.clone(), h.clone(), true) {
+                    Self::deposit_event(RawEvent::RecordArchived(e, o, h, true));
+                }
+            }
+        }
+    }
+}
+
+impl<T: Trait> Retention<T::AccountId, T::Hash> for Module<T> {
+    fn schedule_archive(e: RecordType, o: T::AccountId, h: T::Hash) -> Result {
+        // Nothing to do if there is no retention period for this type of record
+        if let Some(period) = Self::retention_period(e) {
+            let due: T::BlockNumber = <system::Module<T>>::block_number() + period;
+            <ArchiveQueue<T>>::mutate(due, |queue| queue.push((e, o, h)));
+        }
+
+        Ok(())
     }
 }
 
@@ -95,9 +209,11 @@ decl_event!(
     where
         AccountId = <T as system::Trait>::AccountId,
         Hash = <T as system::Trait>::Hash,
+        BlockNumber = <T as system::Trait>::BlockNumber,
         Archival = bool,
         RecordType = u16,
     {
         RecordArchived(RecordType, AccountId, Hash, Archival),
+        RetentionPeriodSet(RecordType, BlockNumber),
     }
 );
\ No newline at end of file
@@ -64,18 +64,20 @@
 /// (effectively rejecting the attempt to store the data), and if it does match then store the data using the reference hash as the key
 /// 3. in the event that an reference hash already exists, the data-hash obtained from the blockchain is always king. Provided it matches, overwrite exiting data.
 
-use parity_codec::{Encode};
-use support::{decl_event, decl_module, decl_storage, dispatch::Result, StorageMap};
+use support::{decl_event, decl_module, decl_storage, dispatch::Result, ensure, StorageMap, StorageValue};
+use support::dispatch::TransactionWeight;
 use substrate_primitives::H256;
-use system::{self, ensure_signed};
+use system::{self, ensure_signed, ensure_root};
 use rstd::prelude::*;
-use runtime_primitives::traits::{Hash, Convert};
+use runtime_primitives::traits::{Convert, Hash, Zero};
+use parity_codec::Encode;
 
 // Totem crates
 use crate::bonsai_traits::{ Storing };
 use crate::orders_traits::{Validating as OrderValidating};
 use crate::timekeeping_traits::{Validating as TimeValidating};
 use crate::projects_traits::{Validating as ProjectValidating};
+use crate::weights::{ READ, SETTING, WRITE };
 
 pub trait Trait: system::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
@@ -92,14 +94,26 @@ pub trait Trait: system::Trait {
 
 pub type RecordType = u16;
 
+/// Blocks a transaction UUID is kept when no retention has been set (30 days)
+const DEFAULT_UUID_RETENTION: u64 = 172800;
+
+/// UUIDs moved from the legacy `TxList` to the expiry queue per block
+const TX_LIST_DRAIN_PER_BLOCK: usize = 100;
+
 decl_storage! {
     trait Store for Module<T: Trait> as BonsaiModule {
         // Bonsai Storage
         IsValidRecord get(is_valid_record): map T::Hash => Option<T::Hash>; 
-        // Hacky workaround for inability of RPC to query transaction by hash
+        // Block number and extrinsic index in which the token of a record was last set. Used by off-chain databases to verify documents
+        RecordSetAt get(record_set_at): map T::Hash => Option<(T::BlockNumber, u32)>;
+        // Transaction UUID lifecycle. Queryable through the bonsai_transactionStatus RPC
         IsStarted get(is_started): map T::Hash => Option<T::BlockNumber>; // maps to current block number allows interrogation of errors
         IsSuccessful get(is_successful): map T::Hash => Option<T::BlockNumber>; // future block number beyond which the Hash should deleted
-        TxList get(tx_list):  map T::Hash => Vec<T::Hash>; // Tracking to ensure that we can perform housekeeping on finalization of block 
+        UuidExpiresAt get(uuid_expires_at): map T::Hash => Option<T::BlockNumber>; // block in which the UUID is removed
+        UuidExpiryQueue get(uuid_expiry_queue): map T::BlockNumber => Vec<T::Hash>; // UUIDs to be removed at the end of a block
+        UuidRetention get(uuid_retention): Option<T::BlockNumber>; // number of blocks a UUID is kept after each state change
+        // Legacy tracking of all UUIDs, replaced by the expiry queue. Drained in on_initialize, empty afterwards.
+        TxList get(tx_list): map T::Hash => Vec<T::Hash>;
     }
 }
 
@@ -114,6 +128,7 @@ decl_module! {
         /// * 4000 Timekeeping
         /// * 5000 Orders
         /// 
+        #[weight = TransactionWeight::Basic(4 * READ + 3 * WRITE, 1)]
         fn update_record(
             origin,
             record_type: RecordType, 
@@ -134,38 +149,28 @@ decl_module! {
             Ok(())
         }
         
-        fn on_finalize() {
-            let current_block: T::BlockNumber = <system::Module<T>>::block_number();
-            // Get all hashes
-            let default_bytes = b"nobody can save fiat currency now";
-            let list_key: T::Hash = T::Hashing::hash(default_bytes.encode().as_slice());
-            if <TxList<T>>::exists(&list_key) {
-                let hashes: Vec<T::Hash> = Self::tx_list(&list_key);
-                // check which storage the hashes come from and hashes that are old
-                for i in hashes {
-                    let key: T::Hash = i.clone();
-                    match Self::is_started(&key) {
-                        Some(block) => {
-                            let mut target_block: u64 = <T::Conversions as Convert<T::BlockNumber, u64>>::convert(block);
-                            target_block = target_block + 172800u64; 
-                            let target_deletion_block: T::BlockNumber = <T::Conversions as Convert<u64, T::BlockNumber>>::convert(target_block);
-                            // cleanup 30 Days from when the transaction started, but did not complete
-                            if current_block >= target_deletion_block {
-                                <IsStarted<T>>::remove(&i);
-                            }
-                        },
-                        None => {
-                            match Self::is_successful(&key) {
-                                Some(block) => {
-                                    if current_block >= block {
-                                        <IsSuccessful<T>>::remove(&i);
-                                    }       
-                                },
-                                None => (),
-                            }
-                        },
-                    }
-                    <TxList<T>>::mutate(&list_key, |tx_list| tx_list.retain(|v| {v != &i}));
+        /// Sets the number of blocks a transaction UUID is kept after it was started or completed
+        #[weight = TransactionWeight::Basic(SETTING, 1)]
+        fn set_uuid_retention(origin, blocks: T::BlockNumber) -> Result {
+            ensure_root(origin)?;
+            ensure!(blocks > T::BlockNumber::zero(), "Retention period must be at least one block");
+            <UuidRetention<T>>::put(blocks);
+            Self::deposit_event(RawEvent::UuidRetentionSet(blocks));
+            Ok(())
+        }
+        
+        fn on_initialize(n: T::BlockNumber) {
+            Self::drain_tx_list(n);
+        }
+
+        fn on_finalize(n: T::BlockNumber) {
+            // Only the UUIDs queued for this block are touched
+            for u in <UuidExpiryQueue<T>>::take(n) {
+                // the UUID may have been queued again for a later block since it was queued for this one
+                if Self::uuid_expires_at(&u) == Some(n) {
+                    <IsStarted<T>>::remove(&u);
+                    <IsSuccessful<T>>::remove(&u);
+                    <UuidExpiresAt<T>>::remove(&u);
                 }
             }
         }
@@ -178,13 +183,20 @@ impl<T: Trait> Module<T> {
         // then check that the supplied hash is owned by the signer of the transaction
         match e {
             3000 => {
-                if let false = <<T as Trait>::Projects as ProjectValidating<T::AccountId, T::Hash>>::is_project_owner(o.clone(), k.clone()) {
+                // project owner or manager
+                if let false = <<T as Trait>::Projects as ProjectValidating<T::AccountId, T::Hash>>::has_project_role(o.clone(), k.clone(), 100) {
                     Self::deposit_event(RawEvent::ErrorRecordOwner(t));
                     return Err("You cannot add a record you do not own");
                 }
             },
             4000 => {
-                if let false = <<T as Trait>::Timekeeping as TimeValidating<T::AccountId, T::Hash>>::is_time_record_owner(o.clone(), k.clone()) {
+                // the worker that owns the time record, or anyone allowed to authorise time on its project
+                let is_owner = <<T as Trait>::Timekeeping as TimeValidating<T::AccountId, T::Hash>>::is_time_record_owner(o.clone(), k.clone());
+                let is_approver = match <<T as Trait>::Timekeeping as TimeValidating<T::AccountId, T::Hash>>::time_record_project(k.clone()) {
+                    Some(project_hash) => <<T as Trait>::Projects as ProjectValidating<T::AccountId, T::Hash>>::has_project_role(o.clone(), project_hash, 200),
+                    None => false,
+                };
+                if !is_owner && !is_approver {
                     Self::deposit_event(RawEvent::ErrorRecordOwner(t));
                     return Err("You cannot add a record you do not own");
                 }
@@ -213,7 +225,10 @@ impl<T: Trait> Module<T> {
             ();
         }
         
-        <IsValidRecord<T>>::insert(k, t);
+        <IsValidRecord<T>>::insert(k.clone(), t);
+        
+        let set_at = (<system::Module<T>>::block_number(), <system::Module<T>>::extrinsic_index().unwrap_or_default());
+        <RecordSetAt<T>>::insert(k, set_at);
         
         Ok(())
     }
@@ -230,24 +245,65 @@ impl<T: Trait> Module<T> {
 
             // The transaction is now completed successfully update the state change
             // remove from started, and place in successful
-            let current_block = <system::Module<T>>::block_number();
-            let mut block: u64 = <T::Conversions as Convert<T::BlockNumber, u64>>::convert(current_block);
-            block = block + 172800u64; // cleanup in 30 Days
-            let deletion_block: T::BlockNumber = <T::Conversions as Convert<u64, T::BlockNumber>>::convert(block);
+            let deletion_block = Self::schedule_uuid_expiry(u.clone());
             <IsStarted<T>>::remove(&u);
             <IsSuccessful<T>>::insert(u, deletion_block);
             
         } else {
             // this is a new UUID just starting the transaction
             let current_block = <system::Module<T>>::block_number();
-            let default_bytes = b"nobody can save fiat currency now";
-            let list_key: T::Hash = T::Hashing::hash(default_bytes.encode().as_slice());
-            <TxList<T>>::mutate(list_key, |tx_list| tx_list.push(u));
+            Self::schedule_uuid_expiry(u.clone());
             <IsStarted<T>>::insert(u, current_block);
             
         }
         Ok(())
     }
+    
+    /// Retention for transaction UUIDs, defaults to 30 days
+    fn uuid_retention_period() -> T::BlockNumber {
+        Self::uuid_retention().unwrap_or_else(|| <T::Conversions as Convert<u64, T::BlockNumber>>::convert(DEFAULT_UUID_RETENTION))
+    }
+    
+    /// Queues the UUID for removal after the retention period and returns the expiry block.
+    /// Any earlier entry for this UUID in the queue is ignored when its block is finalised.
+    fn schedule_uuid_expiry(u: T::Hash) -> T::BlockNumber {
+        let expiry_block = <system::Module<T>>::block_number() + Self::uuid_retention_period();
+        <UuidExpiryQueue<T>>::mutate(expiry_block, |queue| queue.push(u.clone()));
+        <UuidExpiresAt<T>>::insert(u, expiry_block);
+        expiry_block
+    }
+
+    /// Moves the UUIDs tracked by the legacy `TxList` to the expiry queue, a bounded number per block.
+    /// Started UUIDs expire a retention period after they were started, completed ones at the block stored with them.
+    fn drain_tx_list(n: T::BlockNumber) {
+        let default_bytes = b"nobody can save fiat currency now";
+        let list_key: T::Hash = T::Hashing::hash(default_bytes.encode().as_slice());
+        if !<TxList<T>>::exists(&list_key) {
+            return;
+        }
+
+        let mut uuids = <TxList<T>>::take(&list_key);
+        if uuids.len() > TX_LIST_DRAIN_PER_BLOCK {
+            let rest = uuids.split_off(TX_LIST_DRAIN_PER_BLOCK);
+            <TxList<T>>::insert(&list_key, rest);
+        }
+
+        for u in uuids {
+            // Already queued by a state change since the upgrade
+            if <UuidExpiresAt<T>>::exists(&u) {
+                continue;
+            }
+            let expiry_block = match (Self::is_started(&u), Self::is_successful(&u)) {
+                (Some(started), _) => started + Self::uuid_retention_period(),
+                (None, Some(expiry)) => expiry,
+                (None, None) => continue,
+            };
+            // Anything overdue is removed at the end of this block
+            let expiry_block = if expiry_block < n { n } else { expiry_block };
+            <UuidExpiryQueue<T>>::mutate(expiry_block, |queue| queue.push(u.clone()));
+            <UuidExpiresAt<T>>::insert(u, expiry_block);
+        }
+    }
 }
 
 impl<T: Trait> Storing<T::Hash> for Module<T> {
@@ -265,7 +321,9 @@ decl_event!(
     pub enum Event<T>
     where
     Hash = <T as system::Trait>::Hash,
+    BlockNumber = <T as system::Trait>::BlockNumber,
     {
+        UuidRetentionSet(BlockNumber),
         ErrorRecordOwner(Hash),
         ErrorUnknownType(Hash),
     }
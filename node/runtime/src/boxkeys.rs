@@ -59,41 +59,58 @@
 ///    Because any identity can sign the transaction, we still need to validate the additional signature provided against the 
 ///    public signature key that was also provided.
 ///
-/// 2. The runtime checks if this is a new set of keys or a replacement to an existing set. In any case it will generate
-///    a random set of data to be encrypted against the provided public encryption key.
+/// 2. The runtime checks if this is a new set of keys or a replacement to an existing set. In any case it opens a challenge request
+///    for the keys pending verification. The request expires after a number of blocks, at which point it is removed together with
+///    the keys pending verification and the challenge, if one was issued.
 ///
-/// 3. The runtime generates an ephemeral (one-time use) secret key, used to encrypt the data to the provided public encryption key. 
-///    The ephemeral key is prepended to the "data to be encrypted" and then everything is encrypted and stored on chain. 
-/// 
-/// 4. Although the hash of the identifying userid is potentially public (and therefore can be used to monitor blockchain storage), 
-///    only the valid holder of the decryption keys can decipher the data on chain. Once decrypted, technically the ephemeral secret 
-///    key is revealed to the holder of the encryption key pair.
-/// 
-/// 5. The holder of the decrypted data is then required to sign the decrypted data with the signature keys that they are also claiming, 
-///    before sending the resulting signature along with the decrypted data as a transaction back to the blockchain runtime. 
-/// 
-/// 6. As the runtime did not store the unencrypted ephemeral secret key, receiving this information should permit the runtime to 
-///    regenerate the original cipher from the provided data. However !!! this by itself does not prove that the sender of the transaction 
-///    is in possession of the secret encryption key associated with the claimed public encryption key !!!
-///    The sender must also sign the revealed ephemeral secret key with the claimed signature key. Only if both these are fulfilled 
-///    can the keys be considered "authenticated".
+/// 3. The challenge issuer, an account appointed by root, generates a random 32 byte challenge off-chain and an ephemeral (one-time use)
+///    key pair, and encrypts the challenge with the ephemeral secret key to the claimed public encryption key. It submits the ciphertext,
+///    the nonce, the ephemeral public key and the hash of the challenge. The runtime cannot generate the challenge itself, as anything
+///    derived from chain data can be derived by anybody. The issuer is trusted not to disclose the challenges it generates.
+///
+/// 4. Although the hash of the identifying userid is potentially public (and therefore can be used to monitor blockchain storage),
+///    only the valid holder of the decryption keys can decipher the challenge on chain.
+///
+/// 5. The holder of the decrypted challenge is then required to sign it with the signature keys that they are also claiming,
+///    before sending the resulting signature along with the challenge as a transaction back to the blockchain runtime.
+///
+/// 6. The runtime compares the hash of the revealed challenge with the stored hash, which proves possession of the secret encryption key,
+///    and checks the signature, which proves possession of the secret signature key. Only if both these are fulfilled
+///    can the keys be considered "authenticated". Signature keys may be ed25519 or sr25519.
+///    Once the user hash is bound to an account, only that account can answer its challenges. Failed responses are counted per
+///    submitting account. Too many failures lock that account out of verification for a while, the pending keys are left untouched.
 ///
 /// 7. The process for replacing keys is identical with the added exception that the keys must be signed by the previous signature key.
+///    The current keys remain valid until the replacement keys are verified.
+///
+/// 8. Every verified key set is kept in a key history with the block range in which it was valid, so that signatures made with
+///    old keys can still be checked. A key that is known to be compromised can be placed on the revocation list by the holder of the
+///    current signature key.
+///
+/// 9. An AccountId can be bound to a user hash with verified keys. The transaction is signed by the account and carries a signature
+///    of the encoded (account, user hash) by the current signature key, so the binding is proven by both keys. Counterparties can then
+///    look up the encryption key of the AccountIds used in orders, projects and timekeeping.
 ///
 
 use parity_codec::{Decode, Encode};
-use substrate_primitives::{ed25519, H256};
+use substrate_primitives::H256;
 // use node_primitives::Hash;
 use rstd::prelude::*;
-use runtime_primitives::traits::Verify;
-use support::{decl_event, decl_module, decl_storage, StorageMap, dispatch::Result, ensure};
-use system::{self, ensure_signed};
-use runtime_io::{blake2_128, blake2_256};
+use runtime_primitives::MultiSignature;
+use runtime_primitives::traits::{As, Verify, Zero};
+use support::{decl_event, decl_module, decl_storage, StorageMap, StorageValue, dispatch::Result, ensure};
+use support::dispatch::TransactionWeight;
+use system::{self, ensure_signed, ensure_root};
+use runtime_io::blake2_256;
+
+// Totem crates
+use crate::boxkeys_traits::{ Lookup };
+use crate::weights::{ LIST, READ, SETTING, VERIFY, WRITE };
 
 // bring in Nacl encryption
-use sodalite::{box_, box_keypair_seed, BoxPublicKey, BoxSecretKey, BoxNonce};
+use sodalite::BoxNonce;
 
-pub trait Trait: system::Trait + timestamp::Trait {
+pub trait Trait: system::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 }
 
@@ -102,26 +119,38 @@ pub type EncryptPublicKey = H256; //32 bytes Hex
 
 pub type UserNameHash = H256;
 
-pub type Ed25519signature = ed25519::Signature; //AuthoritySignature
-pub type SignedBy = <Ed25519signature as Verify>::Signer; //AuthorityId
+pub type KeySignature = MultiSignature; // ed25519 or sr25519
+pub type SignedBy = <KeySignature as Verify>::Signer;
 
 pub type Data = Vec<u8>;
 
-type EphemeralPublicKey = BoxSecretKey; // generated internally
-type EphemeralSecretKey = BoxSecretKey; // generated internally
+/// Blocks a challenge can be answered when no lifetime has been set
+const DEFAULT_CHALLENGE_LIFETIME: u64 = 100;
+/// Blocks an account is locked out after too many failed verifications
+const FAILURE_LOCKOUT: u64 = 600;
+/// Length of the challenge revealed by the claimant
+const CHALLENGE_LENGTH: usize = 32;
+/// Leading zero bytes of a NaCl box plaintext and ciphertext
+const ZERO_BYTES: usize = 32;
+const BOX_ZERO_BYTES: usize = 16;
 
 #[derive(PartialEq, Eq, Clone, Encode, Decode, Default)]
 #[cfg_attr(feature = "std", derive(Debug))]
-struct PreEncryptionData<EphemeralSecretKey, Data> {
-    key: EphemeralSecretKey,
-    data: Data
+pub struct Challenge<BlockNumber> {
+    pub ephemeral_key: EncryptPublicKey, // public key to open the box with
+    pub nonce: EncryptNonce,
+    pub data: Data, // encrypted challenge without the 16 leading zero bytes of the box
+    pub challenge_hash: H256,
+    pub expires: BlockNumber,
 }
 
 #[derive(PartialEq, Eq, Clone, Encode, Decode, Default)]
 #[cfg_attr(feature = "std", derive(Debug))]
-pub struct EncryptedVerificationData<EncryptPublicKey,Data> {
-    key: EncryptPublicKey,
-    data : Data
+pub struct KeyValidity<EncryptPublicKey, SignedBy, BlockNumber> {
+    pub pub_enc_key: EncryptPublicKey,
+    pub pub_sign_key: SignedBy,
+    pub valid_from: BlockNumber,
+    pub valid_until: Option<BlockNumber>, // None while the keys are current
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Encode, Decode, Default)]
@@ -139,7 +168,19 @@ decl_storage! {
         TempPublicKeyEnc get(temp_public_key_enc): map UserNameHash => Option<EncryptPublicKey>;
         PublicKeySign get(public_key_sign): map UserNameHash => Option<SignedBy>;
         TempPublicKeySign get(temp_public_key_sign): map UserNameHash => Option<SignedBy>;
-        VerificationData get(verification_data): map UserNameHash => Option<EncryptedVerificationData<EncryptPublicKey, Data>>;
+        ChallengeRequests get(challenge_request): map UserNameHash => Option<T::BlockNumber>;
+        Challenges get(challenge): map UserNameHash => Option<Challenge<T::BlockNumber>>;
+        ChallengeExpiry get(challenge_expiry): map T::BlockNumber => Vec<UserNameHash>;
+        ChallengeLifetime get(challenge_lifetime): Option<T::BlockNumber>;
+        ChallengeIssuer get(challenge_issuer): Option<T::AccountId>;
+        FailedVerifications get(failed_verifications): map T::AccountId => u32;
+        MaxFailedVerifications get(max_failed_verifications): u32 = 5;
+        LockedUntil get(locked_until): map T::AccountId => Option<T::BlockNumber>;
+        KeyHistory get(key_history): map UserNameHash => Vec<KeyValidity<EncryptPublicKey, SignedBy, T::BlockNumber>>;
+        RevocationList get(revocation_list): map UserNameHash => Vec<SignedBy>;
+        RevokedAt get(revoked_at): map (UserNameHash, SignedBy) => Option<T::BlockNumber>;
+        AccountUserHash get(account_user_hash): map T::AccountId => Option<UserNameHash>;
+        UserHashAccount get(user_hash_account): map UserNameHash => Option<T::AccountId>;
     }
 }
 
@@ -148,107 +189,179 @@ decl_module! {
         fn deposit_event<T>() = default;
         
         /// deletes all keys. requires a valid signature (from the public signing key) 
+        #[weight = TransactionWeight::Basic(READ + VERIFY + LIST + 8 * WRITE, 1)]
         fn destroy_keys(
             origin,
             user_hash: UserNameHash, // this is what is signed 
-            signature: Ed25519signature
+            signature: KeySignature
         ) -> Result {
 
             // provided you are the owner of the keys you can remove them entirely from storage.
             let sign_key = Self::public_key_sign(&user_hash).ok_or("Storage Read Error: cannot get signature key")?; 
             ensure!(signature.verify(&user_hash[..], &sign_key), "Invalid signature for this key");
 
+            // the history is kept so that old signatures can still be checked
+            Self::close_key_history(user_hash);
+
+            // the keys no longer prove the account binding
+            Self::remove_binding(user_hash);
+
             // no matter what, remove everything
             <UserKeysVerified<T>>::take(&user_hash);
             <PublicKeyEnc<T>>::take(&user_hash);
             <TempPublicKeyEnc<T>>::take(&user_hash);
             <PublicKeySign<T>>::take(&user_hash);
             <TempPublicKeySign<T>>::take(&user_hash);
-            <VerificationData<T>>::take(&user_hash);
+            Self::remove_challenge(user_hash);
     
             Ok(())
 
         }
         
-        /// this should only be called when there is data to verify.
+        /// Answers the challenge issued for the keys pending verification.
         /// TODO implement a significant fee
+        #[weight = TransactionWeight::Basic(6 * READ + VERIFY + LIST + 8 * WRITE, 1)]
         fn auto_verification(
             origin,
             user_hash: UserNameHash, // hash of unique userid
-            decrypted: Vec<u8>, // this is a tuple containing (random_validation_key, &ephemeral_secret_key).encode() 
-            signature: Ed25519signature // detached signature of "discovered ephemeral secret key"
+            decrypted: Vec<u8>, // the decrypted challenge
+            signature: KeySignature // detached signature of the decrypted challenge by the claimed signature key
         ) -> Result {
             // transaction must be signed
-            let _user = ensure_signed(origin)?;
-
-            // have they signed the decrypted_data with the correct public key? Yes
-            let decrypted_data = decrypted.clone(); 
-
-            let temp_sign_key = Self::temp_public_key_sign(&user_hash).ok_or("Storage Read Error: cannot get signature key")?; 
-            ensure!(signature.verify(&decrypted_data[..], &temp_sign_key), "Invalid signature for this key");
-            
-            // grab the claimed encryption public key from temp storage
-            let temp_encrypt_key = Self::temp_public_key_enc(&user_hash).ok_or("Storage Read Error: cannot get encryption key")?; 
-
-            // grab the verification data
-            let data_to_compare = Self::verification_data(&user_hash).ok_or("Storage Read Error: cannot get verification data")?; 
-            
-            // grab the revealed ephemeral secret key
-            let unwrapped_data: PreEncryptionData<EphemeralSecretKey, Data> = PreEncryptionData::decode(&mut &decrypted[..]).ok_or("Error parsing the data sent for validation")?;
-           
-            // Now check that the data supplied can create the correct cipher as stored
-            // we should receive the data already encoded, so no need to do anything special
-            let data_to_encrypt = decrypted.clone();
+            let who = ensure_signed(origin)?;
 
-            // Convert from H256 to [u8; 32]. Might need dereferencing in other contexts
-            let external_pub_key: &BoxPublicKey  = temp_encrypt_key.as_fixed_bytes();
+            // once bound, nobody but the bound account can answer (and fail) on behalf of the user hash
+            if let Some(account) = Self::user_hash_account(&user_hash) {
+                ensure!(account == who, "Only the account bound to these keys can verify them");
+            }
+            Self::ensure_not_locked(&who)?;
+            let current_block = <system::Module<T>>::block_number();
 
-            // this is a dummy placeholder nonce
-            let nonce_24: EncryptNonce = [0u8; 24];
+            let challenge = Self::challenge(&user_hash).ok_or("There is no challenge to answer, or it has expired")?;
+            ensure!(current_block < challenge.expires, "The challenge has expired");
 
-            // initialise ciphertext with a default value 
-            let mut cipher_text = [0u8];
-        
-            // Re encrypt the supplied data returning cipher_text, which will be compared to the stored version
-            match box_(&mut cipher_text, &data_to_encrypt, &nonce_24, external_pub_key, &unwrapped_data.key) {
-                Err(_e) => return Err("Encryption failed."),
-                _ => ()
-            };
+            let temp_sign_key = Self::temp_public_key_sign(&user_hash).ok_or("Storage Read Error: cannot get signature key")?; 
 
-            // compare newly processes cipher to stored cipher, if they agree we have a match!
-            if data_to_compare.data != cipher_text.to_vec() {
+            // The challenge could only be decrypted with the secret encryption key,
+            // and it must be signed with the secret signature key.
+            let is_answered = H256::from(blake2_256(&decrypted[..])) == challenge.challenge_hash;
+            if !is_answered || !signature.verify(&decrypted[..], &temp_sign_key) {
+                Self::record_failure(who, user_hash, current_block);
                 return Err("There was an error authenticating the supplied data");
-            };
+            }
 
-            // if we get this far then the data was decrypted by the owner of the encryption key, 
-            // and it was signed by the owner of the signature key
+            // if we get this far then the data was decrypted by the owner of the encryption key,
+            // and signed by the owner of the signature key
+            Self::remove_challenge(user_hash);
+            <FailedVerifications<T>>::remove(&who);
                 
             // mark the keys as veriffed
             Self::set_verification_state(user_hash, true)?;
             
+            // the previous keys (if any) are valid until this block
+            Self::close_key_history(user_hash);
+            
             // move the keys to the verified storage
             Self::move_temp_keys(user_hash)?;
             
             // remove the keys fro the temp storage
             Self::delete_temp_keys(user_hash)?;
             
+            Self::deposit_event(RawEvent::KeysVerified(user_hash));
+            
             Ok(())
                 
         }
         
+        /// Sets the number of blocks a challenge can be answered
+        #[weight = TransactionWeight::Basic(SETTING, 1)]
+        fn set_challenge_lifetime(origin, blocks: T::BlockNumber) -> Result {
+            ensure_root(origin)?;
+            ensure!(blocks > T::BlockNumber::zero(), "Challenge lifetime must be at least one block");
+            <ChallengeLifetime<T>>::put(blocks);
+            Ok(())
+        }
+        
+        /// Sets the number of failed verifications after which a user hash is locked out
+        #[weight = TransactionWeight::Basic(SETTING, 1)]
+        fn set_max_failed_verifications(origin, attempts: u32) -> Result {
+            ensure_root(origin)?;
+            ensure!(attempts > 0, "At least one attempt must be allowed");
+            <MaxFailedVerifications<T>>::put(attempts);
+            Ok(())
+        }
+        
+        /// Appoints the account that issues the encrypted challenges
+        #[weight = TransactionWeight::Basic(SETTING, 1)]
+        fn set_challenge_issuer(origin, issuer: T::AccountId) -> Result {
+            ensure_root(origin)?;
+            <ChallengeIssuer<T>>::put(issuer);
+            Ok(())
+        }
+        
+        /// Issues the challenge for a pending request. The challenge is generated off-chain by the issuer,
+        /// and encrypted with an ephemeral secret key to the claimed encryption key.
+        #[weight = TransactionWeight::Basic(5 * READ + 3 * WRITE, 1)]
+        fn issue_challenge(
+            origin,
+            user_hash: UserNameHash,
+            ephemeral_key: EncryptPublicKey, // ephemeral public key the challenge was encrypted with
+            nonce: EncryptNonce,
+            data: Data, // encrypted challenge without the 16 leading zero bytes of the box
+            challenge_hash: H256 // blake2_256 hash of the challenge
+        ) -> Result {
+            let who = ensure_signed(origin)?;
+            ensure!(Self::challenge_issuer() == Some(who), "Only the challenge issuer can issue challenges");
+            
+            let expires = Self::challenge_request(&user_hash).ok_or("There is no pending challenge request for these keys")?;
+            ensure!(<system::Module<T>>::block_number() < expires, "The challenge request has expired");
+            ensure!(<TempPublicKeyEnc<T>>::exists(&user_hash), "Storage Read Error: cannot get encryption key");
+            ensure!(data.len() == ZERO_BYTES + CHALLENGE_LENGTH - BOX_ZERO_BYTES, "The encrypted challenge has the wrong length");
+            
+            // the request is answered, the expiry stays queued for the challenge
+            <ChallengeRequests<T>>::remove(&user_hash);
+            <Challenges<T>>::insert(&user_hash, Challenge {
+                ephemeral_key: ephemeral_key,
+                nonce: nonce,
+                data: data,
+                challenge_hash: challenge_hash,
+                expires: expires,
+            });
+            
+            Self::deposit_event(RawEvent::ChallengeIssued(user_hash, expires));
+            
+            Ok(())
+        }
+        
+        fn on_finalize(n: T::BlockNumber) {
+            // unanswered challenges expire with the keys pending verification
+            for user_hash in <ChallengeExpiry<T>>::take(n) {
+                let expired = Self::challenge(&user_hash).map(|c| c.expires) == Some(n)
+                    || Self::challenge_request(&user_hash) == Some(n);
+                if expired {
+                    <Challenges<T>>::remove(&user_hash);
+                    <ChallengeRequests<T>>::remove(&user_hash);
+                    let _ = Self::delete_temp_keys(user_hash);
+                    Self::deposit_event(RawEvent::ChallengeExpired(user_hash));
+                }
+            }
+        }
+        
         // a unique User registers (untrusted/unvalidated) encryption and signing keys
         /// TODO implement a significant fee
+        #[weight = TransactionWeight::Basic(3 * READ + VERIFY + LIST + 4 * WRITE, 1)]
         fn register_keys(
             origin,
             user_hash: UserNameHash, // hash of unique userid
             pub_enc_key: EncryptPublicKey, // master public encryption key associated with chat user
             pub_sign_key: SignedBy, // master public signing key associated with chat user
             nonce: EncryptNonce, // just a nonce generated in the UI
-            signature: Ed25519signature // detached signature
+            signature: KeySignature // detached signature
         ) -> Result {
             
             // check that the transaction is signed
-            let _user = ensure_signed(origin)?;
+            let user = ensure_signed(origin)?;
+            Self::ensure_not_locked(&user)?;
             // if the usernamehash exists, compare keys
             
             // TODO Errors can occur here!!!! Need to validate inputs.
@@ -263,50 +376,19 @@ decl_module! {
             match Self::user_keys_verified(user_hash.clone()) {
                 Some(true) => {
                     // The existing key is verified, but this time it may be a replacement of the key(s).
-                    // Get both keys from storage or error.
-                    let old_enc_key = Self::public_key_enc(&user_hash).ok_or("Storage Read Error: cannot get encryption key, or key is not verified")?; 
-                    let old_sign_key = Self::public_key_sign(&user_hash).ok_or("Storage Read Error: cannot get signature key, or key is not verified")?; 
-                    
-                    let transaction_data_clone = transaction_data.clone(); 
-                    let encoded_data: Vec<u8> = transaction_data_clone.encode(); 
-                    // If the encryption key or the signing key are not the same as already stored
-                    if old_enc_key != transaction_data.pub_enc_key || old_sign_key != transaction_data.pub_sign_key {
-                        // The keys are different, 
-                        // Check that the NEW data is signed by the OLD signature key
-                        ensure!(signature.verify(&encoded_data[..], &old_sign_key), "Invalid signature for this key");
-                        
-                        // remove and replace keys                        
-                        Self::delete_state_and_temp_keys(user_hash)?;
-                        
-                        // Store keys in temp space pending verification. It is necessary to do this now.
-                        // If a later process fails this will be replaced anyway.
-                        if old_enc_key != transaction_data.pub_enc_key {
-                            <TempPublicKeyEnc<T>>::insert(&user_hash, &transaction_data.pub_enc_key);
-                        };
-                        
-                        if old_sign_key != transaction_data.pub_sign_key {
-                            <TempPublicKeySign<T>>::insert(&user_hash, &transaction_data.pub_sign_key);
-                        };
-                        
-                        // set the verification data.
-                        Self::set_generated_verification_data(transaction_data)?;
-                        
-                        // set the verification status to false.
-                        Self::set_verification_state(user_hash, false)?;
-
-                    }; // if the keys are the same, do nothing    
-                    
-                    
+                    Self::request_rotation(transaction_data, signature)?;
                 }, 
                 Some(false) => return Err("The existing key hasn't yet been formally validated by the key owner"),
                 None => {
-                    // This is a first set of keys
+                    // This is a first set of keys, signed by the claimed signature key
+                    ensure!(signature.verify(&transaction_data.encode()[..], &transaction_data.pub_sign_key), "Invalid signature for this key");
+                    ensure!(!<RevokedAt<T>>::exists((user_hash.clone(), transaction_data.pub_sign_key.clone())), "This key has been revoked");
                     // Store keys in temp space pending verification
                     <TempPublicKeyEnc<T>>::insert(&user_hash, &transaction_data.pub_enc_key);
                     <TempPublicKeySign<T>>::insert(&user_hash, &transaction_data.pub_sign_key);
 
                     // set the verification data
-                    Self::set_generated_verification_data(transaction_data)?;
+                    Self::set_challenge(transaction_data)?;
 
                 }  
             } //match
@@ -314,6 +396,99 @@ decl_module! {
             // todo add event
             Ok(())
         } 
+        
+        /// Replaces verified keys. The new keys must be signed by the current signature key.
+        /// The current keys remain valid until the new keys are verified with `auto_verification`.
+        /// TODO implement a significant fee
+        #[weight = TransactionWeight::Basic(3 * READ + VERIFY + LIST + 4 * WRITE, 1)]
+        fn rotate_keys(
+            origin,
+            user_hash: UserNameHash, // hash of unique userid
+            pub_enc_key: EncryptPublicKey, // new public encryption key
+            pub_sign_key: SignedBy, // new public signing key
+            nonce: EncryptNonce, // just a nonce generated in the UI
+            signature: KeySignature // detached signature by the current signing key
+        ) -> Result {
+            let user = ensure_signed(origin)?;
+            
+            ensure!(Self::user_keys_verified(&user_hash) == Some(true), "There are no verified keys to replace");
+            Self::ensure_not_locked(&user)?;
+            
+            let transaction_data = SignedData {
+                user_hash: user_hash.clone(),
+                pub_enc_key: pub_enc_key,
+                pub_sign_key: pub_sign_key,
+                nonce: nonce,
+            };
+            
+            Self::request_rotation(transaction_data, signature)
+        }
+        
+        /// Binds the signing account to a user hash with verified keys.
+        /// The signature is made by the current signature key over the encoded (account, user_hash).
+        #[weight = TransactionWeight::Basic(3 * READ + VERIFY + 2 * WRITE, 1)]
+        fn bind_account(
+            origin,
+            user_hash: UserNameHash,
+            signature: KeySignature
+        ) -> Result {
+            let who = ensure_signed(origin)?;
+            
+            ensure!(Self::user_keys_verified(&user_hash) == Some(true), "The keys for this user are not verified");
+            let sign_key = Self::public_key_sign(&user_hash).ok_or("Storage Read Error: cannot get signature key")?; 
+            let encoded_data: Vec<u8> = (who.clone(), user_hash.clone()).encode();
+            ensure!(signature.verify(&encoded_data[..], &sign_key), "Invalid signature for this key");
+            
+            // one account per user hash and one user hash per account
+            ensure!(!<AccountUserHash<T>>::exists(&who), "This account is already bound to keys");
+            ensure!(!<UserHashAccount<T>>::exists(&user_hash), "These keys are already bound to an account");
+            
+            <AccountUserHash<T>>::insert(&who, &user_hash);
+            <UserHashAccount<T>>::insert(&user_hash, &who);
+            
+            Self::deposit_event(RawEvent::AccountBound(who, user_hash));
+            
+            Ok(())
+        }
+        
+        /// Removes the binding of the signing account.
+        #[weight = TransactionWeight::Basic(READ + 2 * WRITE, 1)]
+        fn unbind_account(origin) -> Result {
+            let who = ensure_signed(origin)?;
+            
+            let user_hash = Self::account_user_hash(&who).ok_or("This account is not bound to any keys")?;
+            Self::remove_binding(user_hash);
+            
+            Ok(())
+        }
+        
+        /// Places a previously used signing key on the revocation list.
+        /// Signed by the current signature key over the encoded (user_hash, revoked_key).
+        #[weight = TransactionWeight::Basic(2 * READ + VERIFY + LIST + 2 * WRITE, 1)]
+        fn revoke_key(
+            origin,
+            user_hash: UserNameHash,
+            revoked_key: SignedBy,
+            signature: KeySignature
+        ) -> Result {
+            let _user = ensure_signed(origin)?;
+            
+            let sign_key = Self::public_key_sign(&user_hash).ok_or("Storage Read Error: cannot get signature key")?; 
+            let encoded_data: Vec<u8> = (user_hash.clone(), revoked_key.clone()).encode();
+            ensure!(signature.verify(&encoded_data[..], &sign_key), "Invalid signature for this key");
+            
+            // the current key must be rotated before it can be revoked
+            ensure!(sign_key != revoked_key, "Cannot revoke the current key. Rotate the keys first");
+            ensure!(Self::key_history(&user_hash).iter().any(|k| k.pub_sign_key == revoked_key), "This key was never used by this user");
+            ensure!(!<RevokedAt<T>>::exists((user_hash.clone(), revoked_key.clone())), "This key is already revoked");
+            
+            <RevokedAt<T>>::insert((user_hash.clone(), revoked_key.clone()), <system::Module<T>>::block_number());
+            <RevocationList<T>>::mutate(&user_hash, |list| list.push(revoked_key.clone()));
+            
+            Self::deposit_event(RawEvent::KeyRevoked(user_hash, revoked_key));
+            
+            Ok(())
+        }
 
     }
     
@@ -324,32 +499,79 @@ decl_event!(
     where
     AccountId = <T as system::Trait>::AccountId,
     Hash = <T as system::Trait>::Hash,
+    BlockNumber = <T as system::Trait>::BlockNumber,
     {
         SubmitedKeys(AccountId, Hash),
+        KeysVerified(UserNameHash),
+        ChallengeRequested(UserNameHash, BlockNumber),
+        ChallengeIssued(UserNameHash, BlockNumber),
+        ChallengeExpired(UserNameHash),
+        VerificationFailed(AccountId, UserNameHash, u32),
+        VerificationLocked(AccountId, BlockNumber),
+        KeysRotationRequested(UserNameHash),
+        KeyRevoked(UserNameHash, SignedBy),
+        AccountBound(AccountId, UserNameHash),
+        AccountUnbound(AccountId, UserNameHash),
     }
 );
 
 impl<T: Trait> Module<T> {
-    fn get_pseudo_random_value(data: &SignedData<UserNameHash, EncryptPublicKey, SignedBy, EncryptNonce>) -> [u8; 16] {
-        let input = (
-            <timestamp::Module<T>>::get(),
-            <system::Module<T>>::random_seed(),
-            data,
-            <system::Module<T>>::extrinsic_index(),
-            <system::Module<T>>::block_number(),
-        );
-        return input.using_encoded(blake2_128);
+    /// Checks if the signing key was valid for the user at the given block and has not been revoked.
+    pub fn is_sign_key_valid_at(user_hash: UserNameHash, key: SignedBy, block: T::BlockNumber) -> bool {
+        if <RevokedAt<T>>::exists((user_hash.clone(), key.clone())) {
+            return false;
+        }
+        Self::key_history(&user_hash).iter().any(|k| {
+            k.pub_sign_key == key && k.valid_from <= block && k.valid_until.map_or(true, |until| block < until)
+        })
     }
-
     
-    fn delete_state_and_temp_keys(user_hash: UserNameHash) -> Result {        
-        <UserKeysVerified<T>>::take(&user_hash);
-
-        Self::delete_temp_keys(user_hash)?;
+    /// Stages replacement keys signed by the current signature key, pending verification.
+    fn request_rotation(transaction_data: SignedData<UserNameHash, EncryptPublicKey, SignedBy, EncryptNonce>, signature: KeySignature) -> Result {
+        let user_hash = transaction_data.user_hash.clone();
+        let old_enc_key = Self::public_key_enc(&user_hash).ok_or("Storage Read Error: cannot get encryption key, or key is not verified")?; 
+        let old_sign_key = Self::public_key_sign(&user_hash).ok_or("Storage Read Error: cannot get signature key, or key is not verified")?; 
+        
+        // if the keys are the same, do nothing
+        if old_enc_key == transaction_data.pub_enc_key && old_sign_key == transaction_data.pub_sign_key {
+            return Ok(());
+        }
+        
+        // Check that the NEW data is signed by the OLD signature key
+        let encoded_data: Vec<u8> = transaction_data.encode(); 
+        ensure!(signature.verify(&encoded_data[..], &old_sign_key), "Invalid signature for this key");
+        ensure!(!<RevokedAt<T>>::exists((user_hash.clone(), transaction_data.pub_sign_key.clone())), "This key has been revoked");
+        
+        // Store both keys in temp space pending verification, replacing any earlier request.
+        // The current keys stay in place and verified until then.
+        <TempPublicKeyEnc<T>>::insert(&user_hash, &transaction_data.pub_enc_key);
+        <TempPublicKeySign<T>>::insert(&user_hash, &transaction_data.pub_sign_key);
+        
+        // set the verification data.
+        Self::set_challenge(transaction_data)?;
+        
+        Self::deposit_event(RawEvent::KeysRotationRequested(user_hash));
         
         Ok(())
     }
     
+    fn remove_binding(user_hash: UserNameHash) {
+        if let Some(account) = <UserHashAccount<T>>::take(&user_hash) {
+            <AccountUserHash<T>>::remove(&account);
+            Self::deposit_event(RawEvent::AccountUnbound(account, user_hash));
+        }
+    }
+    
+    /// Ends the validity of the current keys (if any) at this block and records the newly verified keys.
+    fn close_key_history(user_hash: UserNameHash) {
+        let current_block = <system::Module<T>>::block_number();
+        <KeyHistory<T>>::mutate(&user_hash, |history| {
+            for k in history.iter_mut().filter(|k| k.valid_until.is_none()) {
+                k.valid_until = Some(current_block);
+            }
+        });
+    }
+    
     fn delete_temp_keys(user_hash: UserNameHash) -> Result {
         <TempPublicKeyEnc<T>>::take(&user_hash);
         <TempPublicKeySign<T>>::take(&user_hash);
@@ -364,8 +586,15 @@ impl<T: Trait> Module<T> {
         <PublicKeySign<T>>::take(&user_hash);
         <PublicKeyEnc<T>>::take(&user_hash);
         // insert keys
-        <PublicKeySign<T>>::insert(&user_hash, sign_key);
-        <PublicKeyEnc<T>>::insert(&user_hash, enc_key);
+        <PublicKeySign<T>>::insert(&user_hash, sign_key.clone());
+        <PublicKeyEnc<T>>::insert(&user_hash, enc_key.clone());
+        
+        <KeyHistory<T>>::mutate(&user_hash, |history| history.push(KeyValidity {
+            pub_enc_key: enc_key,
+            pub_sign_key: sign_key,
+            valid_from: <system::Module<T>>::block_number(),
+            valid_until: None,
+        }));
         
         Ok(())
     }
@@ -376,63 +605,69 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
-    fn set_generated_verification_data(transaction_data: SignedData<UserNameHash, EncryptPublicKey, SignedBy, EncryptNonce>) -> Result {
-        // generate 128bit verification data
-        let random_validation_key = Self::get_pseudo_random_value(&transaction_data);
-        
-        // encrypt verification data
-    
-        // Generate ephemeral keys for symmetric encryption
-        let mut ephemeral_public_key: EphemeralPublicKey = Default::default();
-        let mut ephemeral_secret_key: EphemeralSecretKey = Default::default();
+    fn challenge_lifetime_period() -> T::BlockNumber {
+        Self::challenge_lifetime().unwrap_or_else(|| T::BlockNumber::sa(DEFAULT_CHALLENGE_LIFETIME))
+    }
+
+    /// Opens a challenge request for the keys pending verification, replacing any earlier request or challenge.
+    /// The challenge itself is issued by the challenge issuer.
+    fn set_challenge(transaction_data: SignedData<UserNameHash, EncryptPublicKey, SignedBy, EncryptNonce>) -> Result {
+        let user_hash = transaction_data.user_hash;
+        let expires = <system::Module<T>>::block_number() + Self::challenge_lifetime_period();
         
-        let ephemeral_secret_seed = <system::Module<T>>::random_seed().using_encoded(blake2_256);
+        Self::remove_challenge(user_hash);
+        <ChallengeRequests<T>>::insert(&user_hash, expires);
+        <ChallengeExpiry<T>>::mutate(expires, |queue| queue.push(user_hash));
         
-        box_keypair_seed(&mut ephemeral_public_key, &mut ephemeral_secret_key, &ephemeral_secret_seed);                        
-                                
-        // this is a dummy placeholder until we work out how to increment nonce
-        let last_nonce_24: EncryptNonce = [0u8; 24];
-
-        // populate struct with data for manipulation.
-        let pre_encrytion_data = PreEncryptionData {
-            key: &ephemeral_secret_key,
-            data: &random_validation_key
-        };
+        Self::deposit_event(RawEvent::ChallengeRequested(user_hash, expires));
         
-        let data_to_encrypt = pre_encrytion_data.encode();
-    
-        // Convert from H256 to [u8; 32]. Might need dereferencing in other contexts
-        let external_pub_key: &BoxPublicKey  = transaction_data.pub_enc_key.as_fixed_bytes();
+        Ok(())
+    }
     
-        // initialise ciphertext with a default value 
-        let mut cipher_text = [0u8];
+    fn remove_challenge(user_hash: UserNameHash) {
+        let expires = <ChallengeRequests<T>>::take(&user_hash).or_else(|| <Challenges<T>>::take(&user_hash).map(|c| c.expires));
+        if let Some(expires) = expires {
+            <ChallengeExpiry<T>>::mutate(expires, |queue| queue.retain(|u| u != &user_hash));
+        }
+    }
     
-        // Encrypt data returning cipher_text
-        match box_(&mut cipher_text, &data_to_encrypt, &last_nonce_24, external_pub_key, &ephemeral_secret_key) {
-            Err(_e) => return Err("Encryption failed."),
-            Ok(_s) => ()
-        };
-
-        let encrypted_verification_data = EncryptedVerificationData {
-            key: ed25519::Public::from_raw(ephemeral_public_key).0.into(), // convert from raw public key to UI readable public key
-            data: cipher_text.to_vec(),  // cast cipher_text to Vec<u8> string for storage (and ease of use in UI)
-        };
+    /// Keys cannot be submitted or verified by an account that is locked out
+    fn ensure_not_locked(who: &T::AccountId) -> Result {
+        if let Some(until) = Self::locked_until(who) {
+            ensure!(<system::Module<T>>::block_number() >= until, "Too many failed verifications. Try again later");
+            <LockedUntil<T>>::remove(who);
+        }
+        Ok(())
+    }
     
-        match Self::set_validation_data(transaction_data, encrypted_verification_data) {
-            true => return Ok(()),
-            false => return Err("Error storing validation data"),
+    /// Counts a failed verification against the submitting account. Reaching the maximum locks the account out.
+    /// The challenge and the keys pending verification are kept, so that the holder of the keys can still answer.
+    fn record_failure(who: T::AccountId, user_hash: UserNameHash, current_block: T::BlockNumber) {
+        let failures = Self::failed_verifications(&who).saturating_add(1);
+        if failures >= Self::max_failed_verifications() {
+            let until = current_block + T::BlockNumber::sa(FAILURE_LOCKOUT);
+            <LockedUntil<T>>::insert(&who, until);
+            <FailedVerifications<T>>::remove(&who);
+            Self::deposit_event(RawEvent::VerificationLocked(who, until));
+        } else {
+            <FailedVerifications<T>>::insert(&who, failures);
+            Self::deposit_event(RawEvent::VerificationFailed(who, user_hash, failures));
         }
-        
     }
+}
 
-    fn set_validation_data(transaction_data: SignedData<UserNameHash, EncryptPublicKey, SignedBy, EncryptNonce>, 
-        verify_this: EncryptedVerificationData<EncryptPublicKey, Data>) -> bool {
-        
-        // EncryptedVerificationData(Data, EncryptNonce);
-        <VerificationData<T>>::take(&transaction_data.user_hash);
-        // insert (or in the case of new keys, replace)
-        <VerificationData<T>>::insert(transaction_data.user_hash, verify_this);
-    
-        return true;
+impl<T: Trait> Lookup<T::AccountId> for Module<T> {
+    fn user_hash_of(a: T::AccountId) -> Option<H256> {
+        Self::account_user_hash(&a)
+    }
+    fn account_of(u: H256) -> Option<T::AccountId> {
+        Self::user_hash_account(&u)
     }
-}
\ No newline at end of file
+    fn encryption_key_of(a: T::AccountId) -> Option<H256> {
+        let user_hash = Self::account_user_hash(&a)?;
+        match Self::user_keys_verified(&user_hash) {
+            Some(true) => Self::public_key_enc(&user_hash),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,276 @@
+//!                              Næ§@@@ÑÉ©
+//!                        æ@@@@@@@@@@@@@@@@@@
+//!                    Ñ@@@@?.?@@@@@@@@@@@@@@@@@@@N
+//!                 ¶@@@@@?^%@@.=@@@@@@@@@@@@@@@@@@@@
+//!               N@@@@@@@?^@@@»^@@@@@@@@@@@@@@@@@@@@@@
+//!               @@@@@@@@?^@@@».............?@@@@@@@@@É
+//!              Ñ@@@@@@@@?^@@@@@@@@@@@@@@@@@@'?@@@@@@@@Ñ
+//!              @@@@@@@@@?^@@@»..............»@@@@@@@@@@
+//!              @@@@@@@@@?^@@@»^@@@@@@@@@@@@@@@@@@@@@@@@
+//!              @@@@@@@@@?^ë@@&.@@@@@@@@@@@@@@@@@@@@@@@@
+//!               @@@@@@@@?^´@@@o.%@@@@@@@@@@@@@@@@@@@@©
+//!                @@@@@@@?.´@@@@@ë.........*.±@@@@@@@æ
+//!                 @@@@@@@@?´.I@@@@@@@@@@@@@@.&@@@@@N
+//!                  N@@@@@@@@@@ë.*=????????=?@@@@@Ñ
+//!                    @@@@@@@@@@@@@@@@@@@@@@@@@@@¶
+//!                        É@@@@@@@@@@@@@@@@Ñ¶
+//!                             Næ§@@@ÑÉ©
+
+//! Copyright 2020 Chris D'Costa
+//! This file is part of Totem Live Accounting.
+//! Author Chris D'Costa email: chris.dcosta@totemaccounting.com
+
+//! Totem is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License, or
+//! (at your option) any later version.
+
+//! Totem is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+
+//! You should have received a copy of the GNU General Public License
+//! along with Totem.  If not, see <http://www.gnu.org/licenses/>.
+
+
+/// Transaction fee routing.
+/// 
+/// Replaces the balances module as the payment handler of the executive. The fee is calculated and withdrawn as 
+/// before, from the transaction base fee, the fee per encoded byte and the fee per unit of weight of the call, but 
+/// instead of being burned it is split between the treasury and the author of the block. The author share is set in 
+/// parts per ten thousand of the fee.
+/// 
+/// The block author is the session validator whose Aura slot the block was produced in. When there is no author 
+/// the whole fee goes to the treasury.
+/// 
+/// Each fee is posted in the ledgers: the payer expenses network fees against its XTX balance, and the block author 
+/// books its share as network fee income. The treasury share has no identity and so is not posted.
+/// 
+/// Payment is made during transaction validation as well as on inclusion, so the payment only moves funds and queues 
+/// the fee. The queued fees are posted at the end of the block, and an accounting error cannot lose a fee that was paid.
+
+use support::{decl_event, decl_module, decl_storage, dispatch::Result, ensure, StorageMap, StorageValue};
+use support::dispatch::TransactionWeight;
+use support::traits::{Currency, ExistenceRequirement, Imbalance, MakePayment, OnUnbalanced, WithdrawReason};
+use system::{self, ensure_root};
+use rstd::prelude::*;
+use runtime_primitives::traits::{As, Convert, Zero};
+use runtime_primitives::weights::Weight;
+
+// Totem Traits
+use crate::accounting_traits::{ Posting };
+use crate::weights::{ SETTING };
+
+// Totem Trait Types
+type AccountOf<T> = <<T as Trait>::Accounting as Posting<<T as system::Trait>::AccountId,<T as system::Trait>::Hash,<T as system::Trait>::BlockNumber>>::Account;
+type AccountBalanceOf<T> = <<T as Trait>::Accounting as Posting<<T as system::Trait>::AccountId,<T as system::Trait>::Hash,<T as system::Trait>::BlockNumber>>::LedgerBalance;
+
+// Other trait types
+type FeeImbalanceOf<T> = balances::NegativeImbalance<T>;
+
+pub trait Trait: balances::Trait + session::Trait + aura::Trait {
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    type Treasury: OnUnbalanced<FeeImbalanceOf<Self>>;
+    type Conversions: 
+    Convert<u64, AccountOf<Self>> + 
+    Convert<i128, AccountBalanceOf<Self>> + 
+    Convert<Self::Balance, u128> + 
+    Convert<u128, i128>;
+    type Accounting: Posting<Self::AccountId,Self::Hash,Self::BlockNumber>;
+}
+
+pub type Share = u16; // parts per ten thousand of the fee
+
+const SHARE_DENOMINATOR: u64 = 10000;
+
+/// Author share when none has been set
+const DEFAULT_AUTHOR_SHARE: Share = 2000;
+
+// Ledger accounts
+const XTX_BALANCE: u64 = 110100040000000; // 110100040000000 XTX Balance
+const NETWORK_FEE_INCOME: u64 = 240800010000000; // 240800010000000 Network fee income
+const NETWORK_FEES: u64 = 250800010000000; // 250800010000000 Network transaction fees
+
+decl_storage! {
+    trait Store for Module<T: Trait> as FeesModule {
+        // Parts per ten thousand of each fee paid to the block author, the rest goes to the treasury
+        AuthorShare get(author_share): Option<Share>;
+        // Fees paid in the current block awaiting posting: payer, fee, and the block author with its share
+        PendingFees get(pending_fees): map u32 => Option<(T::AccountId, T::Balance, Option<(T::AccountId, T::Balance)>)>;
+        PendingFeeCount get(pending_fee_count): u32;
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+        
+        /// Sets the share of the fees paid to the block author
+        #[weight = TransactionWeight::Basic(SETTING, 1)]
+        fn set_author_share(origin, share: Share) -> Result {
+            ensure_root(origin)?;
+            ensure!(share as u64 <= SHARE_DENOMINATOR, "Author share cannot exceed the fee");
+            <AuthorShare<T>>::put(share);
+            
+            Self::deposit_event(RawEvent::AuthorShareSet(share));
+            Ok(())
+        }
+        
+        fn on_finalize(_n: T::BlockNumber) {
+            Self::post_pending_fees();
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// The validator owning the Aura slot of the current block
+    fn block_author() -> Option<T::AccountId> {
+        let validators = <session::Module<T>>::validators();
+        let slot_duration = <aura::Module<T>>::slot_duration();
+        if validators.is_empty() || slot_duration == 0 {
+            return None;
+        }
+        let slot = <timestamp::Module<T>>::now().as_() / slot_duration;
+        validators.get((slot % validators.len() as u64) as usize).cloned()
+    }
+    
+    /// The block author and its share of the fee
+    fn author_part(fee: T::Balance) -> Option<(T::AccountId, T::Balance)> {
+        let share = Self::author_share().unwrap_or(DEFAULT_AUTHOR_SHARE) as u64;
+        Self::block_author().map(|a| {
+            (a, fee * <T::Balance as As<u64>>::sa(share) / <T::Balance as As<u64>>::sa(SHARE_DENOMINATOR))
+        })
+    }
+    
+    /// Pays the author part of the fee to the author and the rest to the treasury
+    fn pay_out(fee: FeeImbalanceOf<T>, author: &Option<(T::AccountId, T::Balance)>) {
+        match author {
+            Some((author, part)) if !part.is_zero() => {
+                let (to_author, to_treasury) = fee.split(*part);
+                <balances::Module<T> as Currency<T::AccountId>>::resolve_creating(author, to_author);
+                T::Treasury::on_unbalanced(to_treasury);
+            },
+            _ => T::Treasury::on_unbalanced(fee),
+        }
+    }
+    
+    /// Posts the fees paid in this block. A fee that fails to post stays paid.
+    fn post_pending_fees() {
+        for i in 0..<PendingFeeCount<T>>::take() {
+            if let Some((payer, fee, author)) = <PendingFees<T>>::take(i) {
+                // the error is reported by an event
+                let _ = Self::post_fee(&payer, &author, fee);
+                Self::deposit_event(RawEvent::FeePaid(payer, Self::to_ledger(fee)));
+                if let Some((author, part)) = author {
+                    if !part.is_zero() {
+                        Self::deposit_event(RawEvent::AuthorPaid(author, Self::to_ledger(part)));
+                    }
+                }
+            }
+        }
+    }
+    
+    fn to_ledger(amount: T::Balance) -> i128 {
+        let unsigned: u128 = <T::Conversions as Convert<T::Balance, u128>>::convert(amount);
+        <T::Conversions as Convert<u128, i128>>::convert(unsigned)
+    }
+    
+    /// Posts the fee.
+    /// Payer: Debit increase Network transaction fees, Credit decrease XTX Balance
+    /// Author: Debit increase XTX Balance, Credit increase Network fee income
+    fn post_fee(payer: &T::AccountId, author: &Option<(T::AccountId, T::Balance)>, fee: T::Balance) -> Result {
+        let recipient = author.as_ref().map(|(a, _)| a.clone()).unwrap_or_else(|| payer.clone());
+        let h = <<T as Trait>::Accounting as Posting<T::AccountId,T::Hash,T::BlockNumber>>::get_pseudo_random_hash(payer.clone(), recipient);
+        let fee = Self::to_ledger(fee);
+        
+        let mut entries: Vec<(T::AccountId, u64, i128, bool)> = Vec::new();
+        entries.push((payer.clone(), NETWORK_FEES, fee, true));
+        entries.push((payer.clone(), XTX_BALANCE, -fee, false));
+        if let Some((a, share)) = author {
+            let share = Self::to_ledger(*share);
+            if share > 0 {
+                entries.push((a.clone(), XTX_BALANCE, share, true));
+                entries.push((a.clone(), NETWORK_FEE_INCOME, share, false));
+            }
+        }
+        Self::post(h, entries)
+    }
+    
+    /// Posts the entries (identity, account, amount, debit). Every entry but the last gets a reversal.
+    fn post(h: T::Hash, entries: Vec<(T::AccountId, u64, i128, bool)>) -> Result {
+        let current_block = <system::Module<T>>::block_number();
+        let current_block_dupe = <system::Module<T>>::block_number();
+        
+        let mut forward_keys = Vec::<(T::AccountId, AccountOf<T>, AccountBalanceOf<T>, bool, T::Hash, T::BlockNumber, T::BlockNumber)>::with_capacity(entries.len());
+        let mut reversal_keys = Vec::<(T::AccountId, AccountOf<T>, AccountBalanceOf<T>, bool, T::Hash, T::BlockNumber, T::BlockNumber)>::with_capacity(entries.len());
+        for (o, a, amount, debit) in entries {
+            let account: AccountOf<T> = <T::Conversions as Convert<u64, AccountOf<T>>>::convert(a);
+            let forward: AccountBalanceOf<T> = <T::Conversions as Convert<i128, AccountBalanceOf<T>>>::convert(amount);
+            let reverse: AccountBalanceOf<T> = <T::Conversions as Convert<i128, AccountBalanceOf<T>>>::convert(-amount);
+            forward_keys.push((o.clone(), account, forward, debit, h, current_block, current_block_dupe));
+            reversal_keys.push((o, account, reverse, !debit, h, current_block, current_block_dupe));
+        }
+        reversal_keys.pop();
+        
+        let track_rev_keys = Vec::<(T::AccountId, AccountOf<T>, AccountBalanceOf<T>, bool, T::Hash, T::BlockNumber, T::BlockNumber)>::new();
+        
+        match <<T as Trait>::Accounting as Posting<T::AccountId,T::Hash,T::BlockNumber>>::handle_multiposting_amounts(forward_keys, reversal_keys, track_rev_keys) {
+            Ok(_) => Ok(()),
+            Err(_e) => {
+                Self::deposit_event(RawEvent::ErrorInAccounting(h));
+                Err("There was an error posting to accounts")
+            },
+        }
+    }
+}
+
+impl<T: Trait> OnUnbalanced<FeeImbalanceOf<T>> for Module<T> {
+    /// Splits the fee between the block author and the treasury, without posting
+    fn on_unbalanced(fee: FeeImbalanceOf<T>) {
+        let author = Self::author_part(fee.peek());
+        Self::pay_out(fee, &author);
+    }
+}
+
+impl<T: Trait> MakePayment<T::AccountId> for Module<T> {
+    fn make_payment(transactor: &T::AccountId, encoded_len: usize, weight: Weight) -> Result {
+        let encoded_len = <T::Balance as As<u64>>::sa(encoded_len as u64);
+        let weight = <T::Balance as As<u64>>::sa(weight as u64);
+        let fee = <balances::Module<T>>::transaction_base_fee() 
+            + <balances::Module<T>>::transaction_byte_fee() * encoded_len 
+            + <balances::Module<T>>::transaction_weight_fee() * weight;
+        let imbalance = <balances::Module<T> as Currency<T::AccountId>>::withdraw(
+            transactor,
+            fee,
+            WithdrawReason::TransactionPayment,
+            ExistenceRequirement::KeepAlive,
+        )?;
+        
+        let author = Self::author_part(fee);
+        Self::pay_out(imbalance, &author);
+        
+        if !fee.is_zero() {
+            let count = Self::pending_fee_count();
+            <PendingFees<T>>::insert(count, (transactor.clone(), fee, author));
+            <PendingFeeCount<T>>::put(count + 1);
+        }
+        Ok(())
+    }
+}
+
+decl_event!(
+    pub enum Event<T>
+    where
+        AccountId = <T as system::Trait>::AccountId,
+        Hash = <T as system::Trait>::Hash,
+    {
+        /// Payer, fee
+        FeePaid(AccountId, i128),
+        /// Block author, share of a fee
+        AuthorPaid(AccountId, i128),
+        /// Author share in parts per ten thousand
+        AuthorShareSet(u16),
+        ErrorInAccounting(Hash),
+    }
+);
@@ -0,0 +1,363 @@
+//!                              Næ§@@@ÑÉ©
+//!                        æ@@@@@@@@@@@@@@@@@@
+//!                    Ñ@@@@?.?@@@@@@@@@@@@@@@@@@@N
+//!                 ¶@@@@@?^%@@.=@@@@@@@@@@@@@@@@@@@@
+//!               N@@@@@@@?^@@@»^@@@@@@@@@@@@@@@@@@@@@@
+//!               @@@@@@@@?^@@@».............?@@@@@@@@@É
+//!              Ñ@@@@@@@@?^@@@@@@@@@@@@@@@@@@'?@@@@@@@@Ñ
+//!              @@@@@@@@@?^@@@»..............»@@@@@@@@@@
+//!              @@@@@@@@@?^@@@»^@@@@@@@@@@@@@@@@@@@@@@@@
+//!              @@@@@@@@@?^ë@@&.@@@@@@@@@@@@@@@@@@@@@@@@
+//!               @@@@@@@@?^´@@@o.%@@@@@@@@@@@@@@@@@@@@©
+//!                @@@@@@@?.´@@@@@ë.........*.±@@@@@@@æ
+//!                 @@@@@@@@?´.I@@@@@@@@@@@@@@.&@@@@@N
+//!                  N@@@@@@@@@@ë.*=????????=?@@@@@Ñ
+//!                    @@@@@@@@@@@@@@@@@@@@@@@@@@@¶
+//!                        É@@@@@@@@@@@@@@@@Ñ¶
+//!                             Næ§@@@ÑÉ©
+
+//! Copyright 2020 Chris D'Costa
+//! This file is part of Totem Live Accounting.
+//! Author Chris D'Costa email: chris.dcosta@totemaccounting.com
+
+//! Totem is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License, or
+//! (at your option) any later version.
+
+//! Totem is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+
+//! You should have received a copy of the GNU General Public License
+//! along with Totem.  If not, see <http://www.gnu.org/licenses/>.
+
+
+/// Fixed asset register.
+/// 
+/// Each identity keeps a register of its fixed assets with the acquisition cost, residual value, useful life 
+/// and depreciation method: straight-line(0) or declining balance(1). The useful life is given in blocks or in 
+/// accounting periods. Declining balance depreciates at twice the straight-line rate once per full period.
+/// 
+/// Depreciation is not calculated every block. The register remembers the block up to which an asset was depreciated 
+/// and posts the difference the next time the owner interacts with the asset, or when the owner closes the period.
+/// Closing the period works through the register in batches, so a large register takes several calls to close.
+/// Disposal depreciates the asset up to the current block and posts the gain or loss against the net book value.
+
+use parity_codec::{Decode, Encode};
+use support::{decl_event, decl_module, decl_storage, dispatch::Result, ensure, StorageMap, StorageValue};
+use support::dispatch::TransactionWeight;
+use system::{self, ensure_signed, ensure_root};
+use rstd::prelude::*;
+use runtime_primitives::traits::{As, Convert, Zero};
+use runtime_primitives::weights::Weight;
+
+// Totem Traits
+use crate::accounting_traits::{ Posting };
+use crate::weights::{ LIST, POSTING, READ, SETTING, WRITE };
+
+// Totem Trait Types
+type AccountOf<T> = <<T as Trait>::Accounting as Posting<<T as system::Trait>::AccountId,<T as system::Trait>::Hash,<T as system::Trait>::BlockNumber>>::Account;
+type AccountBalanceOf<T> = <<T as Trait>::Accounting as Posting<<T as system::Trait>::AccountId,<T as system::Trait>::Hash,<T as system::Trait>::BlockNumber>>::LedgerBalance;
+
+pub trait Trait: system::Trait {
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    type Conversions: 
+    Convert<u64, AccountOf<Self>> + 
+    Convert<i128, AccountBalanceOf<Self>>;
+    type Accounting: Posting<Self::AccountId,Self::Hash,Self::BlockNumber>;
+}
+
+pub type DepreciationMethod = u8; // straight-line(0), declining balance(1)
+pub type LifeUnit = u8; // blocks(0), periods(1)
+
+/// Length of an accounting period when none has been set (about 30 days)
+const DEFAULT_PERIOD_LENGTH: u64 = 432000;
+/// Maximum number of periods in a declining balance useful life. Bounds the depreciation calculation.
+const MAX_PERIODS: u64 = 1200;
+/// Assets depreciated by a single call of `close_period`, which is weighed for that many
+const ASSETS_PER_CALL: Weight = 10;
+
+// Ledger accounts
+const PROPERTY_PLANT_EQUIPMENT: u64 = 110200010000000; // 110200010000000 Property, plant and equipment
+const ACCUMULATED_DEPRECIATION: u64 = 110200020000000; // 110200020000000 Accumulated depreciation
+const FIXED_ASSET_CLEARING: u64 = 120200050000000; // 120200050000000 Fixed asset clearing
+const GAIN_ON_DISPOSAL: u64 = 240600010000000; // 240600010000000 Gain on disposal of fixed assets
+const DEPRECIATION: u64 = 250600010000000; // 250600010000000 Depreciation
+const LOSS_ON_DISPOSAL: u64 = 250600020000000; // 250600020000000 Loss on disposal of fixed assets
+
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct FixedAsset<AccountId, BlockNumber> {
+    pub owner: AccountId,
+    pub cost: i128,
+    pub residual_value: i128,
+    pub useful_life: BlockNumber, // in blocks
+    pub method: DepreciationMethod,
+    pub acquired: BlockNumber,
+    pub depreciated_to: BlockNumber, // last seen block
+    pub accumulated: i128, // accumulated depreciation
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as FixedAssetsModule {
+        Assets get(asset): map T::Hash => Option<FixedAsset<T::AccountId, T::BlockNumber>>;
+        // Assets in use by an identity
+        OwnerAssets get(owner_assets): map T::AccountId => Vec<T::Hash>;
+        // Disposed assets: block, proceeds, gain (positive) or loss (negative)
+        Disposals get(disposal): map T::Hash => Option<(T::BlockNumber, i128, i128)>;
+        // Position in the register of an identity up to which the current period has been closed
+        PeriodCursor get(period_cursor): map T::AccountId => u32;
+        // Number of blocks in an accounting period
+        PeriodLength get(period_length): Option<T::BlockNumber>;
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+        
+        /// Adds an asset to the register of the sender. The acquisition is posted to property, plant and equipment 
+        /// against the fixed asset clearing account, which is cleared when the asset is paid for.
+        #[weight = TransactionWeight::Basic(2 * READ + 2 * WRITE + LIST + 2 * POSTING, 1)]
+        fn register_asset(
+            origin, 
+            cost: i128, 
+            residual_value: i128, 
+            useful_life: u64, 
+            life_unit: LifeUnit, 
+            method: DepreciationMethod
+        ) -> Result {
+            let who = ensure_signed(origin)?;
+            ensure!(cost > 0, "Cost must be greater than zero");
+            ensure!(residual_value >= 0 && residual_value <= cost, "Residual value must be between zero and the cost");
+            ensure!(useful_life > 0, "Useful life must be greater than zero");
+            ensure!(method <= 1, "Unknown depreciation method");
+            
+            let period: u64 = Self::period().as_();
+            let life: u64 = match life_unit {
+                0 => useful_life,
+                1 => useful_life.checked_mul(period).ok_or("Useful life too long")?,
+                _ => return Err("Unknown useful life unit"),
+            };
+            if method == 1 {
+                ensure!(life / period <= MAX_PERIODS, "Useful life has too many periods for declining balance");
+            }
+            
+            let now = <system::Module<T>>::block_number();
+            let asset_hash: T::Hash = <<T as Trait>::Accounting as Posting<T::AccountId,T::Hash,T::BlockNumber>>::get_pseudo_random_hash(who.clone(), who.clone());
+            ensure!(!<Assets<T>>::exists(&asset_hash), "The hash already exists! Try again.");
+            
+            let mut entries: Vec<(u64, i128, bool)> = Vec::new();
+            entries.push((PROPERTY_PLANT_EQUIPMENT, cost, true)); // Debit  increase
+            entries.push((FIXED_ASSET_CLEARING, cost, false)); // Credit increase
+            Self::post(who.clone(), asset_hash, entries)?;
+            
+            let asset = FixedAsset {
+                owner: who.clone(),
+                cost: cost,
+                residual_value: residual_value,
+                useful_life: T::BlockNumber::sa(life),
+                method: method,
+                acquired: now,
+                depreciated_to: now,
+                accumulated: 0,
+            };
+            <Assets<T>>::insert(&asset_hash, asset);
+            <OwnerAssets<T>>::mutate(&who, |owner_assets| owner_assets.push(asset_hash.clone()));
+            
+            Self::deposit_event(RawEvent::AssetRegistered(asset_hash, who, cost));
+            Ok(())
+        }
+        
+        /// Posts the depreciation of an asset up to the current block
+        #[weight = TransactionWeight::Basic(2 * READ + WRITE + 2 * POSTING, 1)]
+        fn depreciate(origin, asset_hash: T::Hash) -> Result {
+            let who = ensure_signed(origin)?;
+            let asset = Self::asset(&asset_hash).ok_or("Asset does not exist")?;
+            ensure!(asset.owner == who, "Not the owner of the asset");
+            Self::depreciate_to_now(asset_hash, asset)?;
+            Ok(())
+        }
+        
+        /// Posts the depreciation of the next batch of assets of the sender up to the current block. 
+        /// Each call continues where the last one stopped, until the whole register has been depreciated.
+        #[weight = TransactionWeight::Basic(2 * READ + WRITE + ASSETS_PER_CALL * (2 * READ + WRITE + 2 * POSTING), 1)]
+        fn close_period(origin) -> Result {
+            let who = ensure_signed(origin)?;
+            let owner_assets = Self::owner_assets(&who);
+            let start = rstd::cmp::min(Self::period_cursor(&who) as usize, owner_assets.len());
+            let end = rstd::cmp::min(start + ASSETS_PER_CALL as usize, owner_assets.len());
+            for asset_hash in owner_assets[start..end].iter() {
+                if let Some(asset) = Self::asset(asset_hash) {
+                    Self::depreciate_to_now(asset_hash.clone(), asset)?;
+                }
+            }
+            
+            if end < owner_assets.len() {
+                <PeriodCursor<T>>::insert(&who, end as u32);
+                Self::deposit_event(RawEvent::PeriodPartlyClosed(who, end as u32));
+            } else {
+                <PeriodCursor<T>>::remove(&who);
+                Self::deposit_event(RawEvent::PeriodClosed(who, <system::Module<T>>::block_number()));
+            }
+            Ok(())
+        }
+        
+        /// Removes an asset from the register. The proceeds are posted to the fixed asset clearing account 
+        /// and the difference to the net book value as a gain or loss on disposal.
+        #[weight = TransactionWeight::Basic(3 * READ + 4 * WRITE + LIST + 6 * POSTING, 1)]
+        fn dispose_asset(origin, asset_hash: T::Hash, proceeds: i128) -> Result {
+            let who = ensure_signed(origin)?;
+            ensure!(proceeds >= 0, "Proceeds cannot be negative");
+            let asset = Self::asset(&asset_hash).ok_or("Asset does not exist")?;
+            ensure!(asset.owner == who, "Not the owner of the asset");
+            
+            let asset = Self::depreciate_to_now(asset_hash.clone(), asset)?;
+            let book_value: i128 = asset.cost - asset.accumulated;
+            let gain: i128 = proceeds.checked_sub(book_value).ok_or("Gain or loss overflow")?;
+            
+            let mut entries: Vec<(u64, i128, bool)> = Vec::new();
+            entries.push((ACCUMULATED_DEPRECIATION, -asset.accumulated, true)); // Debit  decrease
+            entries.push((FIXED_ASSET_CLEARING, -proceeds, true)); // Debit  decrease
+            if gain < 0 {
+                entries.push((LOSS_ON_DISPOSAL, -gain, true)); // Debit  increase
+            } else {
+                entries.push((GAIN_ON_DISPOSAL, gain, false)); // Credit increase
+            }
+            entries.push((PROPERTY_PLANT_EQUIPMENT, -asset.cost, false)); // Credit decrease
+            entries.retain(|e| e.1 != 0);
+            Self::post(who.clone(), asset_hash.clone(), entries)?;
+            
+            <Assets<T>>::remove(&asset_hash);
+            let mut owner_assets = Self::owner_assets(&who);
+            if let Some(position) = owner_assets.iter().position(|h| h == &asset_hash) {
+                owner_assets.remove(position);
+                // keep a period that is being closed at the same asset
+                if (position as u32) < Self::period_cursor(&who) {
+                    <PeriodCursor<T>>::mutate(&who, |cursor| *cursor -= 1);
+                }
+            }
+            <OwnerAssets<T>>::insert(&who, owner_assets);
+            <Disposals<T>>::insert(&asset_hash, (<system::Module<T>>::block_number(), proceeds, gain));
+            
+            Self::deposit_event(RawEvent::AssetDisposed(asset_hash, who, proceeds, gain));
+            Ok(())
+        }
+        
+        /// Sets the number of blocks in an accounting period
+        #[weight = TransactionWeight::Basic(SETTING, 1)]
+        fn set_period_length(origin, blocks: T::BlockNumber) -> Result {
+            ensure_root(origin)?;
+            ensure!(blocks > T::BlockNumber::zero(), "Period must be at least one block");
+            <PeriodLength<T>>::put(blocks);
+            Ok(())
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    fn period() -> T::BlockNumber {
+        Self::period_length().unwrap_or_else(|| T::BlockNumber::sa(DEFAULT_PERIOD_LENGTH))
+    }
+    
+    /// Accumulated depreciation of the asset at the given block
+    fn accumulated_at(asset: &FixedAsset<T::AccountId, T::BlockNumber>, now: T::BlockNumber) -> rstd::result::Result<i128, &'static str> {
+        let life: u64 = asset.useful_life.as_();
+        let elapsed: u64 = rstd::cmp::min((now - asset.acquired).as_(), life);
+        let depreciable: i128 = asset.cost - asset.residual_value;
+        if elapsed == life {
+            return Ok(depreciable);
+        }
+        
+        match asset.method {
+            0 => Ok(depreciable.checked_mul(elapsed as i128).ok_or("Depreciation overflow")? / life as i128),
+            _ => {
+                // Twice the straight-line rate, applied to the book value once per full period
+                let period: u64 = Self::period().as_();
+                let periods: u64 = rstd::cmp::min(elapsed / period, MAX_PERIODS);
+                let mut book_value: i128 = asset.cost;
+                for _ in 0..periods {
+                    let charge: i128 = book_value.checked_mul(2 * period as i128).ok_or("Depreciation overflow")? / life as i128;
+                    book_value = rstd::cmp::max(book_value - charge, asset.residual_value);
+                }
+                Ok(asset.cost - book_value)
+            },
+        }
+    }
+    
+    /// Posts the depreciation since the asset was last seen and returns the updated asset
+    fn depreciate_to_now(
+        asset_hash: T::Hash, 
+        mut asset: FixedAsset<T::AccountId, T::BlockNumber>
+    ) -> rstd::result::Result<FixedAsset<T::AccountId, T::BlockNumber>, &'static str> {
+        let now = <system::Module<T>>::block_number();
+        if asset.depreciated_to == now {
+            return Ok(asset);
+        }
+        
+        let accumulated = Self::accumulated_at(&asset, now)?;
+        let charge: i128 = accumulated - asset.accumulated;
+        if charge > 0 {
+            let mut entries: Vec<(u64, i128, bool)> = Vec::new();
+            entries.push((DEPRECIATION, charge, true)); // Debit  increase
+            entries.push((ACCUMULATED_DEPRECIATION, charge, false)); // Credit increase
+            Self::post(asset.owner.clone(), asset_hash.clone(), entries)?;
+            Self::deposit_event(RawEvent::AssetDepreciated(asset_hash.clone(), charge, accumulated));
+        }
+        
+        asset.accumulated = accumulated;
+        asset.depreciated_to = now;
+        <Assets<T>>::insert(&asset_hash, asset.clone());
+        
+        Ok(asset)
+    }
+    
+    /// Posts the entries (account, amount, debit) for the owner. Every entry but the last gets a reversal.
+    fn post(o: T::AccountId, h: T::Hash, entries: Vec<(u64, i128, bool)>) -> Result {
+        let current_block = <system::Module<T>>::block_number();
+        let current_block_dupe = <system::Module<T>>::block_number();
+        
+        let mut forward_keys = Vec::<(T::AccountId, AccountOf<T>, AccountBalanceOf<T>, bool, T::Hash, T::BlockNumber, T::BlockNumber)>::with_capacity(entries.len());
+        let mut reversal_keys = Vec::<(T::AccountId, AccountOf<T>, AccountBalanceOf<T>, bool, T::Hash, T::BlockNumber, T::BlockNumber)>::with_capacity(entries.len());
+        for (a, amount, debit) in entries {
+            let account: AccountOf<T> = <T::Conversions as Convert<u64, AccountOf<T>>>::convert(a);
+            let forward: AccountBalanceOf<T> = <T::Conversions as Convert<i128, AccountBalanceOf<T>>>::convert(amount);
+            let reverse: AccountBalanceOf<T> = <T::Conversions as Convert<i128, AccountBalanceOf<T>>>::convert(-amount);
+            forward_keys.push((o.clone(), account, forward, debit, h, current_block, current_block_dupe));
+            reversal_keys.push((o.clone(), account, reverse, !debit, h, current_block, current_block_dupe));
+        }
+        reversal_keys.pop();
+        
+        let track_rev_keys = Vec::<(T::AccountId, AccountOf<T>, AccountBalanceOf<T>, bool, T::Hash, T::BlockNumber, T::BlockNumber)>::new();
+        
+        match <<T as Trait>::Accounting as Posting<T::AccountId,T::Hash,T::BlockNumber>>::handle_multiposting_amounts(forward_keys, reversal_keys, track_rev_keys) {
+            Ok(_) => Ok(()),
+            Err(_e) => {
+                Self::deposit_event(RawEvent::ErrorInAccounting(h));
+                Err("There was an error posting to accounts")
+            },
+        }
+    }
+}
+
+decl_event!(
+    pub enum Event<T>
+    where
+        AccountId = <T as system::Trait>::AccountId,
+        Hash = <T as system::Trait>::Hash,
+        BlockNumber = <T as system::Trait>::BlockNumber,
+    {
+        /// Asset, owner, cost
+        AssetRegistered(Hash, AccountId, i128),
+        /// Asset, depreciation charge, accumulated depreciation
+        AssetDepreciated(Hash, i128, i128),
+        /// Asset, owner, proceeds, gain (positive) or loss (negative)
+        AssetDisposed(Hash, AccountId, i128, i128),
+        PeriodClosed(AccountId, BlockNumber),
+        /// Owner, number of assets depreciated so far. Call `close_period` again to continue.
+        PeriodPartlyClosed(AccountId, u32),
+        ErrorInAccounting(Hash),
+    }
+);
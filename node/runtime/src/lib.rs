@@ -71,6 +71,8 @@ pub use balances::Call as BalancesCall;
 pub use runtime_primitives::{Permill, Perbill};
 pub use support::StorageValue;
 pub use staking::StakerStatus;
+pub use accounting::RawEvent as AccountingEvent;
+pub use fees::RawEvent as FeesEvent;
 
 extern crate sodalite;
 
@@ -79,17 +81,28 @@ extern crate sodalite;
 mod accounting;
 mod accounting_traits;
 mod archive;
+mod archive_traits;
 mod bonsai;
 mod bonsai_traits;
 mod boxkeys;
+mod boxkeys_traits;
+mod fees;
+mod fixed_assets;
+mod marketplace;
+mod messaging;
 mod orders;
 mod orders_traits;
+mod payroll;
+mod payroll_traits;
 mod prefunding;
 mod prefunding_traits;
 mod projects;
 mod projects_traits;
+mod reputation;
+mod reputation_traits;
 mod timekeeping;
 mod timekeeping_traits;
+mod weights;
 
 /// This is the Totem runtime version.
 pub const VERSION: RuntimeVersion = RuntimeVersion {
@@ -100,7 +113,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// for block authoring // fork risk, on change
 	authoring_version: 1,
 	// spec version // fork risk, on change
-	spec_version: 10,
+	spec_version: 11,
     // incremental changes
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
@@ -215,7 +228,7 @@ impl balances::Trait for Runtime {
 	type OnFreeBalanceZero = ((Staking, Contract), Session);
 	type OnNewAccount = Indices;
 	type Event = Event;
-	type TransactionPayment = ();
+	type TransactionPayment = FeesModule;
 	type DustRemoval = ();
 	type TransferPayment = ();
 }
@@ -309,17 +322,28 @@ impl finality_tracker::Trait for Runtime {
 // Totem impl
 impl projects::Trait for Runtime {
 	type Event = Event;
+	type Archive = ArchiveModule;
+	type Timekeeping = TimekeepingModule;
 }
 
 impl timekeeping::Trait for Runtime {
 	type Event = Event;
 	type Projects = ProjectModule;
+	type Archive = ArchiveModule;
+	type Payroll = PayrollModule;
 }
 
 impl boxkeys::Trait for Runtime {
 	type Event = Event;
 }
 
+impl messaging::Trait for Runtime {
+	type Event = Event;
+	type BoxKeys = BoxKeyS;
+	type Orders = OrdersModule;
+	type Projects = ProjectModule;
+}
+
 impl bonsai::Trait for Runtime {
 	type Event = Event;
 	type Orders = OrdersModule;
@@ -330,7 +354,10 @@ impl bonsai::Trait for Runtime {
 
 impl archive::Trait for Runtime {
 	type Event = Event;
+	type Projects = ProjectModule;
 	type Timekeeping = TimekeepingModule;
+	type Orders = OrdersModule;
+	type Prefunding = PrefundingModule;
 }
 
 impl accounting::Trait for Runtime {
@@ -342,6 +369,7 @@ impl prefunding::Trait for Runtime {
     type Currency = balances::Module<Self>;
     type Conversions = ConversionHandler;
     type Accounting = AccountingModule;
+    type OnClose = MarketplaceModule;
 }
 
 impl orders::Trait for Runtime {
@@ -350,6 +378,38 @@ impl orders::Trait for Runtime {
     type Accounting = AccountingModule;
     type Prefunding = PrefundingModule;
     type Bonsai = BonsaiModule;
+    type Archive = ArchiveModule;
+    type Reputation = ReputationModule;
+    type Quantities = AccountingModule;
+}
+
+impl fixed_assets::Trait for Runtime {
+    type Event = Event;
+    type Conversions = ConversionHandler;
+    type Accounting = AccountingModule;
+}
+
+impl payroll::Trait for Runtime {
+    type Event = Event;
+    type Currency = balances::Module<Self>;
+    type Conversions = ConversionHandler;
+    type Accounting = AccountingModule;
+}
+
+impl fees::Trait for Runtime {
+    type Event = Event;
+    type Treasury = Treasury;
+    type Conversions = ConversionHandler;
+    type Accounting = AccountingModule;
+}
+
+impl reputation::Trait for Runtime {
+    type Event = Event;
+}
+
+impl marketplace::Trait for Runtime {
+    type Event = Event;
+    type Orders = OrdersModule;
 }
 
 construct_runtime!(
@@ -379,11 +439,17 @@ construct_runtime!(
 		ProjectModule: projects::{Module, Call, Storage, Event<T>},
 		TimekeepingModule: timekeeping::{Module, Call, Storage, Event<T>},
 		BoxKeyS: boxkeys::{Module, Call, Storage, Event<T>},
+		MessagingModule: messaging::{Module, Call, Storage, Event<T>},
 		BonsaiModule: bonsai::{Module, Call, Storage, Event<T>},
-		ArchiveModule: archive::{Module, Call, Event<T>},
+		ArchiveModule: archive::{Module, Call, Storage, Event<T>},
 		AccountingModule: accounting::{Module, Storage, Event<T>},
 		OrdersModule: orders::{Module, Call, Storage, Event<T>},
         PrefundingModule: prefunding::{Module, Call, Storage, Event<T>},
+        ReputationModule: reputation::{Module, Call, Storage, Event<T>},
+        MarketplaceModule: marketplace::{Module, Call, Storage, Event<T>},
+        FixedAssetsModule: fixed_assets::{Module, Call, Storage, Event<T>},
+        PayrollModule: payroll::{Module, Call, Storage, Event<T>},
+        FeesModule: fees::{Module, Call, Storage, Event<T>},
 	}
 );
 
@@ -402,7 +468,7 @@ pub type UncheckedExtrinsic = generic::UncheckedMortalCompactExtrinsic<Address,
 /// Extrinsic type that has already been checked.
 pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Index, Call>;
 /// Executive: handles dispatch to the various modules.
-pub type Executive = executive::Executive<Runtime, Block, system::ChainContext<Runtime>, Balances, AllModules>;
+pub type Executive = executive::Executive<Runtime, Block, system::ChainContext<Runtime>, FeesModule, AllModules>;
 
 impl_runtime_apis! {
 	impl client_api::Core<Block> for Runtime {
@@ -463,6 +529,40 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl totem_primitives::TotemApi<Block, AccountId, Hash> for Runtime {
+		fn orders_by_party(
+			who: AccountId,
+			party: totem_primitives::OrderParty,
+			status: Option<u16>,
+		) -> Vec<totem_primitives::OrderDetails<AccountId, Hash>> {
+			OrdersModule::orders_by_party(who, party, status)
+		}
+
+		fn order(order_hash: Hash) -> Option<totem_primitives::OrderDetails<AccountId, Hash>> {
+			OrdersModule::order_details(order_hash)
+		}
+
+		fn project(project_hash: Hash) -> Option<totem_primitives::ProjectDetails<AccountId, Hash>> {
+			ProjectModule::project_summary(project_hash).map(|(owner, status, members)| {
+				TimekeepingModule::project_details(project_hash, owner, status, members)
+			})
+		}
+
+		fn timesheet(
+			worker: AccountId,
+			from: u64,
+			to: u64,
+		) -> Vec<totem_primitives::TimeRecordDetails<AccountId, Hash>> {
+			TimekeepingModule::timesheet(worker, from, to)
+		}
+	}
+
+	impl totem_primitives::ReputationApi<Block, AccountId> for Runtime {
+		fn reputation(who: AccountId) -> totem_primitives::ReputationScores {
+			ReputationModule::scores(who)
+		}
+	}
+
 	impl fg_primitives::GrandpaApi<Block> for Runtime {
 		fn grandpa_pending_change(digest: &DigestFor<Block>)
 			-> Option<ScheduledChange<NumberFor<Block>>>
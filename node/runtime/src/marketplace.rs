@@ -0,0 +1,238 @@
+//!                              Næ§@@@ÑÉ©
+//!                        æ@@@@@@@@@@@@@@@@@@
+//!                    Ñ@@@@?.?@@@@@@@@@@@@@@@@@@@N
+//!                 ¶@@@@@?^%@@.=@@@@@@@@@@@@@@@@@@@@
+//!               N@@@@@@@?^@@@»^@@@@@@@@@@@@@@@@@@@@@@
+//!               @@@@@@@@?^@@@».............?@@@@@@@@@É
+//!              Ñ@@@@@@@@?^@@@@@@@@@@@@@@@@@@'?@@@@@@@@Ñ
+//!              @@@@@@@@@?^@@@»..............»@@@@@@@@@@
+//!              @@@@@@@@@?^@@@»^@@@@@@@@@@@@@@@@@@@@@@@@
+//!              @@@@@@@@@?^ë@@&.@@@@@@@@@@@@@@@@@@@@@@@@
+//!               @@@@@@@@?^´@@@o.%@@@@@@@@@@@@@@@@@@@@©
+//!                @@@@@@@?.´@@@@@ë.........*.±@@@@@@@æ
+//!                 @@@@@@@@?´.I@@@@@@@@@@@@@@.&@@@@@N
+//!                  N@@@@@@@@@@ë.*=????????=?@@@@@Ñ
+//!                    @@@@@@@@@@@@@@@@@@@@@@@@@@@¶
+//!                        É@@@@@@@@@@@@@@@@Ñ¶
+//!                             Næ§@@@ÑÉ©
+
+//! Copyright 2020 Chris D'Costa
+//! This file is part of Totem Live Accounting.
+//! Author Chris D'Costa email: chris.dcosta@totemaccounting.com
+
+//! Totem is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License, or
+//! (at your option) any later version.
+
+//! Totem is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+
+//! You should have received a copy of the GNU General Public License
+//! along with Totem.  If not, see <http://www.gnu.org/licenses/>.
+
+/// A marketplace for goods and services offered by Totem identities.
+/// 
+/// Sellers post listings with a unit price in the functional currency and an available quantity.
+/// A purchase creates a Totem order from the buyer (commander) to the seller (fulfiller), approved by the buyer,
+/// and locks the order amount in prefunding. From then on the order follows the usual order workflow:
+/// the seller accepts and invoices the order, and the buyer settles the invoice.
+/// If the seller rejects the order, or the buyer cancels the prefunding after the deadline, the quantity is returned to the listing.
+/// Once the invoice is settled both parties can rate each other through the reputation module.
+
+use support::{decl_event, decl_module, decl_storage, dispatch::Result, ensure, StorageMap, StorageValue};
+use support::dispatch::TransactionWeight;
+use system::{self, ensure_signed};
+use parity_codec::{Decode, Encode};
+use rstd::prelude::*;
+
+// Totem crates
+use crate::orders_traits::{ Ordering };
+use crate::prefunding_traits::{ Closing };
+use crate::weights::{ LIST, ORDER, PREFUNDING, READ, WRITE };
+
+pub trait Trait: system::Trait {
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    type Orders: Ordering<Self::AccountId,Self::Hash>;
+}
+
+pub type ListingId = u32;
+
+/// Open orders a listing can have at any one time
+const MAX_OPEN_ORDERS: usize = 100;
+
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Listing<AccountId, Hash> {
+    pub seller: AccountId,
+    pub product: Hash, // Bonsai reference to the description of the product
+    pub unit_price: i128, // functional currency
+    pub quantity: u128, // quantity still available
+    pub unit_of_measure: u16,
+    pub order_type: u16, // 0 Services, 1 Goods, 2 Inventory
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Marketplace {
+        NextId get(next_id): ListingId;
+        Listings get(listing): map ListingId => Option<Listing<T::AccountId, T::Hash>>;
+        SellerListings get(seller_listings): map T::AccountId => Vec<ListingId>;
+        // Open orders created by purchases from a listing, at most MAX_OPEN_ORDERS
+        ListingOrders get(listing_orders): map ListingId => Vec<T::Hash>;
+        // Listing from which an open order was purchased, and the quantity bought
+        OrderListing get(order_listing): map T::Hash => Option<(ListingId, u128)>;
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+        
+        /// Post a listing for an item for sale.
+        #[weight = TransactionWeight::Basic(READ + 2 * WRITE + LIST, 1)]
+        fn post_listing(
+            origin,
+            product: T::Hash,
+            unit_price: i128,
+            quantity: u128,
+            unit_of_measure: u16,
+            order_type: u16
+        ) -> Result {
+            let seller = ensure_signed(origin)?;
+            
+            ensure!(unit_price > 0, "The price must be greater than zero");
+            ensure!(quantity > 0, "The quantity must be greater than zero");
+            
+            let listing_id = Self::next_id();
+            let next_id = listing_id.checked_add(1).ok_or("No more listings can be created")?;
+            
+            let listing = Listing {
+                seller: seller.clone(),
+                product: product,
+                unit_price: unit_price,
+                quantity: quantity,
+                unit_of_measure: unit_of_measure,
+                order_type: order_type,
+            };
+            
+            <NextId<T>>::put(next_id);
+            <Listings<T>>::insert(listing_id, listing);
+            <SellerListings<T>>::mutate(&seller, |listings| listings.push(listing_id));
+            
+            Self::deposit_event(RawEvent::Posted(seller, listing_id));
+            
+            Ok(())
+        }
+        
+        /// Change the price or the available quantity of a listing. Existing orders are not affected.
+        #[weight = TransactionWeight::Basic(READ + WRITE, 1)]
+        fn update_listing(origin, listing_id: ListingId, unit_price: i128, quantity: u128) -> Result {
+            let seller = ensure_signed(origin)?;
+            
+            let mut listing = Self::listing(listing_id).ok_or("No such listing")?;
+            ensure!(listing.seller == seller, "Cannot change another seller's listing");
+            ensure!(unit_price > 0, "The price must be greater than zero");
+            
+            listing.unit_price = unit_price;
+            listing.quantity = quantity;
+            <Listings<T>>::insert(listing_id, listing);
+            
+            Self::deposit_event(RawEvent::Updated(listing_id));
+            
+            Ok(())
+        }
+        
+        /// Withdraw a listing. Existing orders are not affected.
+        #[weight = TransactionWeight::Basic(READ + WRITE + LIST, 1)]
+        fn cancel_listing(origin, listing_id: ListingId) -> Result {
+            let seller = ensure_signed(origin)?;
+            
+            let listing = Self::listing(listing_id).ok_or("No such listing to cancel")?;
+            ensure!(listing.seller == seller, "Cannot cancel another seller's listing");
+            
+            <Listings<T>>::remove(listing_id);
+            <SellerListings<T>>::mutate(&seller, |listings| listings.retain(|l| l != &listing_id));
+            
+            Self::deposit_event(RawEvent::Cancelled(listing_id));
+            
+            Ok(())
+        }
+        
+        /// Buy a quantity from a listing. This creates a prefunded order for the seller, 
+        /// which locks the amount until the invoice is settled or the order rejected.
+        #[weight = TransactionWeight::Basic(3 * READ + 2 * WRITE + LIST + ORDER + PREFUNDING, 1)]
+        fn buy(
+            origin,
+            listing_id: ListingId,
+            quantity: u128,
+            deadline: u64, // prefunding acceptance deadline 
+            due_date: u64, // due date is the future delivery date (in blocks) 
+            bonsai_token: T::Hash,
+            tx_uid: T::Hash
+        ) -> Result {
+            let buyer = ensure_signed(origin)?;
+            
+            let mut listing = Self::listing(listing_id).ok_or("No such listing to buy")?;
+            ensure!(listing.seller != buyer, "Can't buy own listing");
+            ensure!(quantity > 0, "The quantity must be greater than zero");
+            let remaining = listing.quantity.checked_sub(quantity).ok_or("Not enough quantity available")?;
+            ensure!(Self::listing_orders(listing_id).len() < MAX_OPEN_ORDERS, "Too many open orders for this listing");
+            
+            let order_hash = <T::Orders as Ordering<T::AccountId, T::Hash>>::create_prefunded_order(
+                buyer.clone(),
+                listing.seller.clone(),
+                listing.order_type,
+                deadline,
+                due_date,
+                (listing.product.clone(), listing.unit_price, quantity, listing.unit_of_measure),
+                bonsai_token,
+                tx_uid
+            )?;
+            
+            listing.quantity = remaining;
+            <Listings<T>>::insert(listing_id, listing);
+            <ListingOrders<T>>::mutate(listing_id, |orders| orders.push(order_hash.clone()));
+            <OrderListing<T>>::insert(&order_hash, (listing_id, quantity));
+            
+            Self::deposit_event(RawEvent::Sold(buyer, listing_id, order_hash));
+            
+            Ok(())
+        }
+    }
+}
+
+decl_event!(
+    pub enum Event<T>
+    where
+    AccountId = <T as system::Trait>::AccountId,
+    Hash = <T as system::Trait>::Hash,
+    {
+        Posted(AccountId, ListingId),
+        Updated(ListingId),
+        Cancelled(ListingId),
+        Sold(AccountId, ListingId, Hash),
+        Released(ListingId, Hash, u128),
+    }
+);
+
+impl<T: Trait> Closing<T::Hash> for Module<T> {
+    /// Closes the open order. When the order was not settled the quantity bought is returned to the listing.
+    fn prefunding_closed(h: T::Hash, settled: bool) -> Result {
+        if let Some((listing_id, quantity)) = <OrderListing<T>>::take(&h) {
+            <ListingOrders<T>>::mutate(listing_id, |orders| orders.retain(|o| o != &h));
+            
+            if !settled {
+                // a cancelled listing has nothing to return the quantity to
+                if let Some(mut listing) = Self::listing(listing_id) {
+                    listing.quantity = listing.quantity.saturating_add(quantity);
+                    <Listings<T>>::insert(listing_id, listing);
+                    Self::deposit_event(RawEvent::Released(listing_id, h, quantity));
+                }
+            }
+        }
+        
+        Ok(())
+    }
+}
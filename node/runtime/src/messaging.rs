@@ -0,0 +1,240 @@
+//!                              Næ§@@@ÑÉ©
+//!                        æ@@@@@@@@@@@@@@@@@@
+//!                    Ñ@@@@?.?@@@@@@@@@@@@@@@@@@@N
+//!                 ¶@@@@@?^%@@.=@@@@@@@@@@@@@@@@@@@@
+//!               N@@@@@@@?^@@@»^@@@@@@@@@@@@@@@@@@@@@@
+//!               @@@@@@@@?^@@@».............?@@@@@@@@@É
+//!              Ñ@@@@@@@@?^@@@@@@@@@@@@@@@@@@'?@@@@@@@@Ñ
+//!              @@@@@@@@@?^@@@»..............»@@@@@@@@@@
+//!              @@@@@@@@@?^@@@»^@@@@@@@@@@@@@@@@@@@@@@@@
+//!              @@@@@@@@@?^ë@@&.@@@@@@@@@@@@@@@@@@@@@@@@
+//!               @@@@@@@@?^´@@@o.%@@@@@@@@@@@@@@@@@@@@©
+//!                @@@@@@@?.´@@@@@ë.........*.±@@@@@@@æ
+//!                 @@@@@@@@?´.I@@@@@@@@@@@@@@.&@@@@@N
+//!                  N@@@@@@@@@@ë.*=????????=?@@@@@Ñ
+//!                    @@@@@@@@@@@@@@@@@@@@@@@@@@@¶
+//!                        É@@@@@@@@@@@@@@@@Ñ¶
+//!                             Næ§@@@ÑÉ©
+
+//! Copyright 2020 Chris D'Costa
+//! This file is part of Totem Live Accounting.
+//! Author Chris D'Costa email: chris.dcosta@totemaccounting.com
+
+//! Totem is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License, or
+//! (at your option) any later version.
+
+//! Totem is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+
+//! You should have received a copy of the GNU General Public License
+//! along with Totem.  If not, see <http://www.gnu.org/licenses/>.
+
+/// Encrypted messaging between users with verified boxkeys.
+/// 
+/// Messages carry payloads such as order attachments, hashes of invoice documents, or notes, and always refer to 
+/// an order (5000) or a project (3000) that both the sender and the recipient take part in.
+/// 
+/// The payload is encrypted off-chain with NaCl `box` from the sender's secret encryption key to the recipient's 
+/// public encryption key, as registered and verified in BoxKeyS. The payload stored on chain is the ciphertext 
+/// without the 16 leading zero bytes produced by `box`. The runtime never sees the plaintext.
+/// 
+/// Messages are kept until they expire, or until the sender or the recipient deletes them. Each recipient 
+/// `UserNameHash` has an inbox listing the message hashes. The `messaging_*` RPC serves the encrypted messages, 
+/// which the recipient decrypts client-side.
+
+use support::{decl_event, decl_module, decl_storage, dispatch::Result, ensure, StorageMap, StorageValue};
+use support::dispatch::TransactionWeight;
+use system::{self, ensure_signed, ensure_root};
+use substrate_primitives::H256;
+use rstd::prelude::*;
+use runtime_primitives::traits::{As, Hash, Zero};
+
+// Totem crates
+use crate::boxkeys_traits::{ Lookup as BoxKeysLookup };
+use crate::orders_traits::{ Validating as OrderValidating };
+use crate::projects_traits::{ Validating as ProjectValidating };
+use crate::weights::{ LIST, READ, SETTING, WRITE };
+use totem_primitives::Message;
+
+pub trait Trait: system::Trait {
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    type BoxKeys: BoxKeysLookup<Self::AccountId>;
+    type Orders: OrderValidating<Self::AccountId,Self::Hash>;
+    type Projects: ProjectValidating<Self::AccountId,Self::Hash>;
+}
+
+pub type RecordType = u16;
+pub type UserNameHash = H256;
+pub type EncryptPublicKey = H256;
+pub type EncryptNonce = [u8; 24];
+
+/// Blocks a message is kept when no lifetime has been set (7 days)
+const DEFAULT_MESSAGE_LIFETIME: u64 = 40320;
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Messaging {
+        Messages get(message): map T::Hash => Option<Message<T::AccountId, T::Hash, T::BlockNumber>>;
+        // Message hashes per recipient
+        Inbox get(inbox): map UserNameHash => Vec<T::Hash>;
+        // Messages to be removed at the end of a block
+        ExpiryQueue get(expiry_queue): map T::BlockNumber => Vec<T::Hash>;
+        // Maximum size of an encrypted payload in bytes
+        MaxPayloadSize get(max_payload_size): u32 = 4096;
+        // Maximum number of messages waiting in an inbox
+        MaxInboxSize get(max_inbox_size): u32 = 1000;
+        // Number of blocks a message is kept
+        MessageLifetime get(message_lifetime): Option<T::BlockNumber>;
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+        
+        /// Sends an encrypted payload to a verified user about an order (5000) or a project (3000).
+        /// Both the sender and the recipient must have bound their account to verified boxkeys.
+        #[weight = TransactionWeight::Basic(8 * READ + WRITE + 2 * LIST, 1)]
+        fn send_message(
+            origin,
+            recipient: UserNameHash,
+            reference_type: RecordType,
+            reference: T::Hash,
+            nonce: EncryptNonce,
+            payload: Vec<u8>
+        ) -> Result {
+            let who = ensure_signed(origin)?;
+            
+            ensure!(!payload.is_empty(), "The message is empty");
+            ensure!(payload.len() as u32 <= Self::max_payload_size(), "The message exceeds the maximum size");
+            
+            let sender_user = <T::BoxKeys as BoxKeysLookup<T::AccountId>>::user_hash_of(who.clone()).ok_or("Your account is not bound to any keys")?;
+            let sender_key = <T::BoxKeys as BoxKeysLookup<T::AccountId>>::encryption_key_of(who.clone()).ok_or("Your encryption key is not verified")?;
+            let recipient_account = <T::BoxKeys as BoxKeysLookup<T::AccountId>>::account_of(recipient.clone()).ok_or("The recipient is not bound to any account")?;
+            ensure!(<T::BoxKeys as BoxKeysLookup<T::AccountId>>::encryption_key_of(recipient_account.clone()).is_some(), "The recipient's encryption key is not verified");
+            
+            Self::check_reference(who.clone(), recipient_account, reference_type, reference.clone())?;
+            
+            let mut inbox = Self::inbox(&recipient);
+            ensure!((inbox.len() as u32) < Self::max_inbox_size(), "The recipient's inbox is full");
+            
+            let current_block = <system::Module<T>>::block_number();
+            let message_hash: T::Hash = T::Hashing::hash_of(&(who.clone(), recipient.clone(), reference.clone(), nonce.clone(), current_block));
+            ensure!(!<Messages<T>>::exists(&message_hash), "This message has already been sent");
+            
+            let expires = current_block + Self::lifetime();
+            let message = Message {
+                sender_user: sender_user,
+                recipient: recipient.clone(),
+                reference_type: reference_type,
+                reference: reference.clone(),
+                sender_key: sender_key,
+                nonce: nonce,
+                payload: payload,
+                sent: current_block,
+                expires: expires,
+                sender: who,
+            };
+            
+            <Messages<T>>::insert(&message_hash, message);
+            inbox.push(message_hash.clone());
+            <Inbox<T>>::insert(&recipient, inbox);
+            <ExpiryQueue<T>>::mutate(expires, |queue| queue.push(message_hash.clone()));
+            
+            Self::deposit_event(RawEvent::MessageSent(message_hash, recipient, reference));
+            
+            Ok(())
+        }
+        
+        /// Deletes a message before it expires. Only the sender or the recipient can do this.
+        #[weight = TransactionWeight::Basic(2 * READ + WRITE + 2 * LIST, 1)]
+        fn delete_message(origin, message_hash: T::Hash) -> Result {
+            let who = ensure_signed(origin)?;
+            
+            let message = Self::message(&message_hash).ok_or("Message does not exist")?;
+            let is_recipient = <T::BoxKeys as BoxKeysLookup<T::AccountId>>::user_hash_of(who.clone()) == Some(message.recipient);
+            ensure!(who == message.sender || is_recipient, "You cannot delete a message you did not send or receive");
+            
+            Self::remove_message(message_hash.clone());
+            <ExpiryQueue<T>>::mutate(message.expires, |queue| queue.retain(|h| h != &message_hash));
+            
+            Self::deposit_event(RawEvent::MessageDeleted(message_hash));
+            
+            Ok(())
+        }
+        
+        /// Sets the maximum size of an encrypted payload in bytes
+        #[weight = TransactionWeight::Basic(SETTING, 1)]
+        fn set_max_payload_size(origin, size: u32) -> Result {
+            ensure_root(origin)?;
+            <MaxPayloadSize<T>>::put(size);
+            Ok(())
+        }
+        
+        /// Sets the maximum number of messages waiting in an inbox
+        #[weight = TransactionWeight::Basic(SETTING, 1)]
+        fn set_max_inbox_size(origin, size: u32) -> Result {
+            ensure_root(origin)?;
+            <MaxInboxSize<T>>::put(size);
+            Ok(())
+        }
+        
+        /// Sets the number of blocks new messages are kept
+        #[weight = TransactionWeight::Basic(SETTING, 1)]
+        fn set_message_lifetime(origin, blocks: T::BlockNumber) -> Result {
+            ensure_root(origin)?;
+            ensure!(blocks > T::BlockNumber::zero(), "Message lifetime must be at least one block");
+            <MessageLifetime<T>>::put(blocks);
+            Ok(())
+        }
+        
+        fn on_finalize(n: T::BlockNumber) {
+            for h in <ExpiryQueue<T>>::take(n) {
+                Self::remove_message(h);
+            }
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    fn lifetime() -> T::BlockNumber {
+        Self::message_lifetime().unwrap_or_else(|| T::BlockNumber::sa(DEFAULT_MESSAGE_LIFETIME))
+    }
+    
+    /// Both parties must take part in the referenced order or project
+    fn check_reference(s: T::AccountId, r: T::AccountId, e: RecordType, h: T::Hash) -> Result {
+        match e {
+            3000 => {
+                ensure!(<T::Projects as ProjectValidating<T::AccountId, T::Hash>>::is_project_valid(h.clone()), "The project is not valid");
+                ensure!(<T::Projects as ProjectValidating<T::AccountId, T::Hash>>::has_project_role(s, h.clone(), 300), "You are not a member of this project");
+                ensure!(<T::Projects as ProjectValidating<T::AccountId, T::Hash>>::has_project_role(r, h, 300), "The recipient is not a member of this project");
+            },
+            5000 => {
+                ensure!(<T::Orders as OrderValidating<T::AccountId, T::Hash>>::is_order_party(s, h.clone()), "You are not party to this order");
+                ensure!(<T::Orders as OrderValidating<T::AccountId, T::Hash>>::is_order_party(r, h), "The recipient is not party to this order");
+            },
+            _ => return Err("Unknown or unimplemented reference type. Cannot send message"),
+        }
+        
+        Ok(())
+    }
+    
+    fn remove_message(h: T::Hash) {
+        if let Some(message) = <Messages<T>>::take(&h) {
+            <Inbox<T>>::mutate(&message.recipient, |inbox| inbox.retain(|m| m != &h));
+        }
+    }
+}
+
+decl_event!(
+    pub enum Event<T>
+    where
+    Hash = <T as system::Trait>::Hash,
+    {
+        MessageSent(Hash, UserNameHash, Hash),
+        MessageDeleted(Hash),
+    }
+);
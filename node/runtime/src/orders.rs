@@ -44,6 +44,8 @@
 //! * The order can be prefunded by calling into the prefunding module, which updates the accounting ledgers.
 //! * Once the order is accepted, the work must begin, and once completed, the vendor sets the state to completed.
 //! * The completion state also generates the invoice, and relevant accounting postings for both the buyer and the seller.
+//! For Goods and Inventory orders the items are first issued from the seller's stock and received by the buyer, who expenses Goods
+//! and adds Inventory to stock.
 //! * The completed work is then approved by the buyer (or disputed or rejected). An approval triggers the release of prefunds and 
 //! the invoice is marked as settled in the accounts for both parties
 //! 
@@ -65,20 +67,28 @@ use support::{
     decl_module, 
     decl_storage, 
     dispatch::Result, 
+    ensure,
     StorageMap
 };
+use support::dispatch::TransactionWeight;
 
 use system::ensure_signed;
 use parity_codec::{Decode, Encode};
 use runtime_primitives::traits::{Convert};
-use rstd::prelude::*;
+use rstd::{prelude::*, result};
 // use node_primitives::Hash; // Use only in full node
 
 // Totem Traits
-use crate::accounting_traits::{ Posting };
+use crate::accounting_traits::{ Posting, Quantities };
 use crate::prefunding_traits::{ Encumbrance };
 use crate::bonsai_traits::{ Storing };
-use crate::orders_traits::{ Validating };
+use crate::orders_traits::{ Validating, Ordering };
+use crate::archive_traits::{ Archivable, Retention };
+use crate::reputation_traits::{ Reputation };
+use crate::weights::{ LIST, ORDER, PREFUNDING, READ, SETTLEMENT, UUID, WRITE };
+
+// Runtime api types
+use totem_primitives::{ OrderDetails, OrderLine, OrderParty, PARTY_COMMANDER, PARTY_FULFILLER, PARTY_APPROVER };
 
 // Totem Trait Types
 type AccountBalanceOf<T> = <<T as Trait>::Accounting as Posting<<T as system::Trait>::AccountId,<T as system::Trait>::Hash,<T as system::Trait>::BlockNumber>>::LedgerBalance;
@@ -150,11 +160,15 @@ pub trait Trait: system::Trait {
     Convert<bool, UnLocked<Self>> + 
     Convert<AccountBalanceOf<Self>, i128> + 
     Convert<AccountBalanceOf<Self>, u128> + 
+    Convert<u128, i128> + 
     Convert<u64, Self::BlockNumber> +
     Convert<Self::BlockNumber, u64>;
     type Accounting: Posting<Self::AccountId,Self::Hash,Self::BlockNumber>;
     type Prefunding: Encumbrance<Self::AccountId,Self::Hash,Self::BlockNumber>;
     type Bonsai: Storing<Self::Hash>;
+    type Archive: Retention<Self::AccountId,Self::Hash>;
+    type Reputation: Reputation<Self::AccountId,Self::Hash>;
+    type Quantities: Quantities<Self::AccountId,Self::Hash>;
 }
 
 decl_storage! {
@@ -162,6 +176,10 @@ decl_storage! {
         Owner get(owner): map T::AccountId => Vec<T::Hash>;
         Beneficiary get(beneficiary): map T::AccountId => Vec<T::Hash>;
         Approver get(approver): map T::AccountId => Vec<T::Hash>;
+        // Orders archived by each party. These are no longer shown in the party's active lists.
+        OwnerArchive get(owner_archive): map T::AccountId => Vec<T::Hash>;
+        BeneficiaryArchive get(beneficiary_archive): map T::AccountId => Vec<T::Hash>;
+        ApproverArchive get(approver_archive): map T::AccountId => Vec<T::Hash>;
         Postulate get(postulate): map T::Hash => Vec<T::Hash>;
         Orders get(orders): map T::Hash => Option<OrderHeader<T::AccountId>>;
         OrderItems get(order_items): map T::Hash => Vec<OrderItem<T::Hash>>;
@@ -172,6 +190,7 @@ decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
         fn deposit_event<T>() = default;
         /// Only the owner of an order can delete it provided no work has been done on it. 
+        #[weight = TransactionWeight::Basic(2 * UUID + READ + 3 * WRITE + 3 * LIST, 1)]
         fn delete_order(
             origin,
             tx_keys_medium: TXKeysM<T::Hash>
@@ -214,6 +233,7 @@ decl_module! {
         
         /// Creates either a sales order or a purchase order with multi-line items and a parent order
         /// Will be used for the marketplace in order to set up open orders
+        #[weight = TransactionWeight::Basic(2 * UUID + 2 * READ + LIST + ORDER, 1)]
         fn create_order(
             origin,
             approver: T::AccountId, 
@@ -303,6 +323,7 @@ decl_module! {
         
         /// Create Simple Prefunded Service Order
         /// Can specify an approver. If the approver is the same as the sender then the order is considered approved by default
+        #[weight = TransactionWeight::Basic(2 * UUID + READ + ORDER + PREFUNDING, 1)]
         fn create_spfso(
             origin,
             approver: T::AccountId, 
@@ -310,7 +331,7 @@ decl_module! {
             buy_or_sell: u16, // 0: buy, 1: sell, extensible
             total_amount: i128, // amount should be the sum of all the items untiprices * quantities
             market_order: bool, // 0: open(false) 1: closed(true)
-            order_type: u16, // 0: service, 1: inventory, 2: asset extensible 
+            order_type: u16, // 0 Services, 1 Goods, 2 Inventory
             deadline: u64, // prefunding acceptance deadline 
             due_date: u64, // due date is the future delivery date (in blocks) 
             order_item: OrderItem<T::Hash>, // for simple items there will only be one item, item number is accessed by its position in Vec 
@@ -349,6 +370,7 @@ decl_module! {
         }
         /// Change Simple Prefunded Service Order.
         /// Can only be changed by the original ordering party, and only before it is accepted and the deadline or due date is not passed
+        #[weight = TransactionWeight::Basic(2 * UUID + 2 * READ + 2 * WRITE + PREFUNDING, 1)]
         fn change_spfso(
             origin, 
             approver: T::AccountId, 
@@ -382,6 +404,7 @@ decl_module! {
         }
         /// Sets the approval status of an order 
         /// Can only be used by the nominated approver (must be known to the ordering party)
+        #[weight = TransactionWeight::Basic(2 * UUID + 2 * READ + 2 * WRITE + LIST, 1)]
         fn change_approval(origin, h: T::Hash, s: ApprovalStatus, b: T::Hash, tx_uid: T::Hash) -> Result {
             let who = ensure_signed(origin)?;
             <<T as Trait>::Bonsai as Storing<T::Hash>>::store_uuid(tx_uid)?;
@@ -394,6 +417,7 @@ decl_module! {
         /// Can be used by buyer or seller
         /// Buyer - Used by the buyer to accept or reject (TODO) the invoice that was raised by the seller.
         /// Seller - Used to accept, reject or invoice the order. 
+        #[weight = TransactionWeight::Basic(2 * UUID + READ + 2 * WRITE + SETTLEMENT, 1)]
         fn handle_spfso(origin, h: T::Hash, s: OrderStatus, tx_uid: T::Hash) -> Result {
             let who = ensure_signed(origin)?;
             <<T as Trait>::Bonsai as Storing<T::Hash>>::store_uuid(tx_uid.clone())?;
@@ -413,7 +437,9 @@ decl_module! {
                 // This is the buyer 
                 //TODO if the order us passed as an arg it doesn't need to be read again
                 Self::accept_prefunded_invoice(who.clone(), h.clone(), s, order_hdr.clone(), tx_uid)?;
-                Self::deposit_event(RawEvent::InvoiceSettled(tx_uid));
+                if s == 6 {
+                    Self::deposit_event(RawEvent::InvoiceSettled(tx_uid));
+                }
                 
             } else if who == fulfiller {
                 // This is the seller
@@ -480,7 +506,7 @@ impl<T: Trait> Module<T> {
         buy_or_sell: u16, // 0: buy, 1: sell, extensible
         amount: i128, // amount should be the sum of all the items untiprices * quantities
         market_order: bool, // 0: open(false) 1: closed(true)
-        order_type: u16, // 0: personal, 1: business, extensible 
+        order_type: u16, // 0 Services, 1 Goods, 2 Inventory
         deadline: u64, // prefunding acceptance deadline 
         due_date: u64, // due date is the future delivery date (in blocks) 
         order_hash: T::Hash,
@@ -805,6 +831,7 @@ impl<T: Trait> Module<T> {
                         // We do not need to set release state for releasing funds for fulfiller.
                         
                         // set release state for releasing funds for commander.
+                        // Closing the prefunding also returns the quantity of a marketplace order to its listing.
                         match <<T as Trait>::Prefunding as Encumbrance<T::AccountId,T::Hash,T::BlockNumber>>::set_release_state(order.commander.clone(),lock,h,uid.clone()) {
                             Ok(_) => (),
                             Err(_e) => {
@@ -833,8 +860,24 @@ impl<T: Trait> Module<T> {
                 // Order already in accepted state - Update the status
                 match s {
                     5 => {
-                        // Order Completed. Now we are going to issue the invoice.
-                        match <<T as Trait>::Prefunding as Encumbrance<T::AccountId,T::Hash,T::BlockNumber>>::send_simple_invoice(f.clone(), order.commander.clone(), order.amount, h, uid) {
+                        // Order Completed. Goods are delivered before we issue the invoice.
+                        // A failed call does not revert storage, so everything is checked before the first write.
+                        if !<<T as Trait>::Prefunding as Encumbrance<T::AccountId,T::Hash,T::BlockNumber>>::check_ref_beneficiary(f.clone(), h) {
+                            Self::deposit_event(RawEvent::ErrorInPrefunding5(uid));
+                            return Err("Error in prefunding");
+                        }
+                        ensure!(order.amount.checked_neg().is_some(), "Invoice amount overflow");
+                        let delivered = order.order_type == 1 || order.order_type == 2;
+                        let items = if delivered { Self::order_items(&h) } else { Vec::new() };
+                        if let Err(e) = Self::check_delivery(f.clone(), order.commander.clone(), order.order_type == 2, &items) {
+                            Self::deposit_event(RawEvent::ErrorInsufficientStock(uid));
+                            return Err(e);
+                        }
+                        
+                        if delivered {
+                            Self::deliver_goods(f.clone(), order.commander.clone(), order.order_type == 2, items, h)?;
+                        }
+                        match <<T as Trait>::Prefunding as Encumbrance<T::AccountId,T::Hash,T::BlockNumber>>::send_simple_invoice(f.clone(), order.commander.clone(), order.amount, h, order.order_type, uid) {
                             Ok(_) => (),
                             Err(_e) => {
                                 Self::deposit_event(RawEvent::ErrorInPrefunding5(uid));
@@ -862,7 +905,12 @@ impl<T: Trait> Module<T> {
         order.order_status = s;
         
         <Orders<T>>::remove(&h);
-        <Orders<T>>::insert(&h, order);
+        <Orders<T>>::insert(&h, order.clone());
+
+        // A rejected order can no longer change
+        if s == 2 {
+            Self::schedule_order_archive(h, order)?;
+        }
         
         Self::deposit_event(RawEvent::OrderCompleted(uid));
         Ok(())
@@ -871,13 +919,17 @@ impl<T: Trait> Module<T> {
     fn accept_prefunded_invoice(o: T::AccountId, h: T::Hash, s: OrderStatus, mut order: OrderHeader<T::AccountId>, uid: T::Hash) -> Result {
         // check that this is the fulfiller
         match order.order_status {
-            5 => {
-                // Order has been invoiced. The buyer is now deciding to accept or other
+            5 | 3 => {
+                // Order has been invoiced (or the invoice disputed). The buyer is now deciding to accept or other
                 match s {
                     3 => {
                         // Invoice is disputed. TODO provide the ability to change the invoice and resubmit
-                        Self::deposit_event(RawEvent::ErrorNotImplmented1(uid));
-                        return Err("TODO!");
+                        // The funds remain locked until the buyer accepts the invoice.
+                        if order.order_status == 3 {
+                            Self::deposit_event(RawEvent::ErrorStatusNotAllowed5(uid));
+                            return Err("The invoice is already disputed!");
+                        }
+                        <<T as Trait>::Reputation as Reputation<T::AccountId,T::Hash>>::order_disputed(o.clone(), order.fulfiller.clone(), h.clone())?;
                     },
                     6 => {
                         // Invoice Accepted. Now pay-up!.
@@ -889,6 +941,8 @@ impl<T: Trait> Module<T> {
                             },
                         }
                         
+                        <<T as Trait>::Reputation as Reputation<T::AccountId,T::Hash>>::order_settled(o.clone(), order.fulfiller.clone(), h.clone(), order.amount)?;
+                        
                         Self::deposit_event(RawEvent::InvoiceSettled(uid));
                     },
                     _ => {
@@ -906,7 +960,12 @@ impl<T: Trait> Module<T> {
         }
         order.order_status = s;
         <Orders<T>>::remove(&h);
-        <Orders<T>>::insert(&h, order);
+        <Orders<T>>::insert(&h, order.clone());
+
+        // The invoice is settled and the order can no longer change
+        if s == 6 {
+            Self::schedule_order_archive(h, order)?;
+        }
         
         Ok(())
     }
@@ -938,6 +997,220 @@ impl<T: Trait> Validating<T::AccountId, T::Hash> for Module<T> {
     }
 }
 
+impl<T: Trait> Ordering<T::AccountId, T::Hash> for Module<T> {
+    /// Used by the marketplace. The commander is the approver, so the order is approved and prefunded immediately.
+    fn create_prefunded_order(
+        commander: T::AccountId,
+        fulfiller: T::AccountId,
+        order_type: u16,
+        deadline: u64,
+        due_date: u64,
+        item: (T::Hash, i128, u128, u16),
+        bonsai_token: T::Hash,
+        uid: T::Hash
+    ) -> result::Result<T::Hash, &'static str> {
+        let (product, unit_price, quantity, unit_of_measure) = item;
+        let quantity_signed: i128 = <T::Conversions as Convert<u128, i128>>::convert(quantity);
+        let amount: i128 = unit_price.checked_mul(quantity_signed).ok_or("Order amount overflow")?;
+        ensure!(amount > 0, "Amount cannot be less than zero!");
+        
+        let order_hash: T::Hash = <<T as Trait>::Accounting as Posting<T::AccountId,T::Hash,T::BlockNumber>>::get_pseudo_random_hash(commander.clone(), fulfiller.clone());
+        ensure!(!<Orders<T>>::exists(&order_hash), "The hash already exists! Try again.");
+        
+        let order_item: OrderItem<T::Hash> = OrderItem {
+            product: product,
+            unit_price: unit_price,
+            quantity: quantity,
+            unit_of_measure: unit_of_measure,
+        };
+        
+        Self::set_simple_prefunded_service_order(
+            commander.clone(),
+            commander,
+            fulfiller,
+            0, // buy
+            amount,
+            false,
+            order_type,
+            deadline,
+            due_date,
+            order_hash.clone(),
+            order_item,
+            bonsai_token,
+            uid
+        )?;
+        Self::deposit_event(RawEvent::OrderCreated(uid, order_hash.clone()));
+        
+        Ok(order_hash)
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Checks that the fulfiller holds enough stock of every product on the order,
+    /// and that the commander can receive every item at its unit price.
+    fn check_delivery(f: T::AccountId, c: T::AccountId, stocked: bool, items: &Vec<OrderItem<T::Hash>>) -> Result {
+        let mut required: Vec<(T::Hash, u128)> = Vec::new();
+        for i in items {
+            ensure!(
+                <<T as Trait>::Quantities as Quantities<T::AccountId,T::Hash>>::can_receive(c.clone(), i.product.clone(), i.quantity, i.unit_price, stocked),
+                "The order items cannot be received"
+            );
+            match required.iter_mut().find(|r| r.0 == i.product) {
+                Some(r) => r.1 = r.1.checked_add(i.quantity).ok_or("Order quantity overflow")?,
+                None => required.push((i.product.clone(), i.quantity)),
+            }
+        }
+        for (product, quantity) in required {
+            ensure!(
+                <<T as Trait>::Quantities as Quantities<T::AccountId,T::Hash>>::can_issue(f.clone(), product, quantity), 
+                "Insufficient stock to deliver the order"
+            );
+        }
+        
+        Ok(())
+    }
+    /// Issues the items from the fulfiller's stock and receives them for the commander.
+    /// Stocked items are added to the commander's inventory, otherwise they are expensed.
+    fn deliver_goods(f: T::AccountId, c: T::AccountId, stocked: bool, items: Vec<OrderItem<T::Hash>>, h: T::Hash) -> Result {
+        for i in items {
+            <<T as Trait>::Quantities as Quantities<T::AccountId,T::Hash>>::goods_issued(f.clone(), i.product.clone(), i.quantity, h.clone())?;
+            <<T as Trait>::Quantities as Quantities<T::AccountId,T::Hash>>::goods_received(c.clone(), i.product, i.quantity, i.unit_price, stocked, h.clone())?;
+        }
+        
+        Ok(())
+    }
+    /// Queues the order for automatic archiving by each of the parties
+    fn schedule_order_archive(h: T::Hash, order: OrderHeader<T::AccountId>) -> Result {
+        let mut parties: Vec<T::AccountId> = Vec::new();
+        parties.push(order.commander);
+        if !parties.contains(&order.fulfiller) { parties.push(order.fulfiller) };
+        if !parties.contains(&order.approver) { parties.push(order.approver) };
+
+        for p in parties {
+            <<T as Trait>::Archive as Retention<T::AccountId, T::Hash>>::schedule_archive(5000, p, h.clone())?;
+        }
+
+        Ok(())
+    }
+
+    /// Moves the order between an active list and its archive
+    fn set_order_archive(
+        active: &mut Vec<T::Hash>,
+        archived: &mut Vec<T::Hash>,
+        h: T::Hash,
+        a: bool
+    ) -> Result {
+        // check if it's a retrieval or an archival process
+        let (from, to) = match a {
+            true => (active, archived),
+            false => (archived, active),
+        };
+
+        // Check that the order exists in the list it is moved from, otherwise don't update
+        ensure!(from.iter().any(|x| x == &h), "This record has either been archived already or does not exist!");
+
+        from.retain(|x| x != &h);
+        to.push(h);
+
+        Ok(())
+    }
+    /// Returns an order header together with its items. Used by the runtime api.
+    pub fn order_details(h: T::Hash) -> Option<OrderDetails<T::AccountId, T::Hash>> {
+        let order = Self::orders(&h)?;
+        let items = Self::order_items(&h).into_iter().map(|i| OrderLine {
+            product: i.product,
+            unit_price: i.unit_price,
+            quantity: i.quantity,
+            unit_of_measure: i.unit_of_measure,
+        }).collect();
+
+        Some(OrderDetails {
+            order_hash: h,
+            commander: order.commander,
+            fulfiller: order.fulfiller,
+            approver: order.approver,
+            order_status: order.order_status,
+            approval_status: order.approval_status,
+            buy_or_sell: order.buy_or_sell,
+            amount: order.amount,
+            market_order: order.market_order,
+            order_type: order.order_type,
+            deadline: order.deadline,
+            due_date: order.due_date,
+            items: items,
+        })
+    }
+    /// Returns the active and archived orders in which the account takes the given party role,
+    /// optionally filtered by order status. Used by the runtime api.
+    pub fn orders_by_party(
+        who: T::AccountId, 
+        party: OrderParty, 
+        status: Option<OrderStatus>
+    ) -> Vec<OrderDetails<T::AccountId, T::Hash>> {
+        let (mut hashes, archived) = match party {
+            PARTY_COMMANDER => (Self::owner(&who), Self::owner_archive(&who)),
+            PARTY_FULFILLER => (Self::beneficiary(&who), Self::beneficiary_archive(&who)),
+            PARTY_APPROVER => (Self::approver(&who), Self::approver_archive(&who)),
+            _ => return Vec::new(),
+        };
+        for h in archived {
+            if !hashes.contains(&h) { hashes.push(h) };
+        }
+
+        hashes.into_iter()
+            .filter_map(Self::order_details)
+            .filter(|o| status.map_or(true, |s| o.order_status == s))
+            .collect()
+    }
+}
+
+impl<T: Trait> Archivable<T::AccountId, T::Hash> for Module<T> {
+    /// Each party to the order archives it from their own list. 
+    fn validate_and_archive(o: T::AccountId, h: T::Hash, a: bool) -> Result {
+        let order = Self::orders(&h).ok_or("Unable to fetch order with this reference.")?;
+
+        ensure!(Self::is_order_party(o.clone(), h.clone()), "You cannot archive an order you are not party to");
+
+        if o == order.commander {
+            let mut active = Self::owner(&o);
+            let mut archived = Self::owner_archive(&o);
+            Self::set_order_archive(&mut active, &mut archived, h.clone(), a)?;
+            <Owner<T>>::insert(&o, active);
+            <OwnerArchive<T>>::insert(&o, archived);
+        };
+
+        // market orders do not have a beneficiary entry for the commander
+        if o == order.fulfiller && o != order.commander {
+            let mut active = Self::beneficiary(&o);
+            let mut archived = Self::beneficiary_archive(&o);
+            Self::set_order_archive(&mut active, &mut archived, h.clone(), a)?;
+            <Beneficiary<T>>::insert(&o, active);
+            <BeneficiaryArchive<T>>::insert(&o, archived);
+        };
+
+        if o == order.approver {
+            let mut active = Self::approver(&o);
+            let mut archived = Self::approver_archive(&o);
+            // The approver list is only set when an approval is requested
+            if active.iter().any(|x| x == &h) || archived.iter().any(|x| x == &h) {
+                Self::set_order_archive(&mut active, &mut archived, h.clone(), a)?;
+                <Approver<T>>::insert(&o, active);
+                <ApproverArchive<T>>::insert(&o, archived);
+            };
+        };
+
+        Ok(())
+    }
+
+    fn is_terminal(h: T::Hash) -> bool {
+        // Rejected or invoice settled
+        match Self::orders(&h) {
+            Some(order) => order.order_status == 2 || order.order_status == 6,
+            None => false,
+        }
+    }
+}
+
 decl_event!(
     pub enum Event<T> where
     Hash = <T as system::Trait>::Hash,
@@ -1015,5 +1288,7 @@ decl_event!(
         ErrorInPrefunding7(Hash),
         /// Error Cannot make an market order against a parent order
         ErrorMarketOrder(Hash),
+        /// Error The fulfiller does not hold enough stock to deliver the goods
+        ErrorInsufficientStock(Hash),
     }
 );
\ No newline at end of file
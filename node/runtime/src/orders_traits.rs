@@ -33,8 +33,23 @@
 //! You should have received a copy of the GNU General Public License
 //! along with Totem.  If not, see <http://www.gnu.org/licenses/>.
 
-// use support::{ dispatch::Result };
+use rstd::result;
 
 pub trait Validating<AccountId, Hash> {
     fn is_order_party(o: AccountId, r: Hash) -> bool;
 }
+
+pub trait Ordering<AccountId, Hash> {
+    /// Creates an approved, prefunded order of one item from commander to fulfiller and returns the order hash.
+    /// The item is given as product, unit price, quantity and unit of measure.
+    fn create_prefunded_order(
+        commander: AccountId,
+        fulfiller: AccountId,
+        order_type: u16,
+        deadline: u64,
+        due_date: u64,
+        item: (Hash, i128, u128, u16),
+        bonsai_token: Hash,
+        uid: Hash
+    ) -> result::Result<Hash, &'static str>;
+}
@@ -0,0 +1,426 @@
+//!                              Næ§@@@ÑÉ©
+//!                        æ@@@@@@@@@@@@@@@@@@
+//!                    Ñ@@@@?.?@@@@@@@@@@@@@@@@@@@N
+//!                 ¶@@@@@?^%@@.=@@@@@@@@@@@@@@@@@@@@
+//!               N@@@@@@@?^@@@»^@@@@@@@@@@@@@@@@@@@@@@
+//!               @@@@@@@@?^@@@».............?@@@@@@@@@É
+//!              Ñ@@@@@@@@?^@@@@@@@@@@@@@@@@@@'?@@@@@@@@Ñ
+//!              @@@@@@@@@?^@@@»..............»@@@@@@@@@@
+//!              @@@@@@@@@?^@@@»^@@@@@@@@@@@@@@@@@@@@@@@@
+//!              @@@@@@@@@?^ë@@&.@@@@@@@@@@@@@@@@@@@@@@@@
+//!               @@@@@@@@?^´@@@o.%@@@@@@@@@@@@@@@@@@@@©
+//!                @@@@@@@?.´@@@@@ë.........*.±@@@@@@@æ
+//!                 @@@@@@@@?´.I@@@@@@@@@@@@@@.&@@@@@N
+//!                  N@@@@@@@@@@ë.*=????????=?@@@@@Ñ
+//!                    @@@@@@@@@@@@@@@@@@@@@@@@@@@¶
+//!                        É@@@@@@@@@@@@@@@@Ñ¶
+//!                             Næ§@@@ÑÉ©
+
+//! Copyright 2020 Chris D'Costa
+//! This file is part of Totem Live Accounting.
+//! Author Chris D'Costa email: chris.dcosta@totemaccounting.com
+
+//! Totem is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License, or
+//! (at your option) any later version.
+
+//! Totem is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+
+//! You should have received a copy of the GNU General Public License
+//! along with Totem.  If not, see <http://www.gnu.org/licenses/>.
+
+
+/// Payroll for employees booking time on projects.
+/// 
+/// An employer registers its employees with a pay rate per block worked and a withholding rate. A withholding rate 
+/// requires a withholding recipient to be set first, so that the withheld pay can be remitted. When the employer, 
+/// as project owner, accepts a time record in the timekeeping module the pay is accrued: the gross pay is expensed 
+/// as salaries and wages against net wages payable and the withholding payable. Workers that are not employees of 
+/// the project owner are contractors and invoice their time instead.
+/// 
+/// The employer runs the payroll once per pay period. This transfers the accrued net pay to each employee, and the 
+/// withheld amounts to the withholding recipient, clearing the liabilities. Employees are paid in batches,
+/// so an employer with many employees completes the run over several calls.
+
+use parity_codec::{Decode, Encode};
+use support::{decl_event, decl_module, decl_storage, dispatch::Result, ensure, StorageMap, StorageValue};
+use support::dispatch::TransactionWeight;
+use support::traits::Currency;
+use system::{self, ensure_signed, ensure_root};
+use rstd::prelude::*;
+use runtime_primitives::traits::{As, Convert, Zero};
+use runtime_primitives::weights::Weight;
+
+// Totem Traits
+use crate::accounting_traits::{ Posting };
+use crate::payroll_traits::{ Payroll };
+use crate::weights::{ LIST, POSTING, READ, SETTING, WRITE };
+
+// Totem Trait Types
+type AccountOf<T> = <<T as Trait>::Accounting as Posting<<T as system::Trait>::AccountId,<T as system::Trait>::Hash,<T as system::Trait>::BlockNumber>>::Account;
+type AccountBalanceOf<T> = <<T as Trait>::Accounting as Posting<<T as system::Trait>::AccountId,<T as system::Trait>::Hash,<T as system::Trait>::BlockNumber>>::LedgerBalance;
+
+// Other trait types
+type CurrencyBalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+pub trait Trait: system::Trait {
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    type Currency: Currency<Self::AccountId>;
+    type Conversions: 
+    Convert<u64, AccountOf<Self>> + 
+    Convert<i128, AccountBalanceOf<Self>> + 
+    Convert<i128, u128> + 
+    Convert<u128, CurrencyBalanceOf<Self>>;
+    type Accounting: Posting<Self::AccountId,Self::Hash,Self::BlockNumber>;
+}
+
+pub type PayRate = i128; // pay per block worked
+pub type Withholding = u16; // parts per ten thousand of the gross pay
+
+const WITHHOLDING_DENOMINATOR: i128 = 10000;
+
+/// Blocks between payroll runs when no pay period has been set (about 30 days)
+const DEFAULT_PAY_PERIOD: u64 = 432000;
+/// Employees paid by one call of `run_payroll`; the call is charged for a full batch
+const EMPLOYEES_PER_BATCH: Weight = 10;
+
+// Ledger accounts
+const XTX_BALANCE: u64 = 110100040000000; // 110100040000000 XTX Balance
+const NET_WAGES_PAYABLE: u64 = 120200060000000; // 120200060000000 Net wages payable
+const WITHHOLDING_PAYABLE: u64 = 120200070000000; // 120200070000000 Payroll withholding payable
+const SALARY_INCOME: u64 = 240700010000000; // 240700010000000 Salary income
+const SALARIES_AND_WAGES: u64 = 252100010000000; // 252100010000000 Salaries and wages
+
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Employment {
+    pub pay_rate: PayRate,
+    pub withholding: Withholding,
+}
+
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Accrued {
+    pub net: i128,
+    pub withheld: i128,
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as PayrollModule {
+        // Terms of employment by employer and employee
+        Employments get(employment): map (T::AccountId, T::AccountId) => Option<Employment>;
+        // Employees of an employer, paid in batches by the payroll run
+        Employees get(employees): map T::AccountId => Vec<T::AccountId>;
+        // Position in the employees of an employer up to which the current payroll run has paid
+        PayrollCursor get(payroll_cursor): map T::AccountId => u32;
+        // Pay accrued from authorised time and not yet paid, by employer and employee
+        AccruedPay get(accrued_pay): map (T::AccountId, T::AccountId) => Accrued;
+        // Pay accrued for a time record: employer, employee, gross pay, withheld
+        RecordAccruals get(record_accrual): map T::Hash => Option<(T::AccountId, T::AccountId, i128, i128)>;
+        // Identity receiving the withheld amounts of an employer, for example a tax authority
+        WithholdingRecipient get(withholding_recipient): map T::AccountId => Option<T::AccountId>;
+        // Block of the last payroll run of an employer
+        LastPayrollRun get(last_payroll_run): map T::AccountId => Option<T::BlockNumber>;
+        // Number of blocks between payroll runs
+        PayPeriod get(pay_period): Option<T::BlockNumber>;
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+        
+        /// Employs a worker or changes the terms. Only time authorised after the change is paid at the new terms.
+        #[weight = TransactionWeight::Basic(2 * READ + WRITE + LIST, 1)]
+        fn set_employee(origin, employee: T::AccountId, pay_rate: PayRate, withholding: Withholding) -> Result {
+            let who = ensure_signed(origin)?;
+            ensure!(who != employee, "An employer cannot employ itself");
+            ensure!(pay_rate >= 0, "Pay rate cannot be negative");
+            ensure!(withholding as i128 <= WITHHOLDING_DENOMINATOR, "Withholding cannot exceed the gross pay");
+            ensure!(withholding == 0 || <WithholdingRecipient<T>>::exists(&who), "Set a withholding recipient before withholding pay");
+            
+            let key = (who.clone(), employee.clone());
+            if !<Employments<T>>::exists(&key) {
+                <Employees<T>>::mutate(&who, |employees| employees.push(employee.clone()));
+            }
+            <Employments<T>>::insert(&key, Employment { pay_rate: pay_rate, withholding: withholding });
+            
+            Self::deposit_event(RawEvent::EmployeeSet(who, employee, pay_rate, withholding));
+            Ok(())
+        }
+        
+        /// Ends an employment. Accrued pay must be paid first.
+        #[weight = TransactionWeight::Basic(3 * READ + 3 * WRITE + LIST, 1)]
+        fn remove_employee(origin, employee: T::AccountId) -> Result {
+            let who = ensure_signed(origin)?;
+            let key = (who.clone(), employee.clone());
+            ensure!(<Employments<T>>::exists(&key), "Not an employee");
+            let accrued = Self::accrued_pay(&key);
+            ensure!(accrued.net == 0 && accrued.withheld == 0, "The employee has unpaid accrued pay");
+            
+            <Employments<T>>::remove(&key);
+            <AccruedPay<T>>::remove(&key);
+            let mut employees = Self::employees(&who);
+            if let Some(position) = employees.iter().position(|e| e == &employee) {
+                employees.remove(position);
+                // a payroll run in progress continues with the same employee
+                if (position as u32) < Self::payroll_cursor(&who) {
+                    <PayrollCursor<T>>::mutate(&who, |cursor| *cursor -= 1);
+                }
+            }
+            <Employees<T>>::insert(&who, employees);
+            
+            Self::deposit_event(RawEvent::EmployeeRemoved(who, employee));
+            Ok(())
+        }
+        
+        /// Sets the identity that receives the withheld amounts when the payroll is run
+        #[weight = TransactionWeight::Basic(WRITE, 1)]
+        fn set_withholding_recipient(origin, recipient: T::AccountId) -> Result {
+            let who = ensure_signed(origin)?;
+            <WithholdingRecipient<T>>::insert(&who, recipient);
+            Ok(())
+        }
+        
+        /// Pays the accrued net pay to the next batch of employees, and the withheld amounts to the withholding recipient. 
+        /// A run can be started once per pay period, and is called again until all employees have been paid.
+        #[weight = TransactionWeight::Basic(5 * READ + 3 * WRITE + 2 * POSTING + EMPLOYEES_PER_BATCH * (2 * READ + 3 * WRITE + 4 * POSTING), 1)]
+        fn run_payroll(origin) -> Result {
+            let who = ensure_signed(origin)?;
+            let now = <system::Module<T>>::block_number();
+            let employees = Self::employees(&who);
+            let start = rstd::cmp::min(Self::payroll_cursor(&who) as usize, employees.len());
+            if start == 0 {
+                if let Some(last) = Self::last_payroll_run(&who) {
+                    ensure!(now >= last + Self::period(), "The pay period has not ended yet");
+                }
+            }
+            let end = rstd::cmp::min(start + EMPLOYEES_PER_BATCH as usize, employees.len());
+            
+            // Verify that everything can be paid before making any payment
+            let recipient = Self::withholding_recipient(&who);
+            let mut payments: Vec<(T::AccountId, Accrued)> = Vec::new();
+            let mut total: i128 = 0;
+            for employee in employees[start..end].iter().cloned() {
+                let accrued = Self::accrued_pay((who.clone(), employee.clone()));
+                total = total.checked_add(accrued.net.max(0)).ok_or("Payroll total overflow")?;
+                if recipient.is_some() {
+                    total = total.checked_add(accrued.withheld.max(0)).ok_or("Payroll total overflow")?;
+                }
+                payments.push((employee, accrued));
+            }
+            ensure!(Self::to_currency(total) <= T::Currency::free_balance(&who), "Insufficient funds to run the payroll");
+            
+            let mut withheld: i128 = 0;
+            for (employee, accrued) in payments {
+                if accrued.net > 0 {
+                    Self::pay(who.clone(), employee.clone(), accrued.net)?;
+                }
+                // Negative amounts are reversals of pay that was already paid out. 
+                // They are carried forward and set off against the next accruals.
+                let mut carried = Accrued { net: accrued.net.min(0), withheld: accrued.withheld };
+                if recipient.is_some() && accrued.withheld > 0 {
+                    withheld += accrued.withheld;
+                    carried.withheld = 0;
+                }
+                let key = (who.clone(), employee);
+                if carried == Accrued::default() {
+                    <AccruedPay<T>>::remove(&key);
+                } else {
+                    <AccruedPay<T>>::insert(&key, carried);
+                }
+            }
+            
+            if let Some(r) = recipient {
+                if withheld > 0 {
+                    Self::remit_withholding(who.clone(), r, withheld)?;
+                }
+            }
+            
+            if start == 0 {
+                <LastPayrollRun<T>>::insert(&who, now);
+            }
+            if end < employees.len() {
+                <PayrollCursor<T>>::insert(&who, end as u32);
+                Self::deposit_event(RawEvent::PayrollBatchPaid(who, end as u32, total));
+            } else {
+                <PayrollCursor<T>>::remove(&who);
+                Self::deposit_event(RawEvent::PayrollRun(who, now, total));
+            }
+            Ok(())
+        }
+        
+        /// Sets the number of blocks between payroll runs
+        #[weight = TransactionWeight::Basic(SETTING, 1)]
+        fn set_pay_period(origin, blocks: T::BlockNumber) -> Result {
+            ensure_root(origin)?;
+            ensure!(blocks > T::BlockNumber::zero(), "Pay period must be at least one block");
+            <PayPeriod<T>>::put(blocks);
+            Ok(())
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    fn period() -> T::BlockNumber {
+        Self::pay_period().unwrap_or_else(|| T::BlockNumber::sa(DEFAULT_PAY_PERIOD))
+    }
+    
+    fn to_currency(amount: i128) -> CurrencyBalanceOf<T> {
+        let unsigned: u128 = <T::Conversions as Convert<i128, u128>>::convert(amount);
+        <T::Conversions as Convert<u128, CurrencyBalanceOf<T>>>::convert(unsigned)
+    }
+    
+    /// Pays the net pay to the employee and clears the net wages payable.
+    /// Employer: Debit decrease Net wages payable, Credit decrease XTX Balance
+    /// Employee: Debit increase XTX Balance, Credit increase Salary income
+    fn pay(employer: T::AccountId, employee: T::AccountId, net: i128) -> Result {
+        let reference = <<T as Trait>::Accounting as Posting<T::AccountId,T::Hash,T::BlockNumber>>::get_pseudo_random_hash(employer.clone(), employee.clone());
+        let mut entries: Vec<(T::AccountId, u64, i128, bool)> = Vec::new();
+        entries.push((employer.clone(), NET_WAGES_PAYABLE, -net, true));
+        entries.push((employer.clone(), XTX_BALANCE, -net, false));
+        entries.push((employee.clone(), XTX_BALANCE, net, true));
+        entries.push((employee.clone(), SALARY_INCOME, net, false));
+        T::Currency::transfer(&employer, &employee, Self::to_currency(net))?;
+        Self::post(reference, entries)?;
+        
+        Self::deposit_event(RawEvent::PayPaid(employer, employee, net));
+        Ok(())
+    }
+    
+    /// Pays the withheld amounts to the recipient and clears the withholding payable.
+    /// Employer: Debit decrease Payroll withholding payable, Credit decrease XTX Balance
+    fn remit_withholding(employer: T::AccountId, recipient: T::AccountId, amount: i128) -> Result {
+        let reference = <<T as Trait>::Accounting as Posting<T::AccountId,T::Hash,T::BlockNumber>>::get_pseudo_random_hash(employer.clone(), recipient.clone());
+        let mut entries: Vec<(T::AccountId, u64, i128, bool)> = Vec::new();
+        entries.push((employer.clone(), WITHHOLDING_PAYABLE, -amount, true));
+        entries.push((employer.clone(), XTX_BALANCE, -amount, false));
+        T::Currency::transfer(&employer, &recipient, Self::to_currency(amount))?;
+        Self::post(reference, entries)?;
+        
+        Self::deposit_event(RawEvent::WithholdingRemitted(employer, recipient, amount));
+        Ok(())
+    }
+    
+    /// Posts the entries (identity, account, amount, debit). Every entry but the last gets a reversal.
+    fn post(h: T::Hash, entries: Vec<(T::AccountId, u64, i128, bool)>) -> Result {
+        let current_block = <system::Module<T>>::block_number();
+        let current_block_dupe = <system::Module<T>>::block_number();
+        
+        let mut forward_keys = Vec::<(T::AccountId, AccountOf<T>, AccountBalanceOf<T>, bool, T::Hash, T::BlockNumber, T::BlockNumber)>::with_capacity(entries.len());
+        let mut reversal_keys = Vec::<(T::AccountId, AccountOf<T>, AccountBalanceOf<T>, bool, T::Hash, T::BlockNumber, T::BlockNumber)>::with_capacity(entries.len());
+        for (o, a, amount, debit) in entries {
+            let account: AccountOf<T> = <T::Conversions as Convert<u64, AccountOf<T>>>::convert(a);
+            let forward: AccountBalanceOf<T> = <T::Conversions as Convert<i128, AccountBalanceOf<T>>>::convert(amount);
+            let reverse: AccountBalanceOf<T> = <T::Conversions as Convert<i128, AccountBalanceOf<T>>>::convert(-amount);
+            forward_keys.push((o.clone(), account, forward, debit, h, current_block, current_block_dupe));
+            reversal_keys.push((o, account, reverse, !debit, h, current_block, current_block_dupe));
+        }
+        reversal_keys.pop();
+        
+        let track_rev_keys = Vec::<(T::AccountId, AccountOf<T>, AccountBalanceOf<T>, bool, T::Hash, T::BlockNumber, T::BlockNumber)>::new();
+        
+        match <<T as Trait>::Accounting as Posting<T::AccountId,T::Hash,T::BlockNumber>>::handle_multiposting_amounts(forward_keys, reversal_keys, track_rev_keys) {
+            Ok(_) => Ok(()),
+            Err(_e) => {
+                Self::deposit_event(RawEvent::ErrorInAccounting(h));
+                Err("There was an error posting to accounts")
+            },
+        }
+    }
+}
+
+impl<T: Trait> Payroll<T::AccountId, T::Hash> for Module<T> {
+    /// Accrues the pay for authorised time.
+    /// Employer: Debit increase Salaries and wages, Credit increase Net wages payable and Payroll withholding payable
+    fn time_authorised(employer: T::AccountId, worker: T::AccountId, _project: T::Hash, blocks: u64, reference: T::Hash) -> Result {
+        let key = (employer.clone(), worker.clone());
+        let employment = match Self::employment(&key) {
+            Some(e) => e,
+            None => return Ok(()),
+        };
+        ensure!(!<RecordAccruals<T>>::exists(&reference), "Pay has already been accrued for this time record");
+        
+        let gross: i128 = employment.pay_rate.checked_mul(blocks as i128).ok_or("Gross pay overflow")?;
+        if gross == 0 {
+            return Ok(());
+        }
+        let withheld: i128 = gross.checked_mul(employment.withholding as i128).ok_or("Withholding overflow")? / WITHHOLDING_DENOMINATOR;
+        let net: i128 = gross - withheld;
+        
+        let mut accrued = Self::accrued_pay(&key);
+        accrued.net = accrued.net.checked_add(net).ok_or("Accrued pay overflow")?;
+        accrued.withheld = accrued.withheld.checked_add(withheld).ok_or("Accrued pay overflow")?;
+        
+        let mut entries: Vec<(T::AccountId, u64, i128, bool)> = Vec::new();
+        entries.push((employer.clone(), SALARIES_AND_WAGES, gross, true));
+        if withheld > 0 {
+            entries.push((employer.clone(), WITHHOLDING_PAYABLE, withheld, false));
+        }
+        entries.push((employer.clone(), NET_WAGES_PAYABLE, net, false));
+        Self::post(reference, entries)?;
+        
+        <AccruedPay<T>>::insert(&key, accrued);
+        <RecordAccruals<T>>::insert(&reference, (employer.clone(), worker.clone(), gross, withheld));
+        Self::deposit_event(RawEvent::PayAccrued(employer, worker, gross, withheld, reference));
+        Ok(())
+    }
+    
+    /// Reverses the pay accrued for the time record. Pay that was already paid out is recovered from the next accruals.
+    /// Employer: Debit decrease Salaries and wages, Credit decrease Net wages payable and Payroll withholding payable
+    fn time_reset(reference: T::Hash) -> Result {
+        let (employer, worker, gross, withheld) = match Self::record_accrual(&reference) {
+            Some(a) => a,
+            None => return Ok(()),
+        };
+        let net: i128 = gross - withheld;
+        
+        let key = (employer.clone(), worker.clone());
+        let mut accrued = Self::accrued_pay(&key);
+        accrued.net = accrued.net.checked_sub(net).ok_or("Accrued pay overflow")?;
+        accrued.withheld = accrued.withheld.checked_sub(withheld).ok_or("Accrued pay overflow")?;
+        
+        let mut entries: Vec<(T::AccountId, u64, i128, bool)> = Vec::new();
+        entries.push((employer.clone(), SALARIES_AND_WAGES, -gross, true));
+        if withheld > 0 {
+            entries.push((employer.clone(), WITHHOLDING_PAYABLE, -withheld, false));
+        }
+        entries.push((employer.clone(), NET_WAGES_PAYABLE, -net, false));
+        Self::post(reference, entries)?;
+        
+        <AccruedPay<T>>::insert(&key, accrued);
+        <RecordAccruals<T>>::remove(&reference);
+        Self::deposit_event(RawEvent::PayReversed(employer, worker, gross, withheld, reference));
+        Ok(())
+    }
+}
+
+decl_event!(
+    pub enum Event<T>
+    where
+        AccountId = <T as system::Trait>::AccountId,
+        Hash = <T as system::Trait>::Hash,
+        BlockNumber = <T as system::Trait>::BlockNumber,
+    {
+        /// Employer, employee, pay rate, withholding
+        EmployeeSet(AccountId, AccountId, i128, u16),
+        EmployeeRemoved(AccountId, AccountId),
+        /// Employer, employee, gross pay, withheld, time record
+        PayAccrued(AccountId, AccountId, i128, i128, Hash),
+        /// Employer, employee, gross pay, withheld, time record
+        PayReversed(AccountId, AccountId, i128, i128, Hash),
+        /// Employer, employee, net pay
+        PayPaid(AccountId, AccountId, i128),
+        /// Employer, recipient, amount
+        WithholdingRemitted(AccountId, AccountId, i128),
+        /// Employer, employees paid so far, total paid by the batch. Call `run_payroll` again to continue.
+        PayrollBatchPaid(AccountId, u32, i128),
+        /// Employer, block, total paid by the last batch
+        PayrollRun(AccountId, BlockNumber, i128),
+        ErrorInAccounting(Hash),
+    }
+);
@@ -52,6 +52,7 @@
 
 use parity_codec::{Encode};
 use support::{decl_event, decl_module, decl_storage, dispatch::Result, StorageMap, ensure};
+use support::dispatch::TransactionWeight;
 use runtime_primitives::traits::{Convert, Hash}; // Use with node template only
 // use node_primitives::{Convert, Hash}; // Use with full node
 use system::{self, ensure_signed};
@@ -65,7 +66,9 @@ use support::traits::{
 
 // Totem Traits
 use crate::accounting_traits::{ Posting };
-use crate::prefunding_traits::{ Encumbrance };
+use crate::prefunding_traits::{ Closing, Encumbrance };
+use crate::archive_traits::{ Archivable };
+use crate::weights::{ INVOICE, LIST, POSTING, PREFUNDING, READ, SETTLEMENT, WRITE };
 
 // Totem Trait Types
 type AccountOf<T> = <<T as Trait>::Accounting as Posting<<T as system::Trait>::AccountId,<T as system::Trait>::Hash,<T as system::Trait>::BlockNumber>>::Account;
@@ -96,6 +99,7 @@ pub trait Trait: balances::Trait + system::Trait + timestamp::Trait {
     Convert<AccountBalanceOf<Self>, i128> +
     Convert<CurrencyBalanceOf<Self>, u128>;
     type Accounting: Posting<Self::AccountId,Self::Hash,Self::BlockNumber>;
+    type OnClose: Closing<Self::Hash>;
 }
 
 decl_storage! {
@@ -115,6 +119,8 @@ decl_storage! {
         
         // List for convenience
         OwnerPrefundingHashList get(owner_prefunding_hash_list): map T::AccountId => Vec<T::Hash>;
+        // Prefunding references archived by the owner
+        OwnerPrefundingHashListArchive get(owner_prefunding_hash_list_archive): map T::AccountId => Vec<T::Hash>;
         
         // Reference Hash generic status
         // draft(0),
@@ -138,6 +144,7 @@ decl_module! {
         /// Quatity is not relevant 
         /// The prefunded amount remains as an asset of the buyer until the order is accepted
         /// Updates only the accounts of the buyer 
+        #[weight = TransactionWeight::Basic(READ + PREFUNDING, 1)]
         fn prefund_someone(origin, beneficiary: T::AccountId, amount: u128, deadline: T::BlockNumber, tx_uid: T::Hash) -> Result {
             let who = ensure_signed(origin)?;
             // check that the beneficiary is not the sender
@@ -150,13 +157,15 @@ decl_module! {
         /// Creates a single line simple invoice without taxes, tariffs or commissions
         /// This invoice is associated with a prefunded order - therefore needs to provide the hash reference of the order
         /// Updates the accounting for the vendor and the customer
+        #[weight = TransactionWeight::Basic(INVOICE, 1)]
         fn invoice_prefunded_order(origin, payer: T::AccountId, amount: i128, reference: T::Hash, uid: T::Hash) -> Result {
             let who = ensure_signed(origin)?;
-            Self::send_simple_invoice(who.clone(), payer.clone(), amount, reference, uid)?;
+            Self::send_simple_invoice(who.clone(), payer.clone(), amount, reference, 0, uid)?;
             Ok(())
         }
         /// Buyer pays a prefunded order. Needs to supply the correct hash reference
         /// Updates bother the buyer and the vendor accounts 
+        #[weight = TransactionWeight::Basic(SETTLEMENT, 1)]
         fn pay_prefunded_invoice(origin, reference: T::Hash, uid: T::Hash) -> Result {
             let who = ensure_signed(origin)?;
             Self::settle_prefunded_invoice(who.clone(), reference, uid)?;
@@ -164,6 +173,7 @@ decl_module! {
         }
         
         /// Is used by the buyer to recover funds if the vendor does not accept the order by the deadline
+        #[weight = TransactionWeight::Basic(5 * READ + 6 * WRITE + 3 * LIST + 2 * POSTING, 1)]
         fn cancel_prefunded_closed_order(origin, reference: T::Hash, uid: T::Hash) -> Result {
             let who = ensure_signed(origin)?;
             Self::unlock_funds_for_owner(who.clone(), reference, uid)?;
@@ -255,6 +265,10 @@ impl<T: Trait> Module<T> {
         <PrefundingHashOwner<T>>::take(&h);
         <ReferenceStatus<T>>::insert(&h, s); // This sets the status but does not remove the hash
         <OwnerPrefundingHashList<T>>::mutate(&o, |owner_prefunding_hash_list| owner_prefunding_hash_list.retain(|e| e != &h));
+        // The reference is no longer in play, but is kept in the archive so that it can still be found by the owner
+        <OwnerPrefundingHashListArchive<T>>::mutate(&o, |owner_prefunding_hash_list_archive| owner_prefunding_hash_list_archive.push(h.clone()));
+        // Let dependent modules (e.g. the marketplace) release what they hold against the reference
+        <<T as Trait>::OnClose as Closing<T::Hash>>::prefunding_closed(h.clone(), s == 500)?;
         // Issue event
         Self::deposit_event(RawEvent::PrefundingCancelled(o, h));
         Ok(())
@@ -440,7 +454,9 @@ impl<T: Trait> Encumbrance<T::AccountId,T::Hash,T::BlockNumber> for Module<T> {
     /// Simple invoice. Does not include tax jurisdiction, tax amounts, freight, commissions, tariffs, discounts and other extended line item values
     /// must include a connection to the originating reference. 
     /// Invoices cannot be made to parties that haven't asked for something identified by a valid hash
-    fn send_simple_invoice(o: T::AccountId, p: T::AccountId, n: i128, h: T::Hash, u: T::Hash) -> Result {
+    /// The order type decides the buyer's debit: services(0) are expensed as labour, whereas goods(1) and inventory(2)
+    /// clear the goods received not invoiced account that was credited when the goods were received.
+    fn send_simple_invoice(o: T::AccountId, p: T::AccountId, n: i128, h: T::Hash, t: u16, u: T::Hash) -> Result {
         
         // Validate that the hash is indeed assigned to the seller
         match Self::check_ref_beneficiary(o.clone(), h) {
@@ -474,7 +490,10 @@ impl<T: Trait> Encumbrance<T::AccountId,T::Hash,T::BlockNumber> for Module<T> {
         
         // Buyer
         let account_5: AccountOf<T> = <T::Conversions as Convert<u64, AccountOf<T>>>::convert(120200030000000u64); // Credit increase 120200030000000	Accounts payable
-        let account_6: AccountOf<T> = <T::Conversions as Convert<u64, AccountOf<T>>>::convert(250500120000013u64); // Debit  increase 250500120000013	Labour
+        let (account_6, amount_6, reversal_6): (AccountOf<T>, AccountBalanceOf<T>, AccountBalanceOf<T>) = match t {
+            1 | 2 => (<T::Conversions as Convert<u64, AccountOf<T>>>::convert(120200040000000u64), decrease_amount, increase_amount), // Debit  decrease 120200040000000	Goods received not invoiced
+            _ => (<T::Conversions as Convert<u64, AccountOf<T>>>::convert(250500120000013u64), increase_amount, decrease_amount), // Debit  increase 250500120000013	Labour
+        };
         let account_7: AccountOf<T> = <T::Conversions as Convert<u64, AccountOf<T>>>::convert(360600030000000u64); // Debit  increase 360600030000000	Purchase Ledger by Vendor
         let account_8: AccountOf<T> = <T::Conversions as Convert<u64, AccountOf<T>>>::convert(360600070000000u64); // Debit  increase 360600070000000	Purchase Ledger Control       
         
@@ -486,7 +505,7 @@ impl<T: Trait> Encumbrance<T::AccountId,T::Hash,T::BlockNumber> for Module<T> {
         forward_keys.push((o.clone(), account_4, increase_amount, true, h, current_block, current_block_dupe));
         
         forward_keys.push((p.clone(), account_5, increase_amount, false, h, current_block, current_block_dupe));
-        forward_keys.push((p.clone(), account_6, increase_amount, true, h, current_block, current_block_dupe));
+        forward_keys.push((p.clone(), account_6, amount_6, true, h, current_block, current_block_dupe));
         forward_keys.push((p.clone(), account_7, increase_amount, true, h, current_block, current_block_dupe));
         forward_keys.push((p.clone(), account_8, increase_amount, true, h, current_block, current_block_dupe));
         
@@ -498,7 +517,7 @@ impl<T: Trait> Encumbrance<T::AccountId,T::Hash,T::BlockNumber> for Module<T> {
         reversal_keys.push((o.clone(), account_4, decrease_amount, false, h, current_block, current_block_dupe));
         
         reversal_keys.push((p.clone(), account_5, decrease_amount, true, h, current_block, current_block_dupe));
-        reversal_keys.push((p.clone(), account_6, decrease_amount, false, h, current_block, current_block_dupe));
+        reversal_keys.push((p.clone(), account_6, reversal_6, false, h, current_block, current_block_dupe));
         reversal_keys.push((p.clone(), account_7, decrease_amount, false, h, current_block, current_block_dupe));
         
         let track_rev_keys = Vec::<(T::AccountId, AccountOf<T>, AccountBalanceOf<T>, bool, T::Hash, T::BlockNumber, T::BlockNumber)>::with_capacity(9);
@@ -904,6 +923,50 @@ impl<T: Trait> Encumbrance<T::AccountId,T::Hash,T::BlockNumber> for Module<T> {
     }
 }
 
+impl<T: Trait> Archivable<T::AccountId,T::Hash> for Module<T> {
+    /// Only references that are no longer in play can be archived (settled or cancelled).
+    /// These are archived automatically when the lock is released, so this is mostly used to restore them.
+    fn validate_and_archive(o: T::AccountId, h: T::Hash, a: bool) -> Result {
+        match <ReferenceStatus<T>>::get(&h) {
+            50 | 500 => (),
+            _ => return Err("Only settled or cancelled prefunding can be archived"),
+        }
+
+        match a {
+            true => {
+                // Check that the reference is in the active list, otherwise don't update
+                Self::owner_prefunding_hash_list(&o)
+                .into_iter()
+                .find(| x| x == &h)
+                .ok_or("This record has either been archived already or does not exist!")?;
+
+                <OwnerPrefundingHashListArchive<T>>::mutate(&o, |owner_prefunding_hash_list_archive| owner_prefunding_hash_list_archive.push(h.clone()));
+                <OwnerPrefundingHashList<T>>::mutate(&o, |owner_prefunding_hash_list| owner_prefunding_hash_list.retain(|e| e != &h));
+            },
+            false => {
+                // Check that the reference is in the archive, otherwise don't update
+                Self::owner_prefunding_hash_list_archive(&o)
+                .into_iter()
+                .find(| x| x == &h)
+                .ok_or("This record has either been restored already or does not exist!")?;
+
+                <OwnerPrefundingHashList<T>>::mutate(&o, |owner_prefunding_hash_list| owner_prefunding_hash_list.push(h.clone()));
+                <OwnerPrefundingHashListArchive<T>>::mutate(&o, |owner_prefunding_hash_list_archive| owner_prefunding_hash_list_archive.retain(|e| e != &h));
+            },
+        }
+
+        Ok(())
+    }
+
+    fn is_terminal(h: T::Hash) -> bool {
+        // Abandoned/cancelled or settled
+        match <ReferenceStatus<T>>::get(&h) {
+            50 | 500 => true,
+            _ => false,
+        }
+    }
+}
+
 decl_event!(
     pub enum Event<T>
     where
@@ -41,11 +41,21 @@ pub trait Encumbrance<AccountId,Hash,BlockNumber> {
     type UnLocked: Member + Copy;
 
     fn prefunding_for(who: AccountId, recipient: AccountId, amount: u128, deadline: BlockNumber, ref_hash: Hash, uid: Hash) -> Result;
-    fn send_simple_invoice(o: AccountId, p: AccountId, n: i128, h: Hash, uid: Hash) -> Result;
+    fn send_simple_invoice(o: AccountId, p: AccountId, n: i128, h: Hash, t: u16, uid: Hash) -> Result;
     fn settle_prefunded_invoice(o: AccountId, h: Hash, uid: Hash) -> Result;
     fn set_release_state(o: AccountId, o_lock: Self::UnLocked, h: Hash, uid: Hash) -> Result;
     fn unlock_funds_for_owner(o: AccountId, h: Hash, uid: Hash) -> Result;
     fn check_ref_owner(o: AccountId, h: Hash) -> bool;
     fn check_ref_beneficiary(o: AccountId, h: Hash) -> bool;
 
+}
+
+pub trait Closing<Hash> {
+    /// The prefunding for a reference was closed, either settled to the beneficiary or cancelled and returned to the owner.
+    fn prefunding_closed(h: Hash, settled: bool) -> Result;
+}
+
+// Used where nothing depends on the prefunding being closed.
+impl<Hash> Closing<Hash> for () {
+    fn prefunding_closed(_h: Hash, _s: bool) -> Result { Ok(()) }
 }
\ No newline at end of file
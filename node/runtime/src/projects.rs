@@ -36,12 +36,17 @@
 use parity_codec::{Decode, Encode};
 use rstd::prelude::*;
 use support::{decl_event, decl_module, decl_storage, dispatch::Result, ensure, StorageMap};
+use support::dispatch::TransactionWeight;
 use system::{self, ensure_signed};
 
 // Totem traits
-use crate::projects_traits::{ Validating };
+use crate::projects_traits::{ Validating, Roles };
+use crate::archive_traits::{ Archivable, Retention };
+use crate::timekeeping_traits::{ Team };
+use crate::weights::{ LIST, READ, WRITE };
 
 pub type ProjectStatus = u16; // Reference supplied externally
+pub type ProjectRole = u16; // owner(0), manager(100), approver(200), worker(300). Lower values hold more authority.
 
 #[derive(PartialEq, Eq, Clone, Encode, Decode)]
 #[cfg_attr(feature = "std", derive(Debug))]
@@ -53,6 +58,8 @@ pub struct DeletedProject<AccountId, ProjectStatus> {
 
 pub trait Trait: system::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    type Archive: Retention<Self::AccountId,Self::Hash>;
+    type Timekeeping: Team<Self::AccountId,Self::Hash>;
 }
 
 decl_storage! {
@@ -61,6 +68,16 @@ decl_storage! {
         DeletedProjects get(deleted_project): map T::Hash => Vec<DeletedProject<T::AccountId, ProjectStatus>>;
         ProjectHashOwner get(project_hash_owner): map T::Hash => Option<T::AccountId>;
         OwnerProjectsList get(owner_projects_list): map T::AccountId => Vec<T::Hash>;
+        // Projects archived by the owner. These are no longer shown in the owner's active list.
+        OwnerProjectsListArchive get(owner_projects_list_archive): map T::AccountId => Vec<T::Hash>;
+        // Delegated roles on a project. The owner role (0) is implied by ProjectHashOwner and is never stored here.
+        // manager(100) can invite workers, authorise time and change the project status
+        // approver(200) can authorise time
+        // worker(300) is set when a worker joins the project team
+        ProjectMemberRole get(project_member_role): map (T::Hash, T::AccountId) => Option<ProjectRole>;
+        // List of identities holding a delegated role on the project
+        // Note: Currently unbounded Vec!
+        ProjectRoleHolders get(project_role_holders): map T::Hash => Vec<T::AccountId>;
     }
 }
 
@@ -68,6 +85,7 @@ decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
         fn deposit_event<T>() = default;
 
+        #[weight = TransactionWeight::Basic(2 * READ + 2 * WRITE + LIST, 1)]
         fn add_new_project(origin, project_hash: T::Hash) -> Result {
 
             // Check that the project does not exist
@@ -90,6 +108,7 @@ decl_module! {
             Ok(())
         }
 
+        #[weight = TransactionWeight::Basic(3 * READ + 7 * WRITE + 4 * LIST, 1)]
         fn remove_project(origin, project_hash: T::Hash) -> Result {
             ensure!(<ProjectHashStatus<T>>::exists(project_hash.clone()), "The project does not exist!");
 
@@ -113,6 +132,7 @@ decl_module! {
 
             // retain all other projects except the one we want to delete
             <OwnerProjectsList<T>>::mutate(&project_owner, |owner_projects_list| owner_projects_list.retain(|h| h != &project_hash));
+            <OwnerProjectsListArchive<T>>::mutate(&project_owner, |owner_projects_list_archive| owner_projects_list_archive.retain(|h| h != &project_hash));
 
             // remove project from owner
             <ProjectHashOwner<T>>::remove(project_hash.clone());
@@ -120,6 +140,9 @@ decl_module! {
             // remove status record
             <ProjectHashStatus<T>>::remove(project_hash.clone());
 
+            // remove all delegated roles
+            Self::remove_all_roles(project_hash.clone());
+
             // record the fact of deletion by whom
             <DeletedProjects<T>>::mutate(project_hash.clone(), |deleted_project| deleted_project.push(deleted_project_struct));
 
@@ -128,6 +151,7 @@ decl_module! {
             Ok(())
         }
 
+        #[weight = TransactionWeight::Basic(3 * READ + 3 * WRITE + 4 * LIST, 1)]
         fn reassign_project(origin, new_owner: T::AccountId, project_hash: T::Hash) -> Result {
             ensure!(<ProjectHashStatus<T>>::exists(project_hash.clone()), "The project does not exist!");
 
@@ -143,6 +167,11 @@ decl_module! {
 
             // retain all other projects except the one we want to reassign
             <OwnerProjectsList<T>>::mutate(&project_owner, |owner_projects_list| owner_projects_list.retain(|h| h != &project_hash));
+            // an archived project is restored to the active list of the new owner
+            <OwnerProjectsListArchive<T>>::mutate(&project_owner, |owner_projects_list_archive| owner_projects_list_archive.retain(|h| h != &project_hash));
+
+            // The new owner holds every role, so any delegated role they had is no longer needed
+            Self::remove_role(project_hash.clone(), new_owner.clone());
 
             // Set new owner for hash
             <ProjectHashOwner<T>>::insert(project_hash.clone(), &new_owner);
@@ -154,25 +183,26 @@ decl_module! {
 
         }
 
+        #[weight = TransactionWeight::Basic(3 * READ + 2 * WRITE + LIST, 1)]
         fn close_project(origin, project_hash: T::Hash) -> Result {
             ensure!(<ProjectHashStatus<T>>::exists(project_hash.clone()), "The project does not exist!");
 
             let changer = ensure_signed(origin)?;
 
-           // get project owner by hash
-            let project_owner: T::AccountId = Self::project_hash_owner(project_hash.clone()).ok_or("Error fetching project owner")?;
-
             // TODO Implement a sudo for cleaning data in cases where owner is lost
-            // Otherwise onlu the owner can change the data
-            ensure!(project_owner == changer, "You cannot close a project you do not own");
+            // Otherwise only the owner or a manager can change the data
+            ensure!(Self::has_project_role(changer.clone(), project_hash.clone(), 100), "You cannot close a project you do not own or manage");
             let project_status: ProjectStatus = 500;
             <ProjectHashStatus<T>>::insert(project_hash.clone(), &project_status);
 
+            Self::schedule_project_archive(project_hash.clone())?;
+
             Self::deposit_event(RawEvent::ProjectChanged(project_hash, changer, project_status));
 
             Ok(())
         }
 
+        #[weight = TransactionWeight::Basic(3 * READ + WRITE, 1)]
         fn reopen_project(origin, project_hash: T::Hash) -> Result {
             // Can only reopen a project that is in status "closed"
             let project_status: ProjectStatus = match Self::project_hash_status(project_hash.clone()) {
@@ -183,12 +213,9 @@ decl_module! {
 
             let changer = ensure_signed(origin)?;
 
-            // get project owner by hash
-            let project_owner: T::AccountId = Self::project_hash_owner(project_hash.clone()).ok_or("Error fetching project owner")?;
-
             // TODO Implement a sudo for cleaning data in cases where owner is lost
-            // Otherwise only the owner can change the data
-            ensure!(project_owner == changer, "You cannot change a project you do not own");
+            // Otherwise only the owner or a manager can change the data
+            ensure!(Self::has_project_role(changer.clone(), project_hash.clone(), 100), "You cannot change a project you do not own or manage");
 
             <ProjectHashStatus<T>>::insert(project_hash.clone(), &project_status);
 
@@ -197,17 +224,15 @@ decl_module! {
             Ok(())
         }
 
+        #[weight = TransactionWeight::Basic(4 * READ + 2 * WRITE + LIST, 1)]
         fn set_status_project(origin, project_hash: T::Hash, project_status: ProjectStatus) -> Result {
             ensure!(<ProjectHashStatus<T>>::exists(project_hash.clone()), "The project does not exist!");
 
             let changer = ensure_signed(origin)?;
 
-            // get project owner by hash
-            let project_owner: T::AccountId = Self::project_hash_owner(project_hash.clone()).ok_or("Error fetching project owner")?;
-
             // TODO Implement a sudo for cleaning data in cases where owner is lost
-            // Otherwise only the owner can change the data
-            ensure!(project_owner == changer, "You cannot change a project you do not own");
+            // Otherwise only the owner or a manager can change the data
+            ensure!(Self::has_project_role(changer.clone(), project_hash.clone(), 100), "You cannot change a project you do not own or manage");
 
             let current_project_status = Self::project_hash_status(project_hash.clone()).ok_or("Error fetching project status")?;
             // let proposed_project_status: ProjectStatus = project_status.clone();
@@ -253,11 +278,138 @@ decl_module! {
 
             <ProjectHashStatus<T>>::insert(project_hash.clone(), &allowed_project_status);
 
+            match allowed_project_status {
+                300 | 400 | 500 => Self::schedule_project_archive(project_hash.clone())?,
+                _ => (),
+            };
+
             Self::deposit_event(RawEvent::ProjectChanged(project_hash, changer, allowed_project_status));
 
             Ok(())
         }
 
+        /// Delegates a role on the project to another identity.
+        /// The owner can grant any role, a manager can only grant approver(200).
+        /// The worker role(300) is only given when a worker accepts an invitation in timekeeping.
+        /// An existing role can only be changed by someone with more authority than the current holder.
+        #[weight = TransactionWeight::Basic(5 * READ + WRITE + LIST, 1)]
+        fn grant_project_role(origin, project_hash: T::Hash, member: T::AccountId, role: ProjectRole) -> Result {
+            ensure!(Self::is_project_valid(project_hash.clone()), "The project does not exist or is not active!");
+
+            let granter = ensure_signed(origin)?;
+
+            match role {
+                100 | 200 => (),
+                _ => return Err("This role does not exist or cannot be granted."),
+            };
+
+            // Only owners and managers can delegate, and only to a role with less authority than their own
+            let granter_role: ProjectRole = Self::role_of(granter.clone(), project_hash.clone()).ok_or("You have no role on this project")?;
+            ensure!(granter_role <= 100 && granter_role < role, "You cannot grant this role");
+
+            // The owner already holds every role
+            ensure!(!Self::is_project_owner(member.clone(), project_hash.clone()), "The project owner cannot be given a role");
+
+            // Do not allow a manager to change the role of another manager
+            if let Some(current_role) = Self::project_member_role((project_hash.clone(), member.clone())) {
+                ensure!(current_role != role, "This identity already holds this role");
+                ensure!(granter_role < current_role, "You cannot change the role of this identity");
+            };
+
+            Self::set_role(project_hash.clone(), member.clone(), role);
+
+            Self::deposit_event(RawEvent::ProjectRoleGranted(project_hash, member, granter, role));
+
+            Ok(())
+        }
+
+        /// Removes a delegated role from the project.
+        /// Holders can always give up their own role, otherwise the same rules as granting apply.
+        /// The worker role is revoked by removing the worker from the project team in timekeeping,
+        /// so a member who is still on the team falls back to the worker role.
+        #[weight = TransactionWeight::Basic(4 * READ + 2 * WRITE + 2 * LIST, 1)]
+        fn revoke_project_role(origin, project_hash: T::Hash, member: T::AccountId) -> Result {
+            ensure!(<ProjectHashStatus<T>>::exists(project_hash.clone()), "The project does not exist!");
+
+            let revoker = ensure_signed(origin)?;
+
+            let current_role: ProjectRole = Self::project_member_role((project_hash.clone(), member.clone())).ok_or("This identity has no role on this project")?;
+            // Workers also have to leave the project team, which timekeeping takes care of
+            ensure!(current_role != 300, "Workers are removed from the project team in timekeeping");
+
+            if revoker != member {
+                let revoker_role: ProjectRole = Self::role_of(revoker.clone(), project_hash.clone()).ok_or("You have no role on this project")?;
+                ensure!(revoker_role <= 100 && revoker_role < current_role, "You cannot revoke the role of this identity");
+            };
+
+            if <<T as Trait>::Timekeeping as Team<T::AccountId, T::Hash>>::is_project_worker(member.clone(), project_hash.clone()) {
+                Self::set_role(project_hash.clone(), member.clone(), 300);
+            } else {
+                Self::remove_role(project_hash.clone(), member.clone());
+            }
+
+            Self::deposit_event(RawEvent::ProjectRoleRevoked(project_hash, member, revoker, current_role));
+
+            Ok(())
+        }
+
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Returns the role of the identity on the project. The owner always has role 0.
+    fn role_of(o: T::AccountId, h: T::Hash) -> Option<ProjectRole> {
+        match Self::project_hash_owner(h.clone()) {
+            Some(owner) => {
+                if o == owner {
+                    return Some(0);
+                }
+            },
+            None => return None,
+        }
+
+        Self::project_member_role((h, o))
+    }
+
+    /// Queues a project that reached a terminal status for automatic archiving by its owner
+    fn schedule_project_archive(h: T::Hash) -> Result {
+        let owner: T::AccountId = Self::project_hash_owner(h.clone()).ok_or("Error fetching project owner")?;
+        <<T as Trait>::Archive as Retention<T::AccountId, T::Hash>>::schedule_archive(3000, owner, h)
+    }
+
+    fn set_role(h: T::Hash, o: T::AccountId, r: ProjectRole) {
+        let key = (h.clone(), o.clone());
+        if !<ProjectMemberRole<T>>::exists(&key) {
+            <ProjectRoleHolders<T>>::mutate(&h, |project_role_holders| project_role_holders.push(o.clone()));
+        };
+        <ProjectMemberRole<T>>::insert(&key, r);
+    }
+
+    fn remove_role(h: T::Hash, o: T::AccountId) {
+        <ProjectMemberRole<T>>::remove((h.clone(), o.clone()));
+        <ProjectRoleHolders<T>>::mutate(&h, |project_role_holders| project_role_holders.retain(|a| a != &o));
+    }
+
+    fn remove_all_roles(h: T::Hash) {
+        for o in Self::project_role_holders(h.clone()) {
+            <ProjectMemberRole<T>>::remove((h.clone(), o));
+        }
+        <ProjectRoleHolders<T>>::remove(h);
+    }
+
+    /// Returns the owner, status and role holders of a project. Used by the runtime api.
+    pub fn project_summary(h: T::Hash) -> Option<(T::AccountId, ProjectStatus, Vec<(T::AccountId, ProjectRole)>)> {
+        let owner = Self::project_hash_owner(h.clone())?;
+        let status = Self::project_hash_status(h.clone())?;
+        let mut members: Vec<(T::AccountId, ProjectRole)> = Vec::new();
+        members.push((owner.clone(), 0));
+        for o in Self::project_role_holders(h.clone()) {
+            if let Some(r) = Self::project_member_role((h.clone(), o.clone())) {
+                members.push((o, r));
+            }
+        }
+
+        Some((owner, status, members))
     }
 }
 
@@ -267,11 +419,16 @@ decl_event!(
         AccountId = <T as system::Trait>::AccountId,
         Hash = <T as system::Trait>::Hash,
         ProjectStatus = u16,
+        ProjectRole = u16,
     {
         ProjectRegistered(Hash, AccountId),
         ProjectDeleted(Hash, AccountId, AccountId, ProjectStatus),
         ProjectReassigned(Hash, AccountId, AccountId),
         ProjectChanged(Hash, AccountId, ProjectStatus),
+        /// Project, member, granted by, role
+        ProjectRoleGranted(Hash, AccountId, AccountId, ProjectRole),
+        /// Project, member, revoked by, previous role
+        ProjectRoleRevoked(Hash, AccountId, AccountId, ProjectRole),
     }
 );
 
@@ -328,6 +485,79 @@ impl<T: Trait> Validating<T::AccountId,T::Hash> for Module<T> {
 
         return valid;
     }
+
+    fn get_project_owner(h: T::Hash) -> Option<T::AccountId> {
+        Self::project_hash_owner(h)
+    }
+
+    fn has_project_role(o: T::AccountId, h: T::Hash, r: u16) -> bool {
+        // Holders of a role with more authority (lower value) pass the check as well.
+        match Self::role_of(o, h) {
+            Some(role) => role <= r,
+            None => false,
+        }
+    }
+}
+
+impl<T: Trait> Roles<T::AccountId,T::Hash> for Module<T> {
+    fn set_worker_role(o: T::AccountId, h: T::Hash) -> Result {
+        // Never downgrade somebody who already has more authority on the project
+        match Self::role_of(o.clone(), h.clone()) {
+            Some(_) => (),
+            None => Self::set_role(h, o, 300),
+        }
+
+        Ok(())
+    }
+
+    fn remove_worker_role(o: T::AccountId, h: T::Hash) -> Result {
+        match Self::project_member_role((h.clone(), o.clone())) {
+            Some(300) => Self::remove_role(h, o),
+            _ => (),
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: Trait> Archivable<T::AccountId,T::Hash> for Module<T> {
+    fn validate_and_archive(o: T::AccountId, h: T::Hash, a: bool) -> Result {
+        // Only the owner can archive a project
+        ensure!(Self::is_project_owner(o.clone(), h.clone()), "You cannot archive a project you do not own");
+
+        match a {
+            true => {
+                // Check that the project is in the active list, otherwise don't update
+                Self::owner_projects_list(&o)
+                .into_iter()
+                .find(| x| x == &h)
+                .ok_or("This record has either been archived already or does not exist!")?;
+
+                <OwnerProjectsListArchive<T>>::mutate(&o, |owner_projects_list_archive| owner_projects_list_archive.push(h.clone()));
+                <OwnerProjectsList<T>>::mutate(&o, |owner_projects_list| owner_projects_list.retain(|x| x != &h));
+            },
+            false => {
+                // Check that the project is in the archive, otherwise don't update
+                Self::owner_projects_list_archive(&o)
+                .into_iter()
+                .find(| x| x == &h)
+                .ok_or("This record has either been restored already or does not exist!")?;
+
+                <OwnerProjectsList<T>>::mutate(&o, |owner_projects_list| owner_projects_list.push(h.clone()));
+                <OwnerProjectsListArchive<T>>::mutate(&o, |owner_projects_list_archive| owner_projects_list_archive.retain(|x| x != &h));
+            },
+        }
+
+        Ok(())
+    }
+
+    fn is_terminal(h: T::Hash) -> bool {
+        // Abandoned, cancelled or closed
+        match Self::project_hash_status(h) {
+            Some(300) | Some(400) | Some(500) => true,
+            _ => false,
+        }
+    }
 }
 
 /// tests for this module
@@ -368,6 +598,8 @@ mod tests {
     }
     impl Trait for Test {
         type Event = ();
+        type Archive = ();
+        type Timekeeping = ();
     }
     type ProjectModule = Module<Test>;
 
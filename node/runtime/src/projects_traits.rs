@@ -33,10 +33,19 @@
 //! You should have received a copy of the GNU General Public License
 //! along with Totem.  If not, see <http://www.gnu.org/licenses/>.
 
-// use support::{ dispatch::Result };
+use support::{ dispatch::Result };
 
 pub trait Validating<AccountId, Hash> {
     fn is_project_owner(o: AccountId, h: Hash) -> bool;
     fn is_owner_and_project_valid(o: AccountId, h: Hash) -> bool;
     fn is_project_valid(h: Hash) -> bool;
+    fn get_project_owner(h: Hash) -> Option<AccountId>;
+    /// True if the identity holds the role `r` or a role with more authority.
+    /// Roles are owner(0), manager(100), approver(200), worker(300).
+    fn has_project_role(o: AccountId, h: Hash, r: u16) -> bool;
+}
+
+pub trait Roles<AccountId, Hash> {
+    fn set_worker_role(o: AccountId, h: Hash) -> Result;
+    fn remove_worker_role(o: AccountId, h: Hash) -> Result;
 }
\ No newline at end of file
@@ -0,0 +1,239 @@
+//!                              Næ§@@@ÑÉ©
+//!                        æ@@@@@@@@@@@@@@@@@@
+//!                    Ñ@@@@?.?@@@@@@@@@@@@@@@@@@@N
+//!                 ¶@@@@@?^%@@.=@@@@@@@@@@@@@@@@@@@@
+//!               N@@@@@@@?^@@@»^@@@@@@@@@@@@@@@@@@@@@@
+//!               @@@@@@@@?^@@@».............?@@@@@@@@@É
+//!              Ñ@@@@@@@@?^@@@@@@@@@@@@@@@@@@'?@@@@@@@@Ñ
+//!              @@@@@@@@@?^@@@»..............»@@@@@@@@@@
+//!              @@@@@@@@@?^@@@»^@@@@@@@@@@@@@@@@@@@@@@@@
+//!              @@@@@@@@@?^ë@@&.@@@@@@@@@@@@@@@@@@@@@@@@
+//!               @@@@@@@@?^´@@@o.%@@@@@@@@@@@@@@@@@@@@©
+//!                @@@@@@@?.´@@@@@ë.........*.±@@@@@@@æ
+//!                 @@@@@@@@?´.I@@@@@@@@@@@@@@.&@@@@@N
+//!                  N@@@@@@@@@@ë.*=????????=?@@@@@Ñ
+//!                    @@@@@@@@@@@@@@@@@@@@@@@@@@@¶
+//!                        É@@@@@@@@@@@@@@@@Ñ¶
+//!                             Næ§@@@ÑÉ©
+
+//! Copyright 2020 Chris D'Costa
+//! This file is part of Totem Live Accounting.
+//! Author Chris D'Costa email: chris.dcosta@totemaccounting.com
+
+//! Totem is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License, or
+//! (at your option) any later version.
+
+//! Totem is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+
+//! You should have received a copy of the GNU General Public License
+//! along with Totem.  If not, see <http://www.gnu.org/licenses/>.
+
+/// Reputation of the parties to Totem orders.
+/// 
+/// The orders module reports settled and disputed invoices. Settlement issues a feedback form to each party, 
+/// which allows them to rate the other party once for that order. Nobody else can leave feedback.
+/// 
+/// Feedback is given per category: timeliness, quality and payment behaviour. Each rating is weighted by the 
+/// square root of the settled amount of the order, so that larger orders count more without a few large orders 
+/// dominating the score. Scores halve every half-life period, so that recent behaviour counts more than old behaviour.
+/// Scores are queryable through the Totem `ReputationApi`.
+
+use parity_codec::{Decode, Encode};
+use support::{decl_event, decl_module, decl_storage, dispatch::Result, ensure, StorageMap, StorageValue};
+use support::dispatch::TransactionWeight;
+use system::{self, ensure_signed, ensure_root};
+use rstd::prelude::*;
+use runtime_primitives::traits::{As, IntegerSquareRoot, Zero};
+use totem_primitives::ReputationScores;
+
+// Totem crates
+use crate::reputation_traits::{ Reputation, CategoryFeedback, Rating, MAX_RATING };
+use crate::weights::{ READ, SETTING, WRITE };
+
+pub trait Trait: system::Trait {
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+pub type Score = i128;
+pub type Category = u8; // 0 timeliness, 1 quality, 2 payment
+
+const TIMELINESS: Category = 0;
+const QUALITY: Category = 1;
+const PAYMENT: Category = 2;
+
+/// Blocks after which a score halves when no half-life has been set (about 180 days)
+const DEFAULT_HALF_LIFE: u64 = 2592000;
+
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ScoreRecord<BlockNumber> {
+    pub value: Score,
+    pub updated: BlockNumber, // block up to which decay has been applied
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as ReputationModule {
+        CategoryScores get(category_score): map (T::AccountId, Category) => ScoreRecord<T::BlockNumber>;
+        // Feedback that may still be left, by order and rater. The value is the party to be rated and the settled amount.
+        FeedbackForms get(feedback_form): map (T::Hash, T::AccountId) => Option<(T::AccountId, i128)>;
+        // Number of settled orders fulfilled by an account
+        CompletedOrders get(completed_orders): map T::AccountId => u32;
+        // Number of invoices raised by an account that were disputed
+        DisputedOrders get(disputed_orders): map T::AccountId => u32;
+        // Number of blocks after which scores halve
+        HalfLife get(half_life): Option<T::BlockNumber>;
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+        
+        /// Rates the other party to a settled order. Can only be done once per order.
+        #[weight = TransactionWeight::Basic(4 * READ + 4 * WRITE, 1)]
+        fn leave_feedback(origin, order: T::Hash, feedback: CategoryFeedback) -> Result {
+            let who = ensure_signed(origin)?;
+            <Self as Reputation<T::AccountId, T::Hash>>::rate(who, order, feedback)
+        }
+        
+        /// Sets the number of blocks after which scores halve
+        #[weight = TransactionWeight::Basic(SETTING, 1)]
+        fn set_half_life(origin, blocks: T::BlockNumber) -> Result {
+            ensure_root(origin)?;
+            ensure!(blocks > T::BlockNumber::zero(), "Half-life must be at least one block");
+            <HalfLife<T>>::put(blocks);
+            Ok(())
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Reputation scores of an account as of the current block. Used by the runtime API.
+    pub fn scores(who: T::AccountId) -> ReputationScores {
+        let timeliness = Self::current_score(who.clone(), TIMELINESS);
+        let quality = Self::current_score(who.clone(), QUALITY);
+        let payment = Self::current_score(who.clone(), PAYMENT);
+        ReputationScores {
+            timeliness: timeliness,
+            quality: quality,
+            payment: payment,
+            total: timeliness.saturating_add(quality).saturating_add(payment),
+            completed_orders: Self::completed_orders(&who),
+            disputed_orders: Self::disputed_orders(&who),
+        }
+    }
+    
+    fn current_score(who: T::AccountId, category: Category) -> Score {
+        Self::decay(Self::category_score((who, category)), <system::Module<T>>::block_number()).value
+    }
+    
+    fn half_life_period() -> T::BlockNumber {
+        Self::half_life().unwrap_or_else(|| T::BlockNumber::sa(DEFAULT_HALF_LIFE))
+    }
+    
+    /// Halves the score for every full half-life elapsed since it was last decayed.
+    /// The remainder of the period is carried forward so that frequent updates do not slow down the decay.
+    fn decay(record: ScoreRecord<T::BlockNumber>, now: T::BlockNumber) -> ScoreRecord<T::BlockNumber> {
+        if now <= record.updated {
+            return record;
+        }
+        let half_life = Self::half_life_period();
+        let halvings: u64 = ((now - record.updated) / half_life).as_();
+        if halvings == 0 {
+            return record;
+        }
+        let value = if halvings >= 127 { 0 } else { record.value / (1i128 << halvings) };
+        ScoreRecord {
+            value: value,
+            updated: record.updated + half_life * T::BlockNumber::sa(halvings),
+        }
+    }
+    
+    /// Square root of the settled amount, at least one.
+    fn weight(amount: i128) -> Score {
+        let amount: u128 = if amount > 0 { amount as u128 } else { 0 };
+        let root = amount.integer_sqrt();
+        if root == 0 { 1 } else { root as Score }
+    }
+    
+    /// Computes the new score of a category without storing it
+    fn rated_score(who: T::AccountId, category: Category, rating: Rating, weight: Score, now: T::BlockNumber) -> rstd::result::Result<ScoreRecord<T::BlockNumber>, &'static str> {
+        ensure!(rating >= -MAX_RATING && rating <= MAX_RATING, "Rating is out of range");
+        let mut record = Self::decay(Self::category_score((who, category)), now);
+        let delta = (rating as Score).checked_mul(weight).ok_or("Reputation weight overflow")?;
+        record.value = record.value.checked_add(delta).ok_or("Reputation score overflow")?;
+        Ok(record)
+    }
+}
+
+impl<T: Trait> Reputation<T::AccountId, T::Hash> for Module<T> {
+    type Score = Score;
+    type Feedback = CategoryFeedback;
+    
+    fn order_settled(commander: T::AccountId, fulfiller: T::AccountId, order: T::Hash, amount: i128) -> Result {
+        let completed = Self::completed_orders(&fulfiller).checked_add(1).ok_or("Overflow counting completed orders")?;
+        <CompletedOrders<T>>::insert(&fulfiller, completed);
+        
+        <FeedbackForms<T>>::insert((order.clone(), commander.clone()), (fulfiller.clone(), amount));
+        <FeedbackForms<T>>::insert((order.clone(), fulfiller.clone()), (commander.clone(), amount));
+        
+        Self::deposit_event(RawEvent::OrderSettled(order, commander, fulfiller));
+        
+        Ok(())
+    }
+    
+    fn order_disputed(commander: T::AccountId, fulfiller: T::AccountId, order: T::Hash) -> Result {
+        let disputed = Self::disputed_orders(&fulfiller).checked_add(1).ok_or("Overflow counting disputed orders")?;
+        <DisputedOrders<T>>::insert(&fulfiller, disputed);
+        
+        Self::deposit_event(RawEvent::OrderDisputed(order, commander, fulfiller));
+        
+        Ok(())
+    }
+    
+    fn rate(rater: T::AccountId, order: T::Hash, feedback: CategoryFeedback) -> Result {
+        let (ratee, amount) = Self::feedback_form((order.clone(), rater.clone())).ok_or("You cannot leave feedback for this order")?;
+        
+        let weight = Self::weight(amount);
+        let now = <system::Module<T>>::block_number();
+        
+        // all categories are checked before anything is stored
+        let mut updates: Vec<(Category, ScoreRecord<T::BlockNumber>)> = Vec::new();
+        for (category, rating) in [(TIMELINESS, feedback.timeliness), (QUALITY, feedback.quality), (PAYMENT, feedback.payment)].iter() {
+            if *rating != 0 {
+                updates.push((*category, Self::rated_score(ratee.clone(), *category, *rating, weight, now)?));
+            }
+        }
+        
+        for (category, record) in updates {
+            <CategoryScores<T>>::insert((ratee.clone(), category), record);
+        }
+        <FeedbackForms<T>>::remove((order.clone(), rater.clone()));
+        
+        Self::deposit_event(RawEvent::Rated(rater, ratee, order, feedback));
+        
+        Ok(())
+    }
+    
+    fn reputation(who: T::AccountId) -> Self::Score {
+        Self::scores(who).total
+    }
+}
+
+decl_event!(
+    pub enum Event<T>
+    where
+    AccountId = <T as system::Trait>::AccountId,
+    Hash = <T as system::Trait>::Hash,
+    {
+        OrderSettled(Hash, AccountId, AccountId),
+        OrderDisputed(Hash, AccountId, AccountId),
+        // Rater, Ratee, Order, Rating
+        Rated(AccountId, AccountId, Hash, CategoryFeedback),
+    }
+);
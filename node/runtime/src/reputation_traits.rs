@@ -0,0 +1,76 @@
+//!                              Næ§@@@ÑÉ©
+//!                        æ@@@@@@@@@@@@@@@@@@
+//!                    Ñ@@@@?.?@@@@@@@@@@@@@@@@@@@N
+//!                 ¶@@@@@?^%@@.=@@@@@@@@@@@@@@@@@@@@
+//!               N@@@@@@@?^@@@»^@@@@@@@@@@@@@@@@@@@@@@
+//!               @@@@@@@@?^@@@».............?@@@@@@@@@É
+//!              Ñ@@@@@@@@?^@@@@@@@@@@@@@@@@@@'?@@@@@@@@Ñ
+//!              @@@@@@@@@?^@@@»..............»@@@@@@@@@@
+//!              @@@@@@@@@?^@@@»^@@@@@@@@@@@@@@@@@@@@@@@@
+//!              @@@@@@@@@?^ë@@&.@@@@@@@@@@@@@@@@@@@@@@@@
+//!               @@@@@@@@?^´@@@o.%@@@@@@@@@@@@@@@@@@@@©
+//!                @@@@@@@?.´@@@@@ë.........*.±@@@@@@@æ
+//!                 @@@@@@@@?´.I@@@@@@@@@@@@@@.&@@@@@N
+//!                  N@@@@@@@@@@ë.*=????????=?@@@@@Ñ
+//!                    @@@@@@@@@@@@@@@@@@@@@@@@@@@¶
+//!                        É@@@@@@@@@@@@@@@@Ñ¶
+//!                             Næ§@@@ÑÉ©
+
+//! Copyright 2020 Chris D'Costa
+//! This file is part of Totem Live Accounting.
+//! Author Chris D'Costa email: chris.dcosta@totemaccounting.com
+
+//! Totem is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License, or
+//! (at your option) any later version.
+
+//! Totem is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+
+//! You should have received a copy of the GNU General Public License
+//! along with Totem.  If not, see <http://www.gnu.org/licenses/>.
+
+use support::dispatch::Result;
+use parity_codec::{ Codec, Decode, Encode };
+use runtime_primitives::traits::{ Member };
+
+pub trait Reputation<AccountId, Hash> {
+    /// The reputational score of an account.
+    type Score;
+    /// The kind of feedback that is given by the parties to an order.
+    type Feedback: Member + Codec;
+    
+    /// The invoice of an order was settled. Both parties may now leave feedback for each other.
+    fn order_settled(commander: AccountId, fulfiller: AccountId, order: Hash, amount: i128) -> Result;
+    /// The invoice of an order was disputed by the commander.
+    fn order_disputed(commander: AccountId, fulfiller: AccountId, order: Hash) -> Result;
+    /// A party to a settled order rates the other party.
+    fn rate(rater: AccountId, order: Hash, feedback: Self::Feedback) -> Result;
+    /// The current reputation of an account.
+    fn reputation(who: AccountId) -> Self::Score;
+}
+
+/// A rating from -MAX_RATING to MAX_RATING. Zero means no opinion.
+pub type Rating = i8;
+pub const MAX_RATING: Rating = 2;
+
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct CategoryFeedback {
+    pub timeliness: Rating,
+    pub quality: Rating,
+    pub payment: Rating,
+}
+
+// Used where no reputation system is required, for example in tests.
+impl<AccountId, Hash> Reputation<AccountId, Hash> for () {
+    type Score = ();
+    type Feedback = CategoryFeedback;
+    fn order_settled(_c: AccountId, _f: AccountId, _o: Hash, _a: i128) -> Result { Ok(()) }
+    fn order_disputed(_c: AccountId, _f: AccountId, _o: Hash) -> Result { Ok(()) }
+    fn rate(_r: AccountId, _o: Hash, _f: Self::Feedback) -> Result { Ok(()) }
+    fn reputation(_w: AccountId) -> Self::Score { () }
+}
@@ -41,6 +41,7 @@ use support::{
     ensure, 
     StorageMap
 };
+use support::dispatch::TransactionWeight;
 use system::ensure_signed;
 use parity_codec::{Decode, Encode};
 use runtime_primitives::traits::Hash;
@@ -48,12 +49,20 @@ use runtime_primitives::traits::Hash;
 use rstd::prelude::*;
 
 // Totem crates
-use crate::timekeeping_traits::{ Validating };
-use crate::projects_traits::{ Validating as ProjectValidating};
+use crate::timekeeping_traits::{ Validating, Team };
+use crate::archive_traits::{ Archivable, Retention };
+use crate::projects_traits::{ Validating as ProjectValidating, Roles as ProjectRoles };
+use crate::payroll_traits::{ Payroll };
+use crate::weights::{ LIST, POSTING, READ, WRITE };
+
+// Runtime api types
+use totem_primitives::{ ProjectDetails, TeamMember, TimeRecordDetails };
 
 pub trait Trait: system::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
-    type Projects: ProjectValidating<Self::AccountId,Self::Hash>; 
+    type Projects: ProjectValidating<Self::AccountId,Self::Hash> + ProjectRoles<Self::AccountId,Self::Hash>; 
+    type Archive: Retention<Self::AccountId,Self::Hash>;
+    type Payroll: Payroll<Self::AccountId,Self::Hash>;
 }
 
 pub type NumberOfBreaks = u16; // Number of pauses of the timer
@@ -179,14 +188,13 @@ decl_storage! {
 decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
         fn deposit_event<T>() = default;
-        // Project owner invites worker/team member to project
+        // Project owner or manager invites worker/team member to project
+        #[weight = TransactionWeight::Basic(4 * READ + 3 * WRITE + 3 * LIST, 1)]
         fn notify_project_worker(origin, worker: T::AccountId, project_hash: T::Hash) -> Result {
             let who = ensure_signed(origin)?;
 
-            // check project hash exists and is owner by sender
-            // let hash_has_correct_owner = <projects::Module<T>>::check_owner_valid_project(who.clone(), project_hash.clone());
-            let hash_has_correct_owner = <<T as Trait>::Projects as ProjectValidating<T::AccountId, T::Hash>>::is_owner_and_project_valid(who.clone(), project_hash.clone());
-            ensure!(hash_has_correct_owner, "Invalid project or project owner is not correct");
+            // check project hash exists and is owned or managed by sender
+            ensure!(Self::can_act_on_project(who.clone(), project_hash.clone(), 100), "Invalid project or sender is not the project owner or manager");
 
             // ensure that the project has not already been assigned to the worker, and that they have accepted already
             let status_tuple_key = (project_hash.clone(), worker.clone());
@@ -232,6 +240,7 @@ decl_module! {
             Ok(())
         }
         // worker accepts to join the project
+        #[weight = TransactionWeight::Basic(3 * READ + 3 * WRITE + 2 * LIST, 1)]
         fn worker_acceptance_project(origin, project_hash: T::Hash, accepted: AcceptAssignedStatus) -> Result {
             let who = ensure_signed(origin)?;
 
@@ -287,8 +296,39 @@ decl_module! {
 
             Ok(())
         }
+        // Project owner or manager removes a worker from the project team, or the worker leaves it.
+        // This is the only way to revoke the worker role, so that the team and the roles stay in step.
+        #[weight = TransactionWeight::Basic(3 * READ + 4 * WRITE + 3 * LIST, 1)]
+        fn remove_project_worker(origin, project_hash: T::Hash, worker: T::AccountId) -> Result {
+            let who = ensure_signed(origin)?;
+
+            if who != worker {
+                ensure!(Self::can_act_on_project(who.clone(), project_hash.clone(), 100), "Invalid project or sender is not the project owner or manager");
+            }
+
+            let status_tuple_key = (project_hash.clone(), worker.clone());
+            match Self::worker_projects_backlog_status(&status_tuple_key) {
+                Some(true) => (),
+                _ => return Err("This identity is not a worker on this project."),
+            };
+
+            <<T as Trait>::Projects as ProjectRoles<T::AccountId, T::Hash>>::remove_worker_role(worker.clone(), project_hash.clone())?;
+
+            <WorkerProjectsBacklogStatus<T>>::remove(&status_tuple_key);
+            <WorkerProjectsBacklogList<T>>::mutate(&worker, |worker_projects_backlog_list| {
+                worker_projects_backlog_list.retain(|h| h != &project_hash)
+            });
+            <ProjectWorkersList<T>>::mutate(&project_hash, |project_workers_list| {
+                project_workers_list.retain(|h| h != &worker)
+            });
+
+            Self::deposit_event(RawEvent::ProjectWorkerRemoved(worker, project_hash));
+
+            Ok(())
+        }
 
         // Worker submits/resubmits time record
+        #[weight = TransactionWeight::Basic(7 * READ + 7 * WRITE + 3 * LIST + 3 * POSTING, 1)]
         fn submit_time(
             origin,
             project_hash: T::Hash,
@@ -475,10 +515,9 @@ decl_module! {
                                 old_time_record.reason_code = ReasonCodeStruct(0, 0);
                             },
                             300 => {
-                                // The project owner has already accepted, but a correction is agreed with worker.
+                                // The project owner (or an approver) has already accepted, but a correction is agreed with worker.
                                 // therefore reset the record to "draft"
-                                let hash_has_correct_owner = <<T as Trait>::Projects as ProjectValidating<T::AccountId, T::Hash>>::is_owner_and_project_valid(who.clone(), project_hash.clone());
-                                ensure!(hash_has_correct_owner, "Invalid project or project owner is not correct");
+                                ensure!(Self::can_act_on_project(who.clone(), project_hash.clone(), 200), "Invalid project or sender cannot authorise time on this project");
                                 
                                 // ensure that a correct reason is given by project owner
                                 // TODO inspect reason code values, change if necessary
@@ -492,6 +531,9 @@ decl_module! {
                                     // not appropriate to set these codes here. Other specific functions exist.
                                     _ => return Err("This status cannot be set here."),
                                 }
+                                
+                                // the pay accrued on acceptance is accrued again when the corrected record is accepted
+                                <<T as Trait>::Payroll as Payroll<T::AccountId, T::Hash>>::time_reset(original_time_key.clone())?;
                             },
                             400 => return Err("Time record already invoiced. It cannot be changed."),
                             999 => return Err("Time has been blocked by Project Owner. Check the reason code."),
@@ -512,7 +554,8 @@ decl_module! {
             Ok(())
         }
 
-        // Project owner sets authorisation status of time record
+        // Project owner, manager or approver sets authorisation status of time record
+        #[weight = TransactionWeight::Basic(8 * READ + 6 * WRITE + 2 * LIST + 3 * POSTING, 1)]
         fn authorise_time(
             origin,
             worker: T::AccountId,
@@ -523,9 +566,8 @@ decl_module! {
             ) -> Result {
             let who = ensure_signed(origin)?;
 
-            // ensure that the caller is the project owner
-            let hash_has_correct_owner = <<T as Trait>::Projects as ProjectValidating<T::AccountId, T::Hash>>::is_owner_and_project_valid(who.clone(), project_hash.clone());
-            ensure!(hash_has_correct_owner, "Invalid project or project owner is not correct");
+            // ensure that the caller is allowed to authorise time on this project
+            ensure!(Self::can_act_on_project(who.clone(), project_hash.clone(), 200), "Invalid project or sender cannot authorise time on this project");
 
             // prepare new time key
             let original_time_key = input_time_hash.clone();
@@ -535,6 +577,9 @@ decl_module! {
             let mut changing_time_record = Self::time_record(&original_time_key).ok_or("Time record does not exist, or this is not from the worker.")?;
             ensure!(!changing_time_record.locked_status, "You cannot change a locked time record!");
 
+            // the permission was checked against the supplied project, so the record must belong to it
+            ensure!(changing_time_record.project_hash == project_hash, "Time record does not belong to this project");
+
             let proposed_new_status = status_of_record.clone();
 
             match changing_time_record.submit_status {
@@ -559,6 +604,19 @@ decl_module! {
                 _ => return Err("This should not occur. The stored time record has an invalid Status Code"),
             };
 
+            // accepted time of an employee accrues pay for the project owner
+            if changing_time_record.submit_status == 300 {
+                if let Some(owner) = <<T as Trait>::Projects as ProjectValidating<T::AccountId, T::Hash>>::get_project_owner(project_hash.clone()) {
+                    <<T as Trait>::Payroll as Payroll<T::AccountId, T::Hash>>::time_authorised(
+                        owner, 
+                        changing_time_record.worker.clone(), 
+                        project_hash.clone(), 
+                        changing_time_record.total_blocks, 
+                        original_time_key.clone()
+                    )?;
+                };
+            };
+
             // If project has not ever been seen before and time has not been booked then
             // check if record start blocknumber is lower than currently stored value. If so, replace.
             // this is in the event that the project owner initially approves a time record that has later dates than
@@ -577,6 +635,16 @@ decl_module! {
             // perform update on total amounts of time
             Self::update_totals(changing_time_record.worker.clone(), changing_time_record.project_hash.clone(), changing_time_record.total_blocks.clone())?;
 
+            // accepted time can no longer change, queue it for automatic archiving by the worker and the project owner
+            if changing_time_record.submit_status == 300 {
+                <<T as Trait>::Archive as Retention<T::AccountId, T::Hash>>::schedule_archive(4000, changing_time_record.worker.clone(), original_time_key.clone())?;
+                if let Some(owner) = <<T as Trait>::Projects as ProjectValidating<T::AccountId, T::Hash>>::get_project_owner(project_hash.clone()) {
+                    if owner != changing_time_record.worker {
+                        <<T as Trait>::Archive as Retention<T::AccountId, T::Hash>>::schedule_archive(4000, owner, original_time_key.clone())?;
+                    }
+                };
+            };
+
             Self::update_time_record(original_time_key, changing_time_record)?;
             
             Self::deposit_event(RawEvent::SetAuthoriseStatus(who));
@@ -662,6 +730,13 @@ impl<T: Trait> Module<T> {
 
     // TODO Move lock/unlock to private function
 
+    // Checks that the project is active and that the identity holds the role `r` (or one with more authority)
+    // owner(0), manager(100), approver(200), worker(300)
+    fn can_act_on_project(who: T::AccountId, project_hash: T::Hash, r: u16) -> bool {
+        <<T as Trait>::Projects as ProjectValidating<T::AccountId, T::Hash>>::is_project_valid(project_hash.clone()) &&
+        <<T as Trait>::Projects as ProjectValidating<T::AccountId, T::Hash>>::has_project_role(who, project_hash, r)
+    }
+
     // When the worker accepts to work on the project, they are added to the team
     fn store_worker_acceptance(
         project_hash: T::Hash,
@@ -669,6 +744,9 @@ impl<T: Trait> Module<T> {
         
         let accepted_status: AcceptAssignedStatus = true;     
         let status_tuple_key = (project_hash.clone(), who.clone());
+
+        // record the worker role on the project
+        <<T as Trait>::Projects as ProjectRoles<T::AccountId, T::Hash>>::set_worker_role(who.clone(), project_hash.clone())?;
         // add worker to project team
         <ProjectWorkersList<T>>::mutate(&project_hash, |project_workers_list| {
             project_workers_list.push(who.clone())
@@ -871,6 +949,68 @@ impl<T: Trait> Module<T> {
         Ok(())
         
     }
+
+    /// Adds the booked time to the project members and to any workers without a delegated role. 
+    /// Used by the runtime api.
+    pub fn project_details(
+        h: T::Hash, 
+        owner: T::AccountId, 
+        status: u16, 
+        members: Vec<(T::AccountId, u16)>
+    ) -> ProjectDetails<T::AccountId, T::Hash> {
+        let mut team: Vec<TeamMember<T::AccountId>> = members.into_iter().map(|(a, r)| TeamMember {
+            total_blocks: Self::total_blocks_per_project_per_address((a.clone(), h.clone())),
+            account: a,
+            role: r,
+        }).collect();
+
+        for w in Self::project_workers_list(h.clone()) {
+            if !team.iter().any(|m| m.account == w) {
+                team.push(TeamMember {
+                    total_blocks: Self::total_blocks_per_project_per_address((w.clone(), h.clone())),
+                    account: w,
+                    role: 300,
+                });
+            }
+        }
+
+        let time_records = Self::project_time_records_hash_list(h.clone()).len()
+            + Self::project_time_records_hash_list_archive(h.clone()).len();
+
+        ProjectDetails {
+            project_hash: h.clone(),
+            owner: owner,
+            status: status,
+            team: team,
+            total_blocks: Self::total_blocks_per_project(h),
+            time_records: time_records as u32,
+        }
+    }
+
+    /// Returns the active and archived time records of a worker ending within the period. Used by the runtime api.
+    pub fn timesheet(
+        worker: T::AccountId, 
+        from: StartOrEndBlockNumber, 
+        to: StartOrEndBlockNumber
+    ) -> Vec<TimeRecordDetails<T::AccountId, T::Hash>> {
+        Self::worker_time_records_hash_list(worker.clone()).into_iter()
+            .chain(Self::worker_time_records_hash_list_archive(worker).into_iter())
+            .filter_map(|h| Self::time_record(h.clone()).map(|r| (h, r)))
+            .filter(|(_, r)| r.end_block >= from && r.end_block <= to)
+            .map(|(h, r)| TimeRecordDetails {
+                record_hash: h,
+                worker: r.worker,
+                project_hash: r.project_hash,
+                total_blocks: r.total_blocks,
+                locked_status: r.locked_status,
+                submit_status: r.submit_status,
+                posting_period: r.posting_period,
+                start_block: r.start_block,
+                end_block: r.end_block,
+                nr_of_breaks: r.nr_of_breaks,
+            })
+            .collect()
+    }
 }
 
 impl<T: Trait> Validating<T::AccountId,T::Hash> for Module<T> {
@@ -894,35 +1034,46 @@ impl<T: Trait> Validating<T::AccountId,T::Hash> for Module<T> {
         return valid;
     }
 
-    fn validate_and_archive(o: T::AccountId, h: T::Hash, a: bool) -> bool {
-        let who = o.clone();
-        let mut answer: bool = false;
-        // get the time record 
-        // let time_record_key = h.clone();
-        
+    fn time_record_project(h: T::Hash) -> Option<T::Hash> {
+        Self::time_record(h).map(|record| record.project_hash)
+    }
+}
+
+impl<T: Trait> Team<T::AccountId,T::Hash> for Module<T> {
+    fn is_project_worker(o: T::AccountId, h: T::Hash) -> bool {
+        Self::project_workers_list(h).contains(&o)
+    }
+}
+
+impl<T: Trait> Archivable<T::AccountId,T::Hash> for Module<T> {
+    fn validate_and_archive(o: T::AccountId, h: T::Hash, a: bool) -> Result {
         // get existing time record
-        let old_time_record = match Self::time_record(h.clone()) {
-            Some(record) => record,
-            None => return false,
+        let old_time_record = Self::time_record(h.clone()).ok_or("Time record does not exist")?;
+
+        let is_worker: bool = o == old_time_record.worker;
+        let is_manager: bool = <<T as Trait>::Projects as ProjectValidating<T::AccountId, T::Hash>>::has_project_role(o.clone(), old_time_record.project_hash.clone(), 100);
+
+        ensure!(is_worker || is_manager, "You cannot archive a time record you do not own or manage");
+
+        // The worker archives the record from their own list of time records
+        if is_worker {
+            Self::set_worker_time_archive(o.clone(), h.clone(), a)?;
         };
-    
-        // check the owner of the time record. If so process archive.
-        if who == old_time_record.worker {
-            match Self::set_worker_time_archive(who.clone(), h.clone(), a) {
-                Ok(_) => {
-                    // Attempt match on project owner to archive their own record.
-                    if let true = <<T as Trait>::Projects as ProjectValidating<T::AccountId, T::Hash>>::is_project_owner(who.clone(), old_time_record.project_hash) {
-                        match Self::set_project_time_archive(h, old_time_record.project_hash, a) {
-                            Ok(_) => answer = true,
-                            Err(_) => return answer,
-                        }
-                    }
-                },
-                Err(_) => return answer,
-            }
-        }; 
-        
-        return answer;
+
+        // The project owner or manager archives the record from the project list of time records
+        if is_manager {
+            Self::set_project_time_archive(h, old_time_record.project_hash, a)?;
+        };
+
+        Ok(())
+    }
+
+    fn is_terminal(h: T::Hash) -> bool {
+        // Accepted or invoiced
+        match Self::time_record(h) {
+            Some(record) => record.submit_status == 300 || record.submit_status == 400,
+            None => false,
+        }
     }
 }
 
@@ -937,6 +1088,7 @@ decl_event!(
         SubmitedTimeRecord(Hash),
         NotifyProjectWorker(AccountId, Hash),
         WorkerAcceptanceStatus(AccountId, Hash, AcceptAssignedStatus),
+        ProjectWorkerRemoved(AccountId, Hash),
         SetAuthoriseStatus(AccountId),
         InvoiceTime(AccountId),
         PayTime(AccountId),
@@ -37,5 +37,15 @@
 
 pub trait Validating<AccountId, Hash> {
     fn is_time_record_owner(o: AccountId, h: Hash) -> bool;
-    fn validate_and_archive(o: AccountId, h: Hash, a: bool) -> bool;
+    fn time_record_project(h: Hash) -> Option<Hash>;
+}
+
+pub trait Team<AccountId, Hash> {
+    /// True if the identity has joined the project team.
+    fn is_project_worker(o: AccountId, h: Hash) -> bool;
+}
+
+// Used where no project team is kept, for example in tests.
+impl<AccountId, Hash> Team<AccountId, Hash> for () {
+    fn is_project_worker(_o: AccountId, _h: Hash) -> bool { false }
 }
@@ -0,0 +1,70 @@
+//!                              Næ§@@@ÑÉ©
+//!                        æ@@@@@@@@@@@@@@@@@@
+//!                    Ñ@@@@?.?@@@@@@@@@@@@@@@@@@@N
+//!                 ¶@@@@@?^%@@.=@@@@@@@@@@@@@@@@@@@@
+//!               N@@@@@@@?^@@@»^@@@@@@@@@@@@@@@@@@@@@@
+//!               @@@@@@@@?^@@@».............?@@@@@@@@@É
+//!              Ñ@@@@@@@@?^@@@@@@@@@@@@@@@@@@'?@@@@@@@@Ñ
+//!              @@@@@@@@@?^@@@»..............»@@@@@@@@@@
+//!              @@@@@@@@@?^@@@»^@@@@@@@@@@@@@@@@@@@@@@@@
+//!              @@@@@@@@@?^ë@@&.@@@@@@@@@@@@@@@@@@@@@@@@
+//!               @@@@@@@@?^´@@@o.%@@@@@@@@@@@@@@@@@@@@©
+//!                @@@@@@@?.´@@@@@ë.........*.±@@@@@@@æ
+//!                 @@@@@@@@?´.I@@@@@@@@@@@@@@.&@@@@@N
+//!                  N@@@@@@@@@@ë.*=????????=?@@@@@Ñ
+//!                    @@@@@@@@@@@@@@@@@@@@@@@@@@@¶
+//!                        É@@@@@@@@@@@@@@@@Ñ¶
+//!                             Næ§@@@ÑÉ©
+
+//! Copyright 2020 Chris D'Costa
+//! This file is part of Totem Live Accounting.
+//! Author Chris D'Costa email: chris.dcosta@totemaccounting.com
+
+//! Totem is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License, or
+//! (at your option) any later version.
+
+//! Totem is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+
+//! You should have received a copy of the GNU General Public License
+//! along with Totem.  If not, see <http://www.gnu.org/licenses/>.
+
+/// Weight units for the Totem dispatchables.
+/// 
+/// One unit is roughly a microsecond of execution on a reference validator. A call that is not annotated weighs one 
+/// unit per encoded byte, which covers decoding it and the few storage items the generic modules touch. Totem calls 
+/// add a fixed weight for their storage access on top of that: every storage item read or written, every ledger 
+/// posting made and every stored list that is scanned or rewritten. Writes are dearer than reads because they are 
+/// committed to the database at the end of the block. The byte fee and the weight fee are charged separately, so 
+/// the size of a call and the work it causes are both paid for.
+
+use runtime_primitives::weights::Weight;
+
+/// Reading a storage item.
+pub const READ: Weight = 25;
+/// Writing or removing a storage item.
+pub const WRITE: Weight = 100;
+/// Scanning and rewriting a stored list, whose length is unknown when the call is weighed. Charged as two reads and 
+/// two writes to cover decoding and encoding a long list.
+pub const LIST: Weight = 2 * READ + 2 * WRITE;
+/// One posting to the ledgers: reading the posting number and both balances, writing them with the posting detail, 
+/// and appending to the posting list of the account and the account list of the identity.
+pub const POSTING: Weight = 3 * READ + 4 * WRITE + 2 * LIST;
+/// Settings changed by root, which only read the origin and write one item.
+pub const SETTING: Weight = WRITE;
+/// Verifying a signature supplied with the call.
+pub const VERIFY: Weight = 50;
+/// Starting or completing a transaction UUID in bonsai, which also queues its expiry.
+pub const UUID: Weight = 2 * READ + 3 * WRITE + LIST;
+/// Storing an order: the header, its items and the order lists of the parties.
+pub const ORDER: Weight = 2 * READ + 3 * WRITE + 3 * LIST;
+/// Locking the funds of a prefunded order and posting them as a prefunded asset.
+pub const PREFUNDING: Weight = 3 * READ + 6 * WRITE + LIST + 2 * POSTING;
+/// Sending an invoice against a prefunded order, posted in the ledgers of both parties.
+pub const INVOICE: Weight = 3 * READ + 3 * WRITE + 6 * POSTING;
+/// Settling an invoice from the prefunded funds, releasing the lock and transferring the funds.
+pub const SETTLEMENT: Weight = 4 * READ + 6 * WRITE + 8 * POSTING;
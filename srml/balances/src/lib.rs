@@ -178,7 +178,7 @@ use primitives::traits::{
 };
 use rstd::prelude::*;
 use rstd::{cmp, result};
-use srml_support::dispatch::Result;
+use srml_support::dispatch::{Result, Weight};
 use srml_support::traits::{
     Currency, ExistenceRequirement, Imbalance, LockIdentifier, LockableCurrency, MakePayment,
     OnFreeBalanceZero, OnUnbalanced, ReservableCurrency, SignedImbalance, UpdateBalanceOutcome,
@@ -315,6 +315,8 @@ decl_storage! {
         pub TransactionBaseFee get(transaction_base_fee) config(): T::Balance;
         /// The fee to be paid for making a transaction; the per-byte portion.
         pub TransactionByteFee get(transaction_byte_fee) config(): T::Balance;
+        /// The fee to be paid for making a transaction; the portion per unit of weight.
+        pub TransactionWeightFee get(transaction_weight_fee): T::Balance;
 
         /// Information regarding the vesting of a given account.
         pub Vesting get(vesting) build(|config: &GenesisConfig<T, I>| {
@@ -413,6 +415,15 @@ decl_module! {
             Self::set_free_balance(&who, free);
             Self::set_reserved_balance(&who, reserved);
         }
+
+        /// Set the fee charged per unit of transaction weight.
+        ///
+        /// This will alter `TransactionWeightFee` in storage.
+        ///
+        /// The dispatch origin for this call is `root`.
+        fn set_transaction_weight_fee(#[compact] fee: T::Balance) {
+            <TransactionWeightFee<T, I>>::put(fee);
+        }
     }
 }
 
@@ -1090,10 +1101,12 @@ where
 }
 
 impl<T: Trait<I>, I: Instance> MakePayment<T::AccountId> for Module<T, I> {
-    fn make_payment(transactor: &T::AccountId, encoded_len: usize) -> Result {
+    fn make_payment(transactor: &T::AccountId, encoded_len: usize, weight: Weight) -> Result {
         let encoded_len = <T::Balance as As<u64>>::sa(encoded_len as u64);
-        let transaction_fee =
-            Self::transaction_base_fee() + Self::transaction_byte_fee() * encoded_len;
+        let weight = <T::Balance as As<u64>>::sa(weight as u64);
+        let transaction_fee = Self::transaction_base_fee()
+            + Self::transaction_byte_fee() * encoded_len
+            + Self::transaction_weight_fee() * weight;
         let imbalance = Self::withdraw(
             transactor,
             transaction_fee,
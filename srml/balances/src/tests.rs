@@ -186,7 +186,7 @@ fn lock_reasons_should_work() {
                 "account liquidity restrictions prevent withdrawal"
             );
             assert_ok!(<Balances as ReservableCurrency<_>>::reserve(&1, 1));
-            assert_ok!(<Balances as MakePayment<_>>::make_payment(&1, 1));
+            assert_ok!(<Balances as MakePayment<_>>::make_payment(&1, 1, 0));
 
             Balances::set_lock(
                 ID_1,
@@ -200,7 +200,7 @@ fn lock_reasons_should_work() {
                 <Balances as ReservableCurrency<_>>::reserve(&1, 1),
                 "account liquidity restrictions prevent withdrawal"
             );
-            assert_ok!(<Balances as MakePayment<_>>::make_payment(&1, 1));
+            assert_ok!(<Balances as MakePayment<_>>::make_payment(&1, 1, 0));
 
             Balances::set_lock(
                 ID_1,
@@ -212,7 +212,7 @@ fn lock_reasons_should_work() {
             assert_ok!(<Balances as Currency<_>>::transfer(&1, &2, 1));
             assert_ok!(<Balances as ReservableCurrency<_>>::reserve(&1, 1));
             assert_noop!(
-                <Balances as MakePayment<_>>::make_payment(&1, 1),
+                <Balances as MakePayment<_>>::make_payment(&1, 1, 0),
                 "account liquidity restrictions prevent withdrawal"
             );
         },
@@ -759,3 +759,16 @@ fn extra_balance_should_transfer() {
         },
     );
 }
+
+#[test]
+fn transaction_weight_fee_is_set_by_root() {
+    with_externalities(&mut ExtBuilder::default().build(), || {
+        assert_eq!(Balances::transaction_weight_fee(), 0);
+        assert_noop!(
+            Balances::set_transaction_weight_fee(Some(1).into(), 2),
+            "bad origin: expected to be a root origin"
+        );
+        assert_ok!(Balances::set_transaction_weight_fee(system::RawOrigin::Root.into(), 2));
+        assert_eq!(Balances::transaction_weight_fee(), 2);
+    });
+}
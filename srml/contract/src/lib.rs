@@ -102,7 +102,7 @@ use rstd::prelude::*;
 use rstd::marker::PhantomData;
 use parity_codec::{Codec, Encode, Decode};
 use runtime_primitives::traits::{Hash, As, SimpleArithmetic,Bounded, StaticLookup};
-use srml_support::dispatch::{Result, Dispatchable};
+use srml_support::dispatch::{Result, Dispatchable, Weighable};
 use srml_support::{Parameter, StorageMap, StorageValue, decl_module, decl_event, decl_storage, storage::child};
 use srml_support::traits::{OnFreeBalanceZero, OnUnbalanced, Currency};
 use system::{ensure_signed, RawOrigin};
@@ -182,7 +182,7 @@ pub trait Trait: timestamp::Trait {
 	type Currency: Currency<Self::AccountId>;
 
 	/// The outer call dispatch type.
-	type Call: Parameter + Dispatchable<Origin=<Self as system::Trait>::Origin>;
+	type Call: Parameter + Dispatchable<Origin=<Self as system::Trait>::Origin> + Weighable;
 
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
@@ -235,9 +235,13 @@ pub struct DefaultDispatchFeeComputor<T: Trait>(PhantomData<T>);
 impl<T: Trait> ComputeDispatchFee<T::Call, BalanceOf<T>> for DefaultDispatchFeeComputor<T> {
 	fn compute_dispatch_fee(call: &T::Call) -> BalanceOf<T> {
 		let encoded_len = call.using_encoded(|encoded| encoded.len());
+		let weight = call.weight(encoded_len);
 		let base_fee = <Module<T>>::transaction_base_fee();
 		let byte_fee = <Module<T>>::transaction_byte_fee();
-		base_fee + byte_fee * <BalanceOf<T> as As<u64>>::sa(encoded_len as u64)
+		let weight_fee = <Module<T>>::transaction_weight_fee();
+		base_fee
+			+ byte_fee * <BalanceOf<T> as As<u64>>::sa(encoded_len as u64)
+			+ weight_fee * <BalanceOf<T> as As<u64>>::sa(weight as u64)
 	}
 }
 
@@ -260,6 +264,11 @@ decl_module! {
 			Ok(())
 		}
 
+		/// Sets the fee charged per unit of weight of a call dispatched by a contract.
+		fn set_transaction_weight_fee(#[compact] fee: BalanceOf<T>) {
+			<TransactionWeightFee<T>>::put(fee);
+		}
+
 		/// Stores the given binary Wasm code into the chains storage and returns its `codehash`.
 		/// You can instantiate contracts only with stored code.
 		pub fn put_code(
@@ -433,6 +442,8 @@ decl_storage! {
 		TransactionBaseFee get(transaction_base_fee) config(): BalanceOf<T>;
 		/// The fee to be paid for making a transaction; the per-byte portion.
 		TransactionByteFee get(transaction_byte_fee) config(): BalanceOf<T>;
+		/// The fee to be paid for making a transaction; the portion per unit of weight.
+		TransactionWeightFee get(transaction_weight_fee): BalanceOf<T>;
 		/// The fee required to create a contract instance.
 		ContractFee get(contract_fee) config(): BalanceOf<T> = BalanceOf::<T>::sa(21);
 		/// The base fee charged for calling into a contract.
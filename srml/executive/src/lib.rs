@@ -29,10 +29,12 @@ use srml_support::{Dispatchable, traits::MakePayment};
 use parity_codec::{Codec, Encode};
 use system::extrinsics_root;
 use primitives::{ApplyOutcome, ApplyError};
+use primitives::weights::Weighable;
 use primitives::transaction_validity::{TransactionValidity, TransactionPriority, TransactionLongevity};
 
 mod internal {
 	pub const MAX_TRANSACTIONS_SIZE: u32 = 4 * 1024 * 1024;
+	pub const MAX_TRANSACTIONS_WEIGHT: u32 = 4 * 1024 * 1024;
 
 	pub enum ApplyError {
 		BadSignature(&'static str),
@@ -66,7 +68,7 @@ impl<
 	AllModules: OnInitialize<System::BlockNumber> + OnFinalize<System::BlockNumber> + OffchainWorker<System::BlockNumber>,
 > ExecuteBlock<Block> for Executive<System, Block, Context, Payment, AllModules> where
 	Block::Extrinsic: Checkable<Context> + Codec,
-	<Block::Extrinsic as Checkable<Context>>::Checked: Applyable<Index=System::Index, AccountId=System::AccountId>,
+	<Block::Extrinsic as Checkable<Context>>::Checked: Applyable<Index=System::Index, AccountId=System::AccountId> + Weighable,
 	<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call: Dispatchable,
 	<<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call as Dispatchable>::Origin: From<Option<System::AccountId>>
 {
@@ -83,7 +85,7 @@ impl<
 	AllModules: OnInitialize<System::BlockNumber> + OnFinalize<System::BlockNumber> + OffchainWorker<System::BlockNumber>,
 > Executive<System, Block, Context, Payment, AllModules> where
 	Block::Extrinsic: Checkable<Context> + Codec,
-	<Block::Extrinsic as Checkable<Context>>::Checked: Applyable<Index=System::Index, AccountId=System::AccountId>,
+	<Block::Extrinsic as Checkable<Context>>::Checked: Applyable<Index=System::Index, AccountId=System::AccountId> + Weighable,
 	<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call: Dispatchable,
 	<<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call as Dispatchable>::Origin: From<Option<System::AccountId>>
 {
@@ -188,6 +190,12 @@ impl<
 			return Err(internal::ApplyError::FullBlock);
 		}
 
+		// Check the weight of the block if that extrinsic is applied.
+		let weight = xt.weight(encoded_len);
+		if <system::Module<System>>::all_extrinsics_weight().saturating_add(weight) > internal::MAX_TRANSACTIONS_WEIGHT {
+			return Err(internal::ApplyError::FullBlock);
+		}
+
 		if let (Some(sender), Some(index)) = (xt.sender(), xt.index()) {
 			// check index
 			let expected_index = <system::Module<System>>::account_nonce(sender);
@@ -196,7 +204,7 @@ impl<
 			) }
 
 			// pay any fees.
-			Payment::make_payment(sender, encoded_len).map_err(|_| internal::ApplyError::CantPay)?;
+			Payment::make_payment(sender, encoded_len, weight).map_err(|_| internal::ApplyError::CantPay)?;
 
 			// AUDIT: Under no circumstances may this function panic from here onwards.
 
@@ -213,7 +221,7 @@ impl<
 		// decode parameters and dispatch
 		let (f, s) = xt.deconstruct();
 		let r = f.dispatch(s.into());
-		<system::Module<System>>::note_applied_extrinsic(&r, encoded_len as u32);
+		<system::Module<System>>::note_applied_extrinsic(&r, encoded_len as u32, weight);
 
 		r.map(|_| internal::ApplyOutcome::Success).or_else(|e| match e {
 			primitives::BLOCK_FULL => Err(internal::ApplyError::FullBlock),
@@ -266,9 +274,15 @@ impl<
 			Err(_) => return TransactionValidity::Invalid(UNKNOWN_ERROR),
 		};
 
+		// An extrinsic heavier than a whole block can never be included.
+		let weight = xt.weight(encoded_len);
+		if weight > internal::MAX_TRANSACTIONS_WEIGHT {
+			return TransactionValidity::Invalid(ApplyError::FullBlock as i8)
+		}
+
 		if let (Some(sender), Some(index)) = (xt.sender(), xt.index()) {
 			// pay any fees.
-			if Payment::make_payment(sender, encoded_len).is_err() {
+			if Payment::make_payment(sender, encoded_len, weight).is_err() {
 				return TransactionValidity::Invalid(ApplyError::CantPay as i8)
 			}
 
@@ -317,7 +331,7 @@ mod tests {
 	use primitives::BuildStorage;
 	use primitives::traits::{Header as HeaderT, BlakeTwo256, IdentityLookup};
 	use primitives::testing::{Digest, DigestItem, Header, Block};
-	use srml_support::{traits::Currency, impl_outer_origin, impl_outer_event};
+	use srml_support::{traits::Currency, impl_outer_origin, impl_outer_event, StorageValue};
 	use system;
 	use hex_literal::{hex, hex_impl};
 
@@ -481,4 +495,45 @@ mod tests {
 		run_test(false);
 		run_test(true);
 	}
+
+	#[test]
+	fn block_weight_limit_enforced() {
+		let mut t = new_test_ext();
+		let xt = primitives::testing::TestXt(Some(1), 0, Call::transfer(33, 69));
+		let weight = xt.weight(xt.encode().len());
+		with_externalities(&mut t, || {
+			Executive::initialize_block(&Header::new(1, H256::default(), H256::default(), [69u8; 32].into(), Digest::default()));
+			<system::Module<Runtime>>::note_applied_extrinsic(&Ok(()), 0, internal::MAX_TRANSACTIONS_WEIGHT - weight + 1);
+
+			assert!(Executive::apply_extrinsic(xt.clone()).is_err());
+			assert_eq!(<system::Module<Runtime>>::extrinsic_index(), Some(1));
+			assert_eq!(<system::Module<Runtime>>::all_extrinsics_weight(), internal::MAX_TRANSACTIONS_WEIGHT - weight + 1);
+		});
+	}
+
+	#[test]
+	fn weight_is_charged_as_fee() {
+		let mut t = system::GenesisConfig::<Runtime>::default().build_storage().unwrap().0;
+		t.extend(balances::GenesisConfig::<Runtime> {
+			transaction_base_fee: 10,
+			transaction_byte_fee: 1,
+			balances: vec![(1, 1111)],
+			existential_deposit: 0,
+			transfer_fee: 0,
+			creation_fee: 0,
+			vesting: vec![],
+		}.build_storage().unwrap().0);
+		let xt = primitives::testing::TestXt(Some(1), 0, Call::transfer(2, 69));
+		let len = xt.encode().len() as u64;
+		let weight = xt.weight(len as usize) as u64;
+		let mut t = runtime_io::TestExternalities::<Blake2Hasher>::new(t);
+		with_externalities(&mut t, || {
+			<balances::TransactionWeightFee<Runtime>>::put(2);
+			Executive::initialize_block(&Header::new(1, H256::default(), H256::default(),
+				[69u8; 32].into(), Digest::default()));
+			Executive::apply_extrinsic(xt).unwrap();
+			assert_eq!(<balances::Module<Runtime>>::total_balance(&1), 1111 - 69 - 10 - len - 2 * weight);
+			assert_eq!(<system::Module<Runtime>>::all_extrinsics_weight() as u64, weight);
+		});
+	}
 }
@@ -22,6 +22,7 @@ pub use crate::rstd::prelude::{Vec, Clone, Eq, PartialEq};
 pub use std::fmt;
 pub use crate::rstd::result;
 pub use crate::codec::{Codec, Decode, Encode, Input, Output, HasCompact, EncodeAsRef};
+pub use crate::runtime_primitives::weights::{TransactionWeight, Weighable, Weight};
 pub use srml_metadata::{
 	FunctionMetadata, DecodeDifferent, DecodeDifferentArray,
 	FunctionArgumentMetadata, OuterDispatchMetadata, OuterDispatchCall
@@ -92,6 +93,12 @@ impl<T> Parameter for T where T: Codec + Clone + Eq {}
 /// The `on_initialize` and `on_finalize` functions are special, since it can either take no
 /// parameters, or one parameter, which has the runtime's block number type.
 ///
+/// ### Weights
+///
+/// Every function may be annotated with a `TransactionWeight` after its documentation, e.g.
+/// `#[weight = TransactionWeight::Basic(10_000, 1)]`. Functions without an annotation weigh
+/// `TransactionWeight::default()`. The generated `Call` enum implements `Weighable`.
+///
 /// ### Module with instances
 ///
 /// decl_module! support modules with instances with the following syntax: (DefaultInstance type is
@@ -313,6 +320,7 @@ macro_rules! decl_module {
 		{ $( $offchain:tt )* }
 		[ $($t:tt)* ]
 		$(#[doc = $doc_attr:tt])*
+		#[weight = $weight:expr]
 		$fn_vis:vis fn $fn_name:ident(
 			$origin:ident $(, $(#[$codec_attr:ident])* $param_name:ident : $param:ty)*
 		) $( -> $result:ty )* { $( $impl:tt )* }
@@ -329,6 +337,7 @@ macro_rules! decl_module {
 			[
 				$($t)*
 				$(#[doc = $doc_attr])*
+				#[weight = $weight]
 				$fn_vis fn $fn_name(
 					$origin $( , $(#[$codec_attr])* $param_name : $param )*
 				) $( -> $result )* { $( $impl )* }
@@ -347,6 +356,7 @@ macro_rules! decl_module {
 		{ $( $offchain:tt )* }
 		[ $($t:tt)* ]
 		$(#[doc = $doc_attr:tt])*
+		$(#[weight = $weight:expr])?
 		$fn_vis:vis fn $fn_name:ident(
 			$origin:ident : T::Origin $(, $(#[$codec_attr:ident])* $param_name:ident : $param:ty)*
 		) $( -> $result:ty )* { $( $impl:tt )* }
@@ -368,6 +378,7 @@ macro_rules! decl_module {
 		{ $( $offchain:tt )* }
 		[ $($t:tt)* ]
 		$(#[doc = $doc_attr:tt])*
+		$(#[weight = $weight:expr])?
 		$fn_vis:vis fn $fn_name:ident(
 			origin : $origin:ty $(, $(#[$codec_attr:ident])* $param_name:ident : $param:ty)*
 		) $( -> $result:ty )* { $( $impl:tt )* }
@@ -389,6 +400,7 @@ macro_rules! decl_module {
 		{ $( $offchain:tt )* }
 		[ $($t:tt)* ]
 		$(#[doc = $doc_attr:tt])*
+		#[weight = $weight:expr]
 		$fn_vis:vis fn $fn_name:ident(
 			$( $(#[$codec_attr:ident])* $param_name:ident : $param:ty),*
 		) $( -> $result:ty )* { $( $impl:tt )* }
@@ -405,6 +417,7 @@ macro_rules! decl_module {
 			[
 				$($t)*
 				$(#[doc = $doc_attr])*
+				#[weight = $weight]
 				$fn_vis fn $fn_name(
 					root $( , $(#[$codec_attr])* $param_name : $param )*
 				) $( -> $result )* { $( $impl )* }
@@ -413,6 +426,35 @@ macro_rules! decl_module {
 			$($rest)*
 		);
 	};
+	// Functions without a weight annotation get the default weight.
+	(@normalize
+		$(#[$attr:meta])*
+		pub struct $mod_type:ident<$trait_instance:ident: $trait_name:ident$(<I>, $instance:ident: $instantiable:path $(= $module_default_instance:path)?)?>
+		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
+		{ $( $deposit_event:tt )* }
+		{ $( $on_initialize:tt )* }
+		{ $( $on_finalize:tt )* }
+		{ $( $offchain:tt )* }
+		[ $($t:tt)* ]
+		$(#[doc = $doc_attr:tt])*
+		$fn_vis:vis fn $fn_name:ident( $( $params:tt )* ) $( -> $result:ty )* { $( $impl:tt )* }
+		$($rest:tt)*
+	) => {
+		$crate::decl_module!(@normalize
+			$(#[$attr])*
+			pub struct $mod_type<$trait_instance: $trait_name$(<I>, $instance: $instantiable $(= $module_default_instance)?)?>
+			for enum $call_type where origin: $origin_type, system = $system
+			{ $( $deposit_event )* }
+			{ $( $on_initialize )* }
+			{ $( $on_finalize )* }
+			{ $( $offchain )* }
+			[ $($t)* ]
+			$(#[doc = $doc_attr])*
+			#[weight = $crate::dispatch::TransactionWeight::default()]
+			$fn_vis fn $fn_name( $( $params )* ) $( -> $result )* { $( $impl )* }
+			$($rest)*
+		);
+	};
 	(@normalize
 		$(#[$attr:meta])*
 		pub struct $mod_type:ident<$trait_instance:ident: $trait_name:ident$(<I>, I: $instantiable:path $(= $module_default_instance:path)?)?>
@@ -657,6 +699,7 @@ macro_rules! decl_module {
 		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident {
 			$(
 				$(#[doc = $doc_attr:tt])*
+				#[weight = $weight:expr]
 				$fn_vis:vis fn $fn_name:ident(
 					$from:ident $( , $(#[$codec_attr:ident])* $param_name:ident : $param:ty)*
 				) $( -> $result:ty )* { $( $impl:tt )* }
@@ -841,6 +884,18 @@ macro_rules! decl_module {
 				}
 			}
 		}
+		impl<$trait_instance: $trait_name $(<I>, $instance: $instantiable)?> $crate::dispatch::Weighable
+			for $call_type<$trait_instance $(, $instance)?>
+		{
+			fn weight(&self, _len: usize) -> $crate::dispatch::Weight {
+				match self {
+					$(
+						$call_type::$fn_name(..) => $crate::dispatch::Weighable::weight(&$weight, _len),
+					)*
+					$call_type::__PhantomItem(_, _) => { unreachable!("__PhantomItem should never be used.") },
+				}
+			}
+		}
 		impl<$trait_instance: $trait_name $(<I>, $instance: $instantiable)?> $crate::dispatch::Callable
 			for $mod_type<$trait_instance $(, $instance)?>
 		{
@@ -1020,6 +1075,15 @@ macro_rules! impl_outer_dispatch {
 				}
 			}
 		}
+		impl $crate::dispatch::Weighable for $call_type {
+			fn weight(&self, len: usize) -> $crate::dispatch::Weight {
+				match self {
+					$(
+						$call_type::$camelcase(call) => call.weight(len),
+					)*
+				}
+			}
+		}
 		$(
 			impl $crate::dispatch::IsSubType<$camelcase> for $call_type {
 				fn is_aux_sub_type(&self) -> Option<&<$camelcase as $crate::dispatch::Callable>::Call> {
@@ -1198,6 +1262,7 @@ mod tests {
 			fn aux_0(_origin) -> Result { unreachable!() }
 			fn aux_1(_origin, #[compact] _data: u32) -> Result { unreachable!() }
 			fn aux_2(_origin, _data: i32, _data2: String) -> Result { unreachable!() }
+			#[weight = TransactionWeight::Basic(10, 2)]
 			fn aux_3() -> Result { unreachable!() }
 			fn aux_4(_data: i32) -> Result { unreachable!() }
 
@@ -1276,6 +1341,14 @@ mod tests {
 		assert_eq!(encoded.len(), 2);
 	}
 
+	#[test]
+	fn weight_annotation() {
+		let annotated: Call<TraitImpl> = Call::aux_3();
+		let unannotated: Call<TraitImpl> = Call::aux_4(0);
+		assert_eq!(annotated.weight(5), 20);
+		assert_eq!(unannotated.weight(5), 5);
+	}
+
 	#[test]
 	#[should_panic(expected = "on_initialize")]
 	fn on_initialize_should_work() {
@@ -21,6 +21,7 @@ use crate::codec::{Codec, Encode, Decode};
 use crate::runtime_primitives::traits::{
 	MaybeSerializeDebug, SimpleArithmetic, As
 };
+use crate::runtime_primitives::weights::Weight;
 use super::for_each_tuple;
 
 /// The account with the given id was killed.
@@ -70,13 +71,13 @@ pub enum UpdateBalanceOutcome {
 ///
 /// It operates over a single generic `AccountId` type.
 pub trait MakePayment<AccountId> {
-	/// Make transaction payment from `who` for an extrinsic of encoded length
-	/// `encoded_len` bytes. Return `Ok` iff the payment was successful.
-	fn make_payment(who: &AccountId, encoded_len: usize) -> Result<(), &'static str>;
+	/// Make transaction payment from `who` for an extrinsic of `encoded_len` bytes and weight `weight`.
+	/// Return `Ok` iff the payment was successful.
+	fn make_payment(who: &AccountId, encoded_len: usize, weight: Weight) -> Result<(), &'static str>;
 }
 
 impl<T> MakePayment<T> for () {
-	fn make_payment(_: &T, _: usize) -> Result<(), &'static str> { Ok(()) }
+	fn make_payment(_: &T, _: usize, _: Weight) -> Result<(), &'static str> { Ok(()) }
 }
 
 /// Handler for when some currency "account" decreased in balance for
@@ -337,6 +338,18 @@ pub trait Currency<AccountId> {
 		value: Self::Balance,
 	) -> Self::PositiveImbalance;
 
+	/// Moves the funds of a negative imbalance into the free balance of `who`, creating the
+	/// account if needed. The imbalance is used up, so no funds are burned or minted.
+	///
+	/// Infallible.
+	fn resolve_creating(
+		who: &AccountId,
+		value: Self::NegativeImbalance,
+	) {
+		let v = value.peek();
+		drop(value.offset(Self::deposit_creating(who, v)));
+	}
+
 	/// Ensure an account's free balance equals some value; this will create the account
 	/// if needed.
 	///
@@ -78,6 +78,7 @@ use rstd::map;
 use primitives::traits::{self, CheckEqual, SimpleArithmetic, SimpleBitOps, Zero, One, Bounded, Lookup,
 	Hash, Member, MaybeDisplay, EnsureOrigin, Digest as DigestT, As, CurrentHeight, BlockNumberToHash,
 	MaybeSerializeDebugButNotDeserialize, MaybeSerializeDebug, StaticLookup};
+use primitives::weights::Weight;
 use substrate_primitives::storage::well_known_keys;
 use srml_support::{storage, StorageValue, StorageMap, Parameter, decl_module, decl_event,
 	decl_storage, for_each_tuple};
@@ -305,6 +306,8 @@ decl_storage! {
 		ExtrinsicCount: Option<u32>;
 		/// Total length in bytes for all extrinsics put together, for the current block.
 		AllExtrinsicsLen: Option<u32>;
+		/// Total weight of all extrinsics put together, for the current block.
+		AllExtrinsicsWeight: Option<Weight>;
 		/// Map of block numbers to block hashes.
 		pub BlockHash get(block_hash) build(|_| vec![(T::BlockNumber::zero(), hash69())]): map T::BlockNumber => T::Hash;
 		/// Extrinsics data for the current block (maps extrinsic's index to its data).
@@ -394,6 +397,11 @@ impl<T: Trait> Module<T> {
 		<AllExtrinsicsLen<T>>::get().unwrap_or_default()
 	}
 
+	/// Gets the total weight of all executed extrinsics.
+	pub fn all_extrinsics_weight() -> Weight {
+		<AllExtrinsicsWeight<T>>::get().unwrap_or_default()
+	}
+
 	/// Start the execution of a particular block.
 	pub fn initialize(number: &T::BlockNumber, parent_hash: &T::Hash, txs_root: &T::Hash) {
 		// populate environment.
@@ -411,6 +419,7 @@ impl<T: Trait> Module<T> {
 		<RandomSeed<T>>::kill();
 		<ExtrinsicCount<T>>::kill();
 		<AllExtrinsicsLen<T>>::kill();
+		<AllExtrinsicsWeight<T>>::kill();
 
 		let number = <Number<T>>::take();
 		let parent_hash = <ParentHash<T>>::take();
@@ -504,7 +513,7 @@ impl<T: Trait> Module<T> {
 	}
 
 	/// To be called immediately after an extrinsic has been applied.
-	pub fn note_applied_extrinsic(r: &Result<(), &'static str>, encoded_len: u32) {
+	pub fn note_applied_extrinsic(r: &Result<(), &'static str>, encoded_len: u32, weight: Weight) {
 		Self::deposit_event(match r {
 			Ok(_) => Event::ExtrinsicSuccess,
 			Err(_) => Event::ExtrinsicFailed,
@@ -512,9 +521,11 @@ impl<T: Trait> Module<T> {
 
 		let next_extrinsic_index = Self::extrinsic_index().unwrap_or_default() + 1u32;
 		let total_length = encoded_len.saturating_add(Self::all_extrinsics_len());
+		let total_weight = weight.saturating_add(Self::all_extrinsics_weight());
 
 		storage::unhashed::put(well_known_keys::EXTRINSIC_INDEX, &next_extrinsic_index);
 		<AllExtrinsicsLen<T>>::put(&total_length);
+		<AllExtrinsicsWeight<T>>::put(&total_weight);
 	}
 
 	/// To be called immediately after `note_applied_extrinsic` of the last extrinsic of the block
@@ -618,8 +629,8 @@ mod tests {
 
 			System::initialize(&2, &[0u8; 32].into(), &[0u8; 32].into());
 			System::deposit_event(42u16);
-			System::note_applied_extrinsic(&Ok(()), 0);
-			System::note_applied_extrinsic(&Err(""), 0);
+			System::note_applied_extrinsic(&Ok(()), 0, 0);
+			System::note_applied_extrinsic(&Err(""), 0, 0);
 			System::note_finished_extrinsics();
 			System::deposit_event(3u16);
 			System::finalize();
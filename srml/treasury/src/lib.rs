@@ -256,6 +256,13 @@ impl<T: Trait> OnDilution<BalanceOf<T>> for Module<T> {
 	}
 }
 
+impl<T: Trait> OnUnbalanced<NegativeImbalanceOf<T>> for Module<T> {
+	fn on_unbalanced(amount: NegativeImbalanceOf<T>) {
+		// The funds leave circulation here and are minted again when spent.
+		<Pot<T>>::mutate(|x| *x += amount.peek());
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -477,4 +484,13 @@ mod tests {
 			assert_eq!(Treasury::pot(), 25);
 		});
 	}
+
+	#[test]
+	fn unbalanced_funds_go_to_pot() {
+		with_externalities(&mut new_test_ext(), || {
+			Treasury::on_unbalanced(balances::NegativeImbalance::new(10));
+			assert_eq!(Treasury::pot(), 10);
+			assert_eq!(Balances::total_issuance(), 190);
+		});
+	}
 }
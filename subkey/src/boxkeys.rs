@@ -0,0 +1,297 @@
+// Copyright 2018-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Client side of the boxkeys registration and verification flow.
+//!
+//! A bundle holds the seeds of a NaCl box keypair and of a signing key. The payloads
+//! produced here are JSON argument arrays for `register_keys`, `rotate_keys` and
+//! `auto_verification`, and for the challenge issuer's `issue_challenge`, ready to be
+//! signed with `sign-transaction`.
+
+use parity_codec::{Decode, Encode};
+use rand::{RngCore, rngs::OsRng};
+use serde_json::{json, Value};
+use sodalite::{box_, box_keypair_seed, box_open, BoxNonce, BoxPublicKey, BoxSecretKey};
+use sr_primitives::{MultiSignature, MultiSigner};
+use substrate_primitives::{blake2_256, ed25519, sr25519, H256, Pair};
+
+/// Leading zero bytes of a NaCl box plaintext and ciphertext.
+const ZERO_BYTES: usize = 32;
+const BOX_ZERO_BYTES: usize = 16;
+
+/// Signature scheme of a bundle's signing key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scheme {
+	Ed25519,
+	Sr25519,
+}
+
+impl Scheme {
+	fn name(self) -> &'static str {
+		match self {
+			Scheme::Ed25519 => "ed25519",
+			Scheme::Sr25519 => "sr25519",
+		}
+	}
+}
+
+/// The secret material of a boxkeys user.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bundle {
+	pub box_seed: [u8; 32],
+	pub scheme: Scheme,
+	pub sign_seed: [u8; 32],
+}
+
+impl Bundle {
+	/// Generate a bundle from fresh randomness.
+	pub fn generate(scheme: Scheme) -> Self {
+		let mut rng = OsRng::new().expect("OS randomness is available; qed");
+		let mut bundle = Bundle { box_seed: [0u8; 32], scheme, sign_seed: [0u8; 32] };
+		rng.fill_bytes(&mut bundle.box_seed);
+		rng.fill_bytes(&mut bundle.sign_seed);
+		bundle
+	}
+
+	/// The NaCl box keypair.
+	pub fn box_keypair(&self) -> (BoxPublicKey, BoxSecretKey) {
+		let mut public: BoxPublicKey = Default::default();
+		let mut secret: BoxSecretKey = Default::default();
+		box_keypair_seed(&mut public, &mut secret, &self.box_seed);
+		(public, secret)
+	}
+
+	/// The public signing key.
+	pub fn signer(&self) -> MultiSigner {
+		match self.scheme {
+			Scheme::Ed25519 => MultiSigner::Ed25519(ed25519::Pair::from_seed(self.sign_seed).public()),
+			Scheme::Sr25519 => MultiSigner::Sr25519(sr25519::Pair::from_seed(self.sign_seed).public()),
+		}
+	}
+
+	/// Sign `message` with the signing key.
+	pub fn sign(&self, message: &[u8]) -> MultiSignature {
+		match self.scheme {
+			Scheme::Ed25519 => MultiSignature::Ed25519(ed25519::Pair::from_seed(self.sign_seed).sign(message)),
+			Scheme::Sr25519 => MultiSignature::Sr25519(sr25519::Pair::from_seed(self.sign_seed).sign(message)),
+		}
+	}
+
+	/// The bundle as JSON, including the public keys for reference.
+	pub fn to_json(&self) -> Value {
+		json!({
+			"box_seed": hex_string(&self.box_seed),
+			"box_public": hex_string(&self.box_keypair().0),
+			"sign_scheme": self.scheme.name(),
+			"sign_seed": hex_string(&self.sign_seed),
+			"sign_public": signer_json(&self.signer()),
+		})
+	}
+
+	/// Read a bundle written by `to_json`.
+	pub fn from_json(value: &Value) -> Result<Self, String> {
+		let seed = |name: &str| -> Result<[u8; 32], String> {
+			let s = value[name].as_str().ok_or_else(|| format!("Bundle is missing `{}`", name))?;
+			let bytes = hex::decode(s.trim_start_matches("0x")).map_err(|_| format!("Invalid hex in `{}`", name))?;
+			if bytes.len() != 32 {
+				return Err(format!("`{}` must be 32 bytes", name));
+			}
+			let mut seed = [0u8; 32];
+			seed.copy_from_slice(&bytes);
+			Ok(seed)
+		};
+		let scheme = match value["sign_scheme"].as_str() {
+			Some("ed25519") => Scheme::Ed25519,
+			Some("sr25519") => Scheme::Sr25519,
+			_ => return Err("Bundle `sign_scheme` must be ed25519 or sr25519".into()),
+		};
+		Ok(Bundle { box_seed: seed("box_seed")?, scheme, sign_seed: seed("sign_seed")? })
+	}
+}
+
+/// The user hash for `user`: used as is when it is a `0x`-prefixed 32 byte hex string,
+/// otherwise the blake2-256 hash of the given user id.
+pub fn user_hash(user: &str) -> H256 {
+	if user.starts_with("0x") {
+		if let Ok(bytes) = hex::decode(&user[2..]) {
+			if bytes.len() == 32 {
+				return H256::from_slice(&bytes);
+			}
+		}
+	}
+	H256::from(blake2_256(user.as_bytes()))
+}
+
+/// Arguments for `register_keys`, or for `rotate_keys` when the current keys are given.
+///
+/// The signed data is the encoded (user hash, encryption key, signing key, nonce). A first
+/// registration is signed by the new signing key, a rotation by the current one.
+pub fn register_payload(bundle: &Bundle, user_hash: H256, current: Option<&Bundle>) -> Value {
+	let mut nonce: BoxNonce = [0u8; 24];
+	OsRng::new().expect("OS randomness is available; qed").fill_bytes(&mut nonce);
+
+	let pub_enc_key = H256::from(bundle.box_keypair().0);
+	let pub_sign_key = bundle.signer();
+	let signed_data = (user_hash, pub_enc_key, pub_sign_key.clone(), nonce).encode();
+	let signature = current.unwrap_or(bundle).sign(&signed_data);
+
+	json!([
+		hex_string(user_hash.as_bytes()),
+		hex_string(pub_enc_key.as_bytes()),
+		signer_json(&pub_sign_key),
+		hex_string(&nonce),
+		signature_json(&signature),
+	])
+}
+
+/// Arguments for `issue_challenge`: a fresh random challenge for the user hash, encrypted
+/// with an ephemeral key to the claimed encryption key `pub_enc_key`.
+pub fn challenge_payload(user_hash: H256, pub_enc_key: H256) -> Value {
+	let mut rng = OsRng::new().expect("OS randomness is available; qed");
+	let mut challenge = [0u8; 32];
+	let mut ephemeral_seed = [0u8; 32];
+	let mut nonce: BoxNonce = [0u8; 24];
+	rng.fill_bytes(&mut challenge);
+	rng.fill_bytes(&mut ephemeral_seed);
+	rng.fill_bytes(&mut nonce);
+
+	let mut ephemeral_public: BoxPublicKey = [0u8; 32];
+	let mut ephemeral_secret: BoxSecretKey = [0u8; 32];
+	box_keypair_seed(&mut ephemeral_public, &mut ephemeral_secret, &ephemeral_seed);
+
+	let mut plain_text = vec![0u8; ZERO_BYTES];
+	plain_text.extend_from_slice(&challenge);
+	let mut cipher_text = vec![0u8; plain_text.len()];
+	box_(&mut cipher_text, &plain_text, &nonce, pub_enc_key.as_fixed_bytes(), &ephemeral_secret)
+		.expect("the plaintext starts with the box zero bytes; qed");
+
+	json!([
+		hex_string(user_hash.as_bytes()),
+		hex_string(&ephemeral_public),
+		hex_string(&nonce),
+		hex_string(&cipher_text[BOX_ZERO_BYTES..]),
+		hex_string(&blake2_256(&challenge)),
+	])
+}
+
+/// Arguments for `auto_verification`, given the `Challenges` storage value for the user hash.
+///
+/// The challenge is decrypted with the bundle's box key and signed with its signing key.
+pub fn verification_payload(bundle: &Bundle, user_hash: H256, challenge: &[u8]) -> Result<Value, String> {
+	// ephemeral key, nonce, ciphertext, challenge hash and expiry block of the challenge
+	let (ephemeral_key, nonce, data, challenge_hash, _expires): (H256, BoxNonce, Vec<u8>, H256, u64) =
+		Decode::decode(&mut &challenge[..]).ok_or("Invalid challenge encoding")?;
+
+	let mut cipher_text = vec![0u8; BOX_ZERO_BYTES];
+	cipher_text.extend_from_slice(&data);
+	let mut plain_text = vec![0u8; cipher_text.len()];
+	box_open(&mut plain_text, &cipher_text, &nonce, ephemeral_key.as_fixed_bytes(), &bundle.box_keypair().1)
+		.map_err(|_| "The challenge cannot be decrypted with this bundle's box key")?;
+	if plain_text.len() < ZERO_BYTES {
+		return Err("The challenge is too short".into());
+	}
+	let decrypted = plain_text.split_off(ZERO_BYTES);
+	if H256::from(blake2_256(&decrypted)) != challenge_hash {
+		return Err("The decrypted challenge does not match the challenge hash".into());
+	}
+
+	Ok(json!([
+		hex_string(user_hash.as_bytes()),
+		hex_string(&decrypted),
+		signature_json(&bundle.sign(&decrypted)),
+	]))
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+	format!("0x{}", hex::encode(bytes))
+}
+
+fn signer_json(signer: &MultiSigner) -> Value {
+	match signer {
+		MultiSigner::Ed25519(public) => json!({ "ed25519": hex_string(public.as_ref()) }),
+		MultiSigner::Sr25519(public) => json!({ "sr25519": hex_string(public.as_ref()) }),
+	}
+}
+
+fn signature_json(signature: &MultiSignature) -> Value {
+	match signature {
+		MultiSignature::Ed25519(signature) => json!({ "ed25519": hex_string(signature.as_ref()) }),
+		MultiSignature::Sr25519(signature) => json!({ "sr25519": hex_string(signature.as_ref()) }),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sr_primitives::traits::Verify;
+
+	fn bytes(value: &Value) -> Vec<u8> {
+		hex::decode(&value.as_str().unwrap()[2..]).unwrap()
+	}
+
+	fn signature(value: &Value) -> MultiSignature {
+		let sig = bytes(&value["sr25519"]);
+		MultiSignature::Sr25519(sr25519::Signature::from_slice(&sig))
+	}
+
+	#[test]
+	fn bundle_json_round_trips() {
+		let bundle = Bundle::generate(Scheme::Ed25519);
+		assert_eq!(Bundle::from_json(&bundle.to_json()), Ok(bundle));
+	}
+
+	#[test]
+	fn register_payload_is_signed_by_the_right_key() {
+		let current = Bundle::generate(Scheme::Sr25519);
+		let next = Bundle::generate(Scheme::Sr25519);
+		let user = user_hash("alice@example.com");
+
+		let payload = register_payload(&next, user, Some(&current));
+		let mut nonce: BoxNonce = [0u8; 24];
+		nonce.copy_from_slice(&bytes(&payload[3]));
+		let signed_data = (user, H256::from(next.box_keypair().0), next.signer(), nonce).encode();
+
+		assert!(signature(&payload[4]).verify(&signed_data[..], &current.signer()));
+		assert!(!signature(&payload[4]).verify(&signed_data[..], &next.signer()));
+	}
+
+	#[test]
+	fn verification_payload_answers_the_issued_challenge() {
+		let bundle = Bundle::generate(Scheme::Sr25519);
+		let user = user_hash("0x1111111111111111111111111111111111111111111111111111111111111111");
+		assert_eq!(user, H256::repeat_byte(0x11));
+
+		// store the issued challenge the way the runtime does
+		let issued = challenge_payload(user, H256::from(bundle.box_keypair().0));
+		assert_eq!(bytes(&issued[0]), user.as_bytes().to_vec());
+		let mut nonce: BoxNonce = [0u8; 24];
+		nonce.copy_from_slice(&bytes(&issued[2]));
+		let challenge_hash = H256::from_slice(&bytes(&issued[4]));
+		let stored = (
+			H256::from_slice(&bytes(&issued[1])), nonce, bytes(&issued[3]), challenge_hash, 10u64,
+		).encode();
+
+		let payload = verification_payload(&bundle, user, &stored).unwrap();
+		let decrypted = bytes(&payload[1]);
+		assert_eq!(decrypted.len(), 32);
+		assert_eq!(H256::from(blake2_256(&decrypted)), challenge_hash);
+		assert!(signature(&payload[2]).verify(&decrypted[..], &bundle.signer()));
+
+		let other = Bundle::generate(Scheme::Sr25519);
+		assert!(verification_payload(&other, user, &stored).is_err());
+		assert!(verification_payload(&bundle, user, &[0u8; 4]).is_err());
+	}
+}
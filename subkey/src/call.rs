@@ -0,0 +1,368 @@
+// Copyright 2018-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Building runtime calls from JSON arguments.
+//!
+//! Module and call indices as well as argument types are read from the node runtime's
+//! metadata. Arguments are SCALE-encoded according to their declared type and the result
+//! is decoded as a `Call`, so anything that does not match the runtime is rejected.
+
+use node_runtime::{Call, Runtime};
+use parity_codec::{Compact, Decode, Encode};
+use serde_json::Value;
+use srml_metadata::{DecodeDifferent, RuntimeMetadata, RuntimeMetadataPrefixed};
+use substrate_primitives::{crypto::Ss58Codec, sr25519};
+
+/// Build a `Call` for `module::call` from a JSON array of arguments.
+///
+/// Module names are the names used in the runtime metadata (e.g. `orders`, `prefunding`,
+/// `timekeeping`); the case and a trailing `Module` are ignored.
+pub fn encode_call(module: &str, call: &str, args: &Value) -> Result<Call, String> {
+	let metadata = RuntimeMetadataPrefixed::decode(&mut &Runtime::metadata().encode()[..])
+		.ok_or("Runtime metadata could not be decoded")?;
+	let modules = match metadata.1 {
+		RuntimeMetadata::V4(metadata) => decoded(metadata.modules),
+		_ => return Err("Unsupported runtime metadata version".into()),
+	};
+	let args = args.as_array().ok_or("Call arguments must be a JSON array")?;
+
+	let wanted = module.to_lowercase();
+	let wanted = wanted.trim_end_matches("module");
+	let mut module_index = 0u8;
+	for module_metadata in modules {
+		let calls = match module_metadata.calls {
+			Some(calls) => decoded(calls),
+			None => continue,
+		};
+		if decoded(module_metadata.name).to_lowercase() != wanted {
+			module_index += 1;
+			continue;
+		}
+
+		let (call_index, function) = calls.into_iter()
+			.enumerate()
+			.find(|(_, function)| decoded_ref(&function.name) == call)
+			.ok_or_else(|| format!("Unknown call `{}` in module `{}`", call, module))?;
+		let arguments = decoded(function.arguments);
+		if arguments.len() != args.len() {
+			return Err(format!("`{}` takes {} arguments, {} given", call, arguments.len(), args.len()));
+		}
+
+		let mut encoded = vec![module_index, call_index as u8];
+		for (argument, value) in arguments.iter().zip(args) {
+			let ty = normalise(decoded_ref(&argument.ty));
+			encode_value(&ty, value, &mut encoded)
+				.map_err(|e| format!("Invalid argument `{}`: {}", decoded_ref(&argument.name), e))?;
+		}
+
+		return Call::decode(&mut &encoded[..])
+			.filter(|decoded| decoded.encode() == encoded)
+			.ok_or_else(|| format!("Arguments do not encode a valid `{}::{}` call", module, call));
+	}
+
+	Err(format!("Unknown module `{}`", module))
+}
+
+fn decoded<B, O>(value: DecodeDifferent<B, O>) -> O {
+	match value {
+		DecodeDifferent::Decoded(value) => value,
+		DecodeDifferent::Encode(_) => unreachable!("metadata was decoded from its encoding; qed"),
+	}
+}
+
+fn decoded_ref<B, O>(value: &DecodeDifferent<B, O>) -> &O {
+	match value {
+		DecodeDifferent::Decoded(value) => value,
+		DecodeDifferent::Encode(_) => unreachable!("metadata was decoded from its encoding; qed"),
+	}
+}
+
+/// Strip whitespace and `T::` qualifiers from a type as written in `decl_module!`.
+fn normalise(ty: &str) -> String {
+	ty.chars().filter(|c| !c.is_whitespace()).collect::<String>().replace("T::", "")
+}
+
+/// The underlying type of the runtime's type aliases.
+fn alias(ty: &str) -> Option<&'static str> {
+	Some(match ty {
+		"Balance" | "BalanceOf<T>" | "ComparisonAmounts" => "u128",
+		"BlockNumber" | "Moment" | "Index" | "NumberOfBlocks" | "StartOrEndBlockNumber" => "u64",
+		"Perbill" | "Permill" | "ListingId" => "u32",
+		"RecordType" | "ProjectStatus" | "ProjectRole" | "ApprovalStatus" | "OrderStatus" | "StatusOfTimeRecord"
+			| "PostingPeriod" | "NumberOfBreaks" | "ReasonCode" | "ReasonCodeType" | "Withholding" | "Share" => "u16",
+		"DepreciationMethod" | "LifeUnit" => "u8",
+		"Rating" => "i8",
+		"PayRate" => "i128",
+		"AcceptAssignedStatus" | "UnLocked" => "bool",
+		"UserNameHash" | "EncryptPublicKey" | "H256" => "Hash",
+		"AuthorityId" | "SessionKey" => "[u8;32]",
+		"EncryptNonce" | "BoxNonce" => "[u8;24]",
+		"KeySignature" => "MultiSignature",
+		"SignedBy" => "MultiSigner",
+		"<LookupasStaticLookup>::Source" => "Address",
+		_ => return None,
+	})
+}
+
+/// Fields of the structs taken by Totem calls, in declaration order.
+fn fields(ty: &str) -> Option<&'static [(&'static str, &'static str)]> {
+	Some(match ty {
+		"OrderItem<Hash>" => &[("product", "Hash"), ("unit_price", "i128"), ("quantity", "u128"), ("unit_of_measure", "u16")],
+		"TXKeysL<Hash>" => &[("record_id", "Hash"), ("parent_id", "Hash"), ("bonsai_token", "Hash"), ("tx_uid", "Hash")],
+		"TXKeysM<Hash>" => &[("record_id", "Hash"), ("bonsai_token", "Hash"), ("tx_uid", "Hash")],
+		"TXKeysS<Hash>" => &[("bonsai_token", "Hash"), ("tx_uid", "Hash")],
+		"ReasonCodeStruct" => &[("reason_code", "u16"), ("reason_code_type", "u16")],
+		"CategoryFeedback" => &[("timeliness", "i8"), ("quality", "i8"), ("payment", "i8")],
+		_ => return None,
+	})
+}
+
+fn encode_value(ty: &str, value: &Value, out: &mut Vec<u8>) -> Result<(), String> {
+	if let Some(ty) = alias(ty) {
+		return encode_value(ty, value, out);
+	}
+	if let Some(fields) = fields(ty) {
+		return encode_struct(fields, value, out);
+	}
+	if let Some(inner) = generic(ty, "Vec") {
+		if inner == "u8" {
+			bytes(value)?.encode_to(out);
+			return Ok(());
+		}
+		let items = value.as_array().ok_or("expected a JSON array")?;
+		Compact(items.len() as u32).encode_to(out);
+		return items.iter().try_for_each(|item| encode_value(inner, item, out));
+	}
+	if let Some(inner) = generic(ty, "Option") {
+		return match value {
+			Value::Null => Ok(out.push(0)),
+			value => {
+				out.push(1);
+				encode_value(inner, value, out)
+			}
+		};
+	}
+	if let Some(inner) = generic(ty, "Compact") {
+		if inner.starts_with('i') {
+			return Err("compact signed integers are not supported".into());
+		}
+		Compact(unsigned(value)?).encode_to(out);
+		return Ok(());
+	}
+	if ty.starts_with('(') && ty.ends_with(')') {
+		let types = split_top_level(&ty[1..ty.len() - 1]);
+		let items = value.as_array().ok_or("expected a JSON array for a tuple")?;
+		if items.len() != types.len() {
+			return Err(format!("expected a tuple of {} values", types.len()));
+		}
+		return types.iter().zip(items).try_for_each(|(ty, item)| encode_value(ty, item, out));
+	}
+	if ty.starts_with("[u8;") && ty.ends_with(']') {
+		let len: usize = ty[4..ty.len() - 1].parse().map_err(|_| format!("unsupported type `{}`", ty))?;
+		out.extend(fixed_bytes(value, len)?);
+		return Ok(());
+	}
+
+	match ty {
+		"bool" => out.push(value.as_bool().ok_or("expected true or false")? as u8),
+		"u8" => out.push(in_range(unsigned(value)?, u8::max_value() as u128)? as u8),
+		"u16" => (in_range(unsigned(value)?, u16::max_value() as u128)? as u16).encode_to(out),
+		"u32" => (in_range(unsigned(value)?, u32::max_value() as u128)? as u32).encode_to(out),
+		"u64" => (in_range(unsigned(value)?, u64::max_value() as u128)? as u64).encode_to(out),
+		"u128" => unsigned(value)?.encode_to(out),
+		"i8" => (signed_in_range(signed(value)?, i8::min_value() as i128, i8::max_value() as i128)? as i8).encode_to(out),
+		"i16" => (signed_in_range(signed(value)?, i16::min_value() as i128, i16::max_value() as i128)? as i16).encode_to(out),
+		"i32" => (signed_in_range(signed(value)?, i32::min_value() as i128, i32::max_value() as i128)? as i32).encode_to(out),
+		"i64" => (signed_in_range(signed(value)?, i64::min_value() as i128, i64::max_value() as i128)? as i64).encode_to(out),
+		"i128" => signed(value)?.encode_to(out),
+		"Hash" => out.extend(fixed_bytes(value, 32)?),
+		"AccountId" => out.extend(account(value)?),
+		"Address" => {
+			// `Address::Id` is encoded with a `0xff` prefix.
+			out.push(0xff);
+			out.extend(account(value)?);
+		}
+		"MultiSignature" => {
+			let (index, value) = variant(value)?;
+			out.push(index);
+			out.extend(fixed_bytes(value, 64)?);
+		}
+		"MultiSigner" => {
+			let (index, value) = variant(value)?;
+			out.push(index);
+			out.extend(account(value)?);
+		}
+		_ => return Err(format!("unsupported type `{}`", ty)),
+	}
+	Ok(())
+}
+
+/// Encode a struct given as a JSON object keyed by field name or as an array in field order.
+fn encode_struct(fields: &[(&str, &str)], value: &Value, out: &mut Vec<u8>) -> Result<(), String> {
+	match value {
+		Value::Object(map) => fields.iter().try_for_each(|(name, ty)| {
+			let field = map.get(*name).ok_or_else(|| format!("missing field `{}`", name))?;
+			encode_value(ty, field, out).map_err(|e| format!("field `{}`: {}", name, e))
+		}),
+		Value::Array(items) if items.len() == fields.len() => fields.iter()
+			.zip(items)
+			.try_for_each(|((_, ty), item)| encode_value(ty, item, out)),
+		_ => Err(format!("expected an object with fields {:?}", fields.iter().map(|f| f.0).collect::<Vec<_>>())),
+	}
+}
+
+/// The type parameter of `name<...>`, if `ty` is of that form.
+fn generic<'a>(ty: &'a str, name: &str) -> Option<&'a str> {
+	if ty.starts_with(name) && ty[name.len()..].starts_with('<') && ty.ends_with('>') {
+		Some(&ty[name.len() + 1..ty.len() - 1])
+	} else {
+		None
+	}
+}
+
+/// Split a comma separated type list, ignoring commas nested in `<>`, `()` or `[]`.
+fn split_top_level(types: &str) -> Vec<&str> {
+	let mut depth = 0;
+	let mut start = 0;
+	let mut result = Vec::new();
+	for (i, c) in types.char_indices() {
+		match c {
+			'<' | '(' | '[' => depth += 1,
+			'>' | ')' | ']' => depth -= 1,
+			',' if depth == 0 => {
+				result.push(&types[start..i]);
+				start = i + 1;
+			}
+			_ => {}
+		}
+	}
+	if start < types.len() {
+		result.push(&types[start..]);
+	}
+	result
+}
+
+/// Integers may be given as JSON numbers or, for values beyond 64 bits, as decimal strings.
+fn unsigned(value: &Value) -> Result<u128, String> {
+	match value {
+		Value::Number(n) => n.as_u64().map(Into::into).ok_or_else(|| format!("expected an unsigned integer, got {}", n)),
+		Value::String(s) => s.parse().map_err(|_| format!("expected an unsigned integer, got `{}`", s)),
+		_ => Err("expected an unsigned integer".into()),
+	}
+}
+
+fn signed(value: &Value) -> Result<i128, String> {
+	match value {
+		Value::Number(n) => n.as_i64().map(Into::into).ok_or_else(|| format!("expected an integer, got {}", n)),
+		Value::String(s) => s.parse().map_err(|_| format!("expected an integer, got `{}`", s)),
+		_ => Err("expected an integer".into()),
+	}
+}
+
+fn in_range(value: u128, max: u128) -> Result<u128, String> {
+	if value > max {
+		Err(format!("{} is larger than {}", value, max))
+	} else {
+		Ok(value)
+	}
+}
+
+fn signed_in_range(value: i128, min: i128, max: i128) -> Result<i128, String> {
+	if value < min || value > max {
+		Err(format!("{} is outside {}..={}", value, min, max))
+	} else {
+		Ok(value)
+	}
+}
+
+/// Byte vectors are given as `0x`-prefixed hex or as a plain UTF-8 string.
+fn bytes(value: &Value) -> Result<Vec<u8>, String> {
+	let s = value.as_str().ok_or("expected a string")?;
+	if s.starts_with("0x") {
+		hex::decode(&s[2..]).map_err(|_| format!("invalid hex `{}`", s))
+	} else {
+		Ok(s.as_bytes().to_vec())
+	}
+}
+
+fn fixed_bytes(value: &Value, len: usize) -> Result<Vec<u8>, String> {
+	let s = value.as_str().ok_or("expected a hex string")?;
+	let bytes = hex::decode(s.trim_start_matches("0x")).map_err(|_| format!("invalid hex `{}`", s))?;
+	if bytes.len() != len {
+		return Err(format!("expected {} bytes, got {}", len, bytes.len()));
+	}
+	Ok(bytes)
+}
+
+/// Accounts are given as SS58 addresses or as `0x`-prefixed public keys.
+fn account(value: &Value) -> Result<Vec<u8>, String> {
+	let s = value.as_str().ok_or("expected an SS58 address or hex public key")?;
+	if s.starts_with("0x") {
+		return fixed_bytes(value, 32);
+	}
+	sr25519::Public::from_ss58check(s)
+		.map(|public| public.0.to_vec())
+		.map_err(|_| format!("invalid SS58 address `{}`", s))
+}
+
+/// Signature scheme variants are given as `{"ed25519": ...}` or `{"sr25519": ...}`.
+fn variant(value: &Value) -> Result<(u8, &Value), String> {
+	let map = value.as_object().filter(|map| map.len() == 1)
+		.ok_or("expected an object with a single `ed25519` or `sr25519` key")?;
+	let (scheme, value) = map.iter().next().expect("map has exactly one entry; qed");
+	match scheme.to_lowercase().as_str() {
+		"ed25519" => Ok((0, value)),
+		"sr25519" => Ok((1, value)),
+		_ => Err(format!("unknown signature scheme `{}`", scheme)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use node_runtime::BalancesCall;
+	use serde_json::json;
+	use substrate_primitives::Pair;
+
+	#[test]
+	fn encodes_balances_transfer() {
+		let to = sr25519::Pair::from_string("//Bob", None).unwrap().public();
+		let call = encode_call("balances", "transfer", &json!([to.to_ss58check(), "1000"])).unwrap();
+		assert_eq!(call, Call::Balances(BalancesCall::transfer(to.into(), 1000)));
+	}
+
+	#[test]
+	fn encodes_totem_calls() {
+		let hash = format!("0x{}", "11".repeat(32));
+		let call = encode_call("OrdersModule", "change_approval", &json!([hash, 1, hash, hash])).unwrap();
+		assert!(match call { Call::OrdersModule(_) => true, _ => false });
+
+		let item = json!({ "product": hash, "unit_price": 10, "quantity": "5", "unit_of_measure": 1 });
+		let call = encode_call("orders", "change_spfso", &json!([
+			format!("0x{}", "22".repeat(32)), format!("0x{}", "33".repeat(32)), -10, 100, 200, item, hash, hash, hash
+		])).unwrap();
+		assert!(match call { Call::OrdersModule(_) => true, _ => false });
+	}
+
+	#[test]
+	fn rejects_invalid_arguments() {
+		let hash = format!("0x{}", "11".repeat(32));
+		assert!(encode_call("orders", "change_approval", &json!([hash])).is_err());
+		assert!(encode_call("orders", "change_approval", &json!([hash, 70000, hash, hash])).is_err());
+		assert!(encode_call("orders", "no_such_call", &json!([])).is_err());
+		assert!(encode_call("no_such_module", "transfer", &json!([])).is_err());
+	}
+}
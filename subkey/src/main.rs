@@ -22,7 +22,7 @@ extern crate substrate_bip39;
 extern crate rustc_hex;
 #[macro_use] extern crate hex_literal;
 
-use std::io::{stdin, Read};
+use std::{fs, io::{stdin, Read}};
 use clap::load_yaml;
 use rand::{RngCore, rngs::OsRng};
 use substrate_bip39::mini_secret_from_entropy;
@@ -34,6 +34,8 @@ use schnorrkel::keys::MiniSecretKey;
 use node_primitives::{Balance, Index, Hash};
 use node_runtime::{Call, UncheckedExtrinsic, BalancesCall};
 
+mod boxkeys;
+mod call;
 mod vanity;
 
 trait Crypto {
@@ -149,6 +151,7 @@ fn execute<C: Crypto<Seed=[u8; 32]>>(matches: clap::ArgMatches) where
 	<<C as Crypto>::Pair as Pair>::Public: Sized + AsRef<[u8]> + Ss58Codec + AsRef<<<C as Crypto>::Pair as Pair>::Public>,
 {
 	let password = matches.value_of("password");
+	let is_ed25519 = matches.is_present("ed25519");
 	match matches.subcommand() {
 		("generate", Some(_matches)) => {
 			// create a new randomly generated mnemonic phrase
@@ -201,31 +204,103 @@ fn execute<C: Crypto<Seed=[u8; 32]>>(matches: clap::ArgMatches) where
 
 			let function = Call::Balances(BalancesCall::transfer(to.into(), amount));
 
-			let genesis_hash: Hash = match matches.value_of("genesis").unwrap_or("alex") {
-				"elm" => hex!["10c08714a10c7da78f40a60f6f732cf0dba97acfb5e2035445b032386157d5c3"].into(),
-				"alex" => hex!["dcd1346701ca8396496e52aa2785b1748deb6db09551b72159dcb3e08991025b"].into(),
-				h => hex::decode(h).ok().and_then(|x| Decode::decode(&mut &x[..])).expect("Invalid genesis hash or unrecognised chain identifier"),
-			};
-
+			let genesis_hash = genesis_hash(matches);
 			println!("Using a genesis hash of {}", HexDisplay::from(&genesis_hash.as_ref()));
 
-			let era = Era::immortal();
-			let raw_payload = (Compact(index), function, era, genesis_hash);
-			let signature = raw_payload.using_encoded(|payload| if payload.len() > 256 {
-				signer.sign(&blake2_256(payload)[..])
-			} else {
-				println!("Signing {}", HexDisplay::from(&payload));
-				signer.sign(payload)
-			});
-			let extrinsic = UncheckedExtrinsic::new_signed(
-				index,
-				raw_payload.1,
-				signer.public().into(),
-				signature.into(),
-				era,
-			);
+			let extrinsic = sign_extrinsic(&signer, index, function, Era::immortal(), genesis_hash);
+			println!("0x{}", hex::encode(&extrinsic.encode()));
+		}
+		("sign-transaction", Some(matches)) => {
+			let signer = matches.value_of("suri")
+				.expect("parameter is required; thus it can't be None; qed");
+			let signer = Sr25519::pair_from_suri(signer, password);
+
+			let module = matches.value_of("module")
+				.expect("parameter is required; thus it can't be None; qed");
+			let call_name = matches.value_of("call")
+				.expect("parameter is required; thus it can't be None; qed");
+			let args = matches.value_of("args")
+				.expect("parameter is required; thus it can't be None; qed");
+			let args = serde_json::from_str(args)
+				.expect("Invalid 'args' parameter; expecting a JSON array.");
+			let function = call::encode_call(module, call_name, &args)
+				.unwrap_or_else(|e| panic!("{}", e));
+
+			let index = matches.value_of("index")
+				.expect("parameter is required; thus it can't be None; qed");
+			let index = str::parse::<Index>(index)
+				.expect("Invalid 'index' parameter; expecting an integer.");
+
+			let (era, checkpoint) = match matches.value_of("era-period") {
+				Some(period) => {
+					let period = str::parse::<u64>(period)
+						.expect("Invalid 'era-period' parameter; expecting an integer.");
+					let block = matches.value_of("era-block")
+						.map(|b| str::parse::<u64>(b).expect("Invalid 'era-block' parameter; expecting an integer."))
+						.expect("A mortal era requires 'era-block'.");
+					let era = Era::mortal(period, block);
+					if era.birth(block) != block {
+						panic!("Block {} cannot start an era of period {}; use block {} instead.", block, period, era.birth(block));
+					}
+					let checkpoint = matches.value_of("era-hash")
+						.and_then(parse_hash)
+						.expect("A mortal era requires the hash of 'era-block' as 'era-hash'.");
+					(era, checkpoint)
+				}
+				None => (Era::immortal(), genesis_hash(matches)),
+			};
+			println!("Using a checkpoint hash of {}", HexDisplay::from(&checkpoint.as_ref()));
+
+			let extrinsic = sign_extrinsic(&signer, index, function, era, checkpoint);
 			println!("0x{}", hex::encode(&extrinsic.encode()));
 		}
+		("boxkeys", Some(matches)) => {
+			let read_bundle = |name| {
+				let path = matches.subcommand().1
+					.and_then(|m| m.value_of(name))
+					.expect("parameter is required; thus it can't be None; qed");
+				let json = fs::read(path).unwrap_or_else(|e| panic!("Cannot read bundle {}: {}", path, e));
+				let json = serde_json::from_slice(&json).expect("Bundle file is not valid JSON");
+				boxkeys::Bundle::from_json(&json).unwrap_or_else(|e| panic!("{}", e))
+			};
+			match matches.subcommand() {
+				("generate", Some(_)) => {
+					let scheme = if is_ed25519 { boxkeys::Scheme::Ed25519 } else { boxkeys::Scheme::Sr25519 };
+					let bundle = boxkeys::Bundle::generate(scheme);
+					println!("{}", serde_json::to_string_pretty(&bundle.to_json()).expect("JSON values serialize; qed"));
+				}
+				("register", Some(register)) => {
+					let bundle = read_bundle("bundle");
+					let current = register.value_of("current").map(|_| read_bundle("current"));
+					let user = register.value_of("user")
+						.expect("parameter is required; thus it can't be None; qed");
+					let payload = boxkeys::register_payload(&bundle, boxkeys::user_hash(user), current.as_ref());
+					println!("{}", payload);
+				}
+				("verify", Some(verify)) => {
+					let bundle = read_bundle("bundle");
+					let user = verify.value_of("user")
+						.expect("parameter is required; thus it can't be None; qed");
+					let challenge = verify.value_of("challenge")
+						.expect("parameter is required; thus it can't be None; qed");
+					let challenge = hex::decode(challenge.trim_start_matches("0x"))
+						.expect("Invalid 'challenge' parameter; expecting hex.");
+					let payload = boxkeys::verification_payload(&bundle, boxkeys::user_hash(user), &challenge)
+						.unwrap_or_else(|e| panic!("{}", e));
+					println!("{}", payload);
+				}
+				("challenge", Some(challenge)) => {
+					let user = challenge.value_of("user")
+						.expect("parameter is required; thus it can't be None; qed");
+					let enc_key = challenge.value_of("enc-key")
+						.and_then(parse_hash)
+						.expect("Invalid 'enc-key' parameter; expecting 32 bytes hex.");
+					let payload = boxkeys::challenge_payload(boxkeys::user_hash(user), enc_key);
+					println!("{}", payload);
+				}
+				_ => print_usage(matches),
+			}
+		}
 		("verify", Some(matches)) => {
 			let sig_data = matches.value_of("sig")
 				.expect("signature parameter is required; thus it can't be None; qed");
@@ -255,6 +330,44 @@ fn execute<C: Crypto<Seed=[u8; 32]>>(matches: clap::ArgMatches) where
 	}
 }
 
+/// The genesis hash given by `--genesis`, either in hex or as a recognised chain identifier.
+fn genesis_hash(matches: &clap::ArgMatches) -> Hash {
+	match matches.value_of("genesis").unwrap_or("alex") {
+		"elm" => hex!["10c08714a10c7da78f40a60f6f732cf0dba97acfb5e2035445b032386157d5c3"].into(),
+		"alex" => hex!["dcd1346701ca8396496e52aa2785b1748deb6db09551b72159dcb3e08991025b"].into(),
+		h => parse_hash(h).expect("Invalid genesis hash or unrecognised chain identifier"),
+	}
+}
+
+fn parse_hash(h: &str) -> Option<Hash> {
+	hex::decode(h.trim_start_matches("0x")).ok().and_then(|x| Decode::decode(&mut &x[..]))
+}
+
+/// Sign `function` as the `index`-th transaction of `signer`, valid for `era` starting at the
+/// block with hash `checkpoint` (the genesis hash for immortal transactions).
+fn sign_extrinsic(
+	signer: &sr25519::Pair,
+	index: Index,
+	function: Call,
+	era: Era,
+	checkpoint: Hash,
+) -> UncheckedExtrinsic {
+	let raw_payload = (Compact(index), function, era, checkpoint);
+	let signature = raw_payload.using_encoded(|payload| if payload.len() > 256 {
+		signer.sign(&blake2_256(payload)[..])
+	} else {
+		println!("Signing {}", HexDisplay::from(&payload));
+		signer.sign(payload)
+	});
+	UncheckedExtrinsic::new_signed(
+		index,
+		raw_payload.1,
+		signer.public().into(),
+		signature.into(),
+		era,
+	)
+}
+
 fn main() {
 	let yaml = load_yaml!("cli.yml");
 	let matches = clap::App::from_yaml(yaml)